@@ -0,0 +1,89 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use yape::{Pixel, PixelMode, Sprite, YapeEngine, YapeEngineApi};
+
+const SIZES: [(u32, u32); 2] = [(160, 120), (640, 480)];
+
+fn random_pixels(count: usize) -> Vec<Pixel> {
+    let mut rng = StdRng::seed_from_u64(42);
+    (0..count).map(|_| Pixel::from_rgb(rng.gen_range(0, 16777215))).collect()
+}
+
+// Full screen per pixel fill, same as examples/random.rs without the rng cost
+fn bench_random_fill(c: &mut Criterion) {
+    let mut group = c.benchmark_group("random_fill");
+    for (w, h) in SIZES.iter() {
+        let mut engine = YapeEngine::construct_headless(*w, *h);
+        let pixels = random_pixels((w * h) as usize);
+        group.throughput(Throughput::Elements((w * h) as u64));
+        group.bench_function(format!("{}x{}", w, h), |b| b.iter(|| {
+            engine.clear(&yape::WHITE);
+            for y in 0..*h {
+                for x in 0..*w {
+                    engine.draw_pixel(x, y, &pixels[(y * w + x) as usize]);
+                }
+            }
+        }));
+    }
+    group.finish();
+}
+
+fn bench_present(c: &mut Criterion) {
+    let mut group = c.benchmark_group("present");
+    for (w, h) in SIZES.iter() {
+        let mut sprite = Sprite::new(*w, *h);
+        sprite.data = random_pixels((w * h) as usize);
+        let mut rgb_buffer: Vec<u32> = vec![0; sprite.data.len()];
+        group.throughput(Throughput::Elements((w * h) as u64));
+        // Indexed conversion the frame loop used before write_rgb
+        group.bench_function(format!("indexed_{}x{}", w, h), |b| b.iter(|| {
+            for (i, p) in sprite.data.iter().enumerate() {
+                rgb_buffer[i] = p.to_rgb();
+            }
+            black_box(&rgb_buffer);
+        }));
+        group.bench_function(format!("write_rgb_{}x{}", w, h), |b| b.iter(|| {
+            sprite.write_rgb(&mut rgb_buffer);
+            black_box(&rgb_buffer);
+        }));
+    }
+    group.finish();
+}
+
+fn bench_fills(c: &mut Criterion) {
+    let (w, h) = (640, 480);
+    let mut group = c.benchmark_group("fill");
+    group.throughput(Throughput::Elements((w * h) as u64));
+    for (name, mode) in [("normal", PixelMode::Normal), ("alpha", PixelMode::Alpha)].iter() {
+        let mut engine = YapeEngine::construct_headless(w, h);
+        let pixel = Pixel::rgba(200, 100, 50, 128);
+        engine.set_pixel_mode(*mode);
+        // Per pixel loop fill_rect used before span fills
+        group.bench_function(format!("rect_per_pixel_{}", name), |b| b.iter(|| {
+            for i in 0..w {
+                for j in 0..h {
+                    engine.draw_pixel(i, j, &pixel);
+                }
+            }
+        }));
+        group.bench_function(format!("rect_{}", name), |b| b.iter(|| {
+            engine.fill_rect(0, 0, w, h, &pixel);
+        }));
+        group.bench_function(format!("circle_{}", name), |b| b.iter(|| {
+            engine.fill_circle(w / 2, h / 2, h / 2, &pixel);
+        }));
+        group.bench_function(format!("triangle_{}", name), |b| b.iter(|| {
+            engine.fill_triangle(0, 0, w - 1, h / 2, w / 3, h - 1, &pixel);
+        }));
+    }
+    let mut engine = YapeEngine::construct_headless(w, h);
+    group.bench_function("clear", |b| b.iter(|| {
+        engine.clear(&yape::DARK_BLUE);
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, bench_random_fill, bench_present, bench_fills);
+criterion_main!(benches);
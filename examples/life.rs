@@ -1,4 +1,3 @@
-use rand;
 use yape::{YapeCallback, YapeEngine, YapeEngineApi, YapeResult};
 
 struct Life {
@@ -44,14 +43,14 @@ impl YapeCallback for Life {
                     for n_col in -1..2 {
                         let _c = (col + n_col + cols) % cols;
                         let _r = (row + n_row + rows) % rows;
-                        let n = (_r * cols as i32 + _c) as usize;
+                        let n = (_r * cols + _c) as usize;
                         n_sum += self.grid[n] as i32;
                     }
                 }
 
                 if !state && n_sum == 3 {
                     self.output[i] = 1;
-                } else if state && (n_sum < 2 || n_sum > 3) {
+                } else if state && !(2..=3).contains(&n_sum) {
                     self.output[i] = 0;
                 } else {
                     self.output[i] = self.grid[i];
@@ -1,10 +1,8 @@
-use yape::{YapeCallback, YapeEngine, YapeEngineApi, YapeResult, MouseButton};
+use yape::{YapeCallback, YapeEngine, YapeEngineApi, YapeResult, MouseButton, Vi2d};
 
 struct Lines {
-    x: u32,
-    y: u32,
-    clicked: bool,
-    lines: Vec<(u32, u32, u32, u32)>
+    start: Option<Vi2d>,
+    lines: Vec<(Vi2d, Vi2d)>
 }
 
 impl YapeCallback for Lines {
@@ -17,24 +15,18 @@ impl YapeCallback for Lines {
         engine.clear(&yape::WHITE);
 
         if engine.get_mouse_button_state(&MouseButton::Left).released {
-            if self.clicked {
-                let x = engine.get_mouse_x();
-                let y = engine.get_mouse_y();
-                self.lines.push((self.x, self.y, x, y));
-                self.clicked = false;
-            } else {
-                self.x = engine.get_mouse_x();
-                self.y = engine.get_mouse_y();
-                self.clicked = true;
+            match self.start.take() {
+                Some(start) => self.lines.push((start, engine.get_mouse_pos())),
+                None => self.start = Some(engine.get_mouse_pos()),
             }
         }
 
-        if self.clicked {
-            engine.draw_line(self.x, self.y, engine.get_mouse_x(), engine.get_mouse_y(), &yape::BLACK);
+        if let Some(start) = self.start {
+            engine.draw_line_v(start, engine.get_mouse_pos(), &yape::BLACK);
         }
 
-        for (x1, y1, x2, y2) in self.lines.as_slice() {
-            engine.draw_line(*x1, *y1, *x2, *y2, &yape::BLACK);
+        for (p1, p2) in self.lines.iter() {
+            engine.draw_line_v(*p1, *p2, &yape::BLACK);
         }
 
         Ok(true)
@@ -42,7 +34,7 @@ impl YapeCallback for Lines {
 }
 
 fn main() -> YapeResult<()>  {
-    let mut example = Lines { x: 0, y: 0, clicked: false, lines: Vec::new() };
+    let mut example = Lines { start: None, lines: Vec::new() };
     let mut engine = YapeEngine::construct("Lines", 320, 240, 4, 4)?;
     engine.start(&mut example)
 }
@@ -0,0 +1,54 @@
+use std::sync::{Arc, Mutex};
+
+use yape::synth::{self, Envelope, Synth, Voice, VoiceId, Waveform};
+use yape::{KeyboardKey, YapeCallback, YapeEngine, YapeEngineApi, YapeResult};
+
+const KEYS: [KeyboardKey; 8] = [
+    KeyboardKey::Z, KeyboardKey::X, KeyboardKey::C, KeyboardKey::V,
+    KeyboardKey::B, KeyboardKey::N, KeyboardKey::M, KeyboardKey::Space,
+];
+const NOTES: [i32; 8] = [60, 62, 64, 65, 67, 69, 71, 72];
+
+struct Piano {
+    synth: Arc<Mutex<Synth>>,
+    playing: Vec<Option<VoiceId>>,
+}
+
+impl YapeCallback for Piano {
+    fn on_create(&mut self) -> YapeResult<bool> {
+        Ok(true)
+    }
+
+    fn on_update(&mut self, engine: &mut dyn YapeEngineApi, _time_elapsed: f32) -> YapeResult<bool> {
+        engine.clear(&yape::BLACK);
+        let mut synth = self.synth.lock().unwrap();
+        for (i, key) in KEYS.iter().enumerate() {
+            let state = engine.get_key_state(key);
+            if state.pressed {
+                let voice = Voice::new(Waveform::Square, synth::note_frequency(NOTES[i]))
+                    .with_oscillator(synth::Oscillator::new(Waveform::Triangle, synth::note_frequency(NOTES[i] - 12)))
+                    .with_envelope(Envelope::new(0.01, 0.1, 0.6, 0.3))
+                    .with_volume(0.2);
+                self.playing[i] = Some(synth.play(voice));
+            }
+            if state.released {
+                if let Some(id) = self.playing[i].take() {
+                    synth.release(id);
+                }
+            }
+            if state.held {
+                engine.fill_rect(i as u32 * 20 + 2, 10, 16, 40, &yape::WHITE);
+            } else {
+                engine.draw_rect(i as u32 * 20 + 2, 10, 16, 40, &yape::WHITE);
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn main() -> YapeResult<()> {
+    let mut engine = YapeEngine::construct("Synth", 160, 60, 4, 4)?;
+    let synth = engine.enable_sound()?;
+    let mut piano = Piano { synth, playing: vec![None; KEYS.len()] };
+    engine.start(&mut piano)
+}
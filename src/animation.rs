@@ -0,0 +1,216 @@
+// Sprite sheet animations, frames are areas of one sheet advanced with on_update's time_elapsed
+use crate::{Recti, Sprite, Vi2d, YapeEngineApi};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayMode {
+    Loop,
+    // Forward then backward without repeating the end frames, 0 1 2 1 0 1 2...
+    PingPong,
+    // Stops on the last frame
+    Once,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    // Area of the sheet
+    pub source: Recti,
+    // Where source is drawn relative to the animation position, for trimmed frames
+    pub offset: Vi2d,
+    // Seconds
+    pub duration: f32,
+    // Reported by update every time the frame is entered, including the first frame when playback
+    // starts, restarts or jumps to it with set_frame
+    pub event: Option<String>,
+}
+
+impl Frame {
+    pub fn new(source: Recti, duration: f32) -> Frame {
+        Frame { source, offset: Vi2d::new(0, 0), duration, event: None }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameEvent {
+    pub frame: usize,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    pub name: String,
+    pub mode: PlayMode,
+    pub speed: f32,
+    frames: Vec<Frame>,
+    current: usize,
+    // Time spent on the current frame
+    time: f32,
+    backwards: bool,
+    finished: bool,
+    // The current frame's event has been reported
+    entered: bool,
+}
+
+impl Animation {
+    pub fn new(name: &str, mode: PlayMode) -> Animation {
+        Animation {
+            name: name.to_string(),
+            mode,
+            speed: 1.0,
+            frames: Vec::new(),
+            current: 0,
+            time: 0.0,
+            backwards: false,
+            finished: false,
+            entered: false,
+        }
+    }
+
+    // count frames of a grid of equal cells, read left to right and top to bottom from first
+    pub fn from_grid(name: &str, mode: PlayMode, cell: Vi2d, columns: u32, first: u32, count: u32, duration: f32) -> Animation {
+        let columns = columns.max(1);
+        (first..first + count).fold(Animation::new(name, mode), |animation, i| {
+            let pos = Vi2d::new((i % columns) as i32 * cell.x, (i / columns) as i32 * cell.y);
+            animation.with_frame(Recti::from_pos_size(pos, cell), duration)
+        })
+    }
+
+    pub fn with_frame(mut self, source: Recti, duration: f32) -> Animation {
+        self.frames.push(Frame::new(source, duration));
+        self
+    }
+
+    pub fn with_event(mut self, frame: usize, name: &str) -> Animation {
+        self.set_event(frame, Some(name));
+        self
+    }
+
+    pub fn push_frame(&mut self, frame: Frame) {
+        self.frames.push(frame);
+    }
+
+    pub fn set_event(&mut self, frame: usize, name: Option<&str>) {
+        if let Some(frame) = self.frames.get_mut(frame) {
+            frame.event = name.map(str::to_string);
+        }
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    // Length of one pass through the frames in seconds
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
+
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    pub fn current_frame(&self) -> Option<&Frame> {
+        self.frames.get(self.current)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn restart(&mut self) {
+        self.current = 0;
+        self.time = 0.0;
+        self.backwards = false;
+        self.finished = false;
+        self.entered = false;
+    }
+
+    pub fn set_frame(&mut self, frame: usize) {
+        if frame < self.frames.len() {
+            self.current = frame;
+            self.time = 0.0;
+            self.finished = false;
+            self.entered = false;
+        }
+    }
+
+    // Advances by time_elapsed seconds scaled by speed, returns the events of every frame entered
+    // on the way, so frames shorter than a game frame still report theirs. A long hitch in a
+    // looping animation skips whole cycles without reporting their events
+    pub fn update(&mut self, time_elapsed: f32) -> Vec<FrameEvent> {
+        let mut events = Vec::new();
+        if self.frames.is_empty() || self.finished {
+            return events;
+        }
+        if !self.entered {
+            self.entered = true;
+            if let Some(name) = &self.frames[self.current].event {
+                events.push(FrameEvent { frame: self.current, name: name.clone() });
+            }
+        }
+        self.time += time_elapsed * self.speed.max(0.0);
+        // Frames repeat every cycle, so anything past the next cycle can be dropped without
+        // changing where playback ends up
+        let cycle = self.cycle();
+        if cycle > 0.0 && self.time >= 2.0 * cycle {
+            self.time = cycle + self.time % cycle;
+        }
+        // Bounded so frames without duration can't spin forever, two ping pong cycles are the
+        // most steps a single update takes
+        for _ in 0..self.frames.len() * 4 + 1 {
+            let duration = self.frames[self.current].duration;
+            if self.time < duration || self.finished {
+                break;
+            }
+            self.time -= duration;
+            self.advance();
+            // Finishing stays on the last frame without entering it again
+            if self.finished {
+                break;
+            }
+            if let Some(name) = &self.frames[self.current].event {
+                events.push(FrameEvent { frame: self.current, name: name.clone() });
+            }
+        }
+        events
+    }
+
+    // Time after which playback is back on the same frame going the same way, Once never is
+    fn cycle(&self) -> f32 {
+        let last = self.frames.len() - 1;
+        match self.mode {
+            PlayMode::Loop => self.duration(),
+            PlayMode::PingPong if last > 0 => 2.0 * self.duration() - self.frames[0].duration - self.frames[last].duration,
+            PlayMode::PingPong => self.duration(),
+            PlayMode::Once => 0.0,
+        }
+    }
+
+    fn advance(&mut self) {
+        let last = self.frames.len() - 1;
+        match self.mode {
+            PlayMode::Loop => self.current = if self.current == last { 0 } else { self.current + 1 },
+            PlayMode::Once => {
+                if self.current == last {
+                    self.finished = true;
+                    self.time = 0.0;
+                } else {
+                    self.current += 1;
+                }
+            }
+            PlayMode::PingPong => {
+                if last == 0 {
+                    return;
+                }
+                if self.backwards && self.current == 0 || !self.backwards && self.current == last {
+                    self.backwards = !self.backwards;
+                }
+                self.current = if self.backwards { self.current - 1 } else { self.current + 1 };
+            }
+        }
+    }
+
+    pub fn draw<E: YapeEngineApi + ?Sized>(&self, engine: &mut E, sheet: &Sprite, pos: Vi2d, scale: u32) {
+        if let Some(frame) = self.current_frame() {
+            engine.draw_partial_sprite(pos + frame.offset * scale.max(1) as i32, sheet, &frame.source, scale);
+        }
+    }
+}
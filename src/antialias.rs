@@ -0,0 +1,172 @@
+// Anti-aliased primitives, coverage is blended into the target through the Alpha pixel mode
+use std::collections::HashMap;
+
+use crate::raster;
+use crate::{Pixel, PixelMode, Vf2d, Vi2d, YapeEngineApi};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineCap {
+    // Ends exactly at the end points
+    Butt,
+    // Extends half the width past the end points
+    Square,
+    Round,
+}
+
+// Runs draw with Normal mode switched to Alpha so coverage blends, other modes already use
+// the alpha channel and are kept. The previous mode is restored afterwards
+fn with_alpha_mode<E, F>(engine: &mut E, draw: F)
+where
+    E: YapeEngineApi + ?Sized,
+    F: FnOnce(&mut E),
+{
+    let mode = *engine.get_pixel_mode();
+    if mode == PixelMode::Normal {
+        engine.set_pixel_mode(PixelMode::Alpha);
+    }
+    draw(engine);
+    engine.set_pixel_mode(mode);
+}
+
+fn plot_coverage<E: YapeEngineApi + ?Sized>(engine: &mut E, x: i32, y: i32, pixel: &Pixel, coverage: f32) {
+    let coverage = coverage.clamp(0.0, 1.0);
+    if coverage > 0.0 {
+        let alpha = (pixel.alpha as f32 * coverage).round() as u8;
+        raster::plot(engine, x, y, &pixel.with_alpha(alpha));
+    }
+}
+
+// Xiaolin Wu's line algorithm, integer coordinates are pixel centers
+// https://en.wikipedia.org/wiki/Xiaolin_Wu%27s_line_algorithm
+pub(crate) fn line<E: YapeEngineApi + ?Sized>(engine: &mut E, p1: Vf2d, p2: Vf2d, pixel: &Pixel) {
+    let (mut x0, mut y0, mut x1, mut y1) = (p1.x, p1.y, p2.x, p2.y);
+    let steep = (y1 - y0).abs() > (x1 - x0).abs();
+    if steep {
+        std::mem::swap(&mut x0, &mut y0);
+        std::mem::swap(&mut x1, &mut y1);
+    }
+    if x0 > x1 {
+        std::mem::swap(&mut x0, &mut x1);
+        std::mem::swap(&mut y0, &mut y1);
+    }
+    let dx = x1 - x0;
+    let gradient = if dx == 0.0 { 1.0 } else { (y1 - y0) / dx };
+
+    let fpart = |v: f32| v - v.floor();
+    let rfpart = |v: f32| 1.0 - fpart(v);
+
+    with_alpha_mode(engine, |engine| {
+        let mut plot = |x: i32, y: i32, coverage: f32| {
+            if steep {
+                plot_coverage(engine, y, x, pixel, coverage);
+            } else {
+                plot_coverage(engine, x, y, pixel, coverage);
+            }
+        };
+
+        // First end point
+        let x_end = x0.round();
+        let y_end = y0 + gradient * (x_end - x0);
+        let x_gap = rfpart(x0 + 0.5);
+        let x_start = x_end as i32;
+        plot(x_start, y_end.floor() as i32, rfpart(y_end) * x_gap);
+        plot(x_start, y_end.floor() as i32 + 1, fpart(y_end) * x_gap);
+        let mut inter_y = y_end + gradient;
+
+        // Second end point
+        let x_end = x1.round();
+        let y_end = y1 + gradient * (x_end - x1);
+        let x_gap = fpart(x1 + 0.5);
+        let x_stop = x_end as i32;
+        if x_stop != x_start {
+            plot(x_stop, y_end.floor() as i32, rfpart(y_end) * x_gap);
+            plot(x_stop, y_end.floor() as i32 + 1, fpart(y_end) * x_gap);
+        }
+
+        for x in x_start + 1..x_stop {
+            plot(x, inter_y.floor() as i32, rfpart(inter_y));
+            plot(x, inter_y.floor() as i32 + 1, fpart(inter_y));
+            inter_y += gradient;
+        }
+    });
+}
+
+// Wu's circle: every column of an octant splits its coverage between the two pixels
+// around the exact radius, octants are mirrored and overlapping pixels keep the strongest coverage
+pub(crate) fn circle<E: YapeEngineApi + ?Sized>(engine: &mut E, center: Vi2d, radius: f32, pixel: &Pixel) {
+    if radius <= 0.0 {
+        return;
+    }
+    let mut coverage: HashMap<(i32, i32), f32> = HashMap::new();
+    let mut add = |x: i32, y: i32, c: f32| {
+        for &(px, py) in [(x, y), (-x, y), (x, -y), (-x, -y), (y, x), (-y, x), (y, -x), (-y, -x)].iter() {
+            let entry = coverage.entry((center.x + px, center.y + py)).or_insert(0.0);
+            *entry = entry.max(c);
+        }
+    };
+
+    let end = (radius / std::f32::consts::SQRT_2).ceil() as i32;
+    for x in 0..=end {
+        let y = (radius * radius - (x * x) as f32).max(0.0).sqrt();
+        let yi = y.floor();
+        let f = y - yi;
+        add(x, yi as i32, 1.0 - f);
+        add(x, yi as i32 + 1, f);
+    }
+
+    with_alpha_mode(engine, |engine| {
+        for ((x, y), c) in coverage {
+            plot_coverage(engine, x, y, pixel, c);
+        }
+    });
+}
+
+// Coverage from the signed distance of each pixel center to the stroke outline,
+// which gives anti-aliased edges for any width
+pub(crate) fn thick_line<E: YapeEngineApi + ?Sized>(
+    engine: &mut E,
+    p1: Vf2d,
+    p2: Vf2d,
+    width: f32,
+    cap: LineCap,
+    pixel: &Pixel,
+) {
+    let half = width.max(0.0) / 2.0;
+    let delta = p2 - p1;
+    let length = delta.length();
+    let dir = if length > 0.0 { delta / length } else { Vf2d::new(1.0, 0.0) };
+    let center = p1.lerp(p2, 0.5);
+    let extension = match cap {
+        LineCap::Butt => 0.0,
+        LineCap::Square | LineCap::Round => half,
+    };
+
+    let min = p1.min(p2) - Vf2d::new(half + 1.0, half + 1.0);
+    let max = p1.max(p2) + Vf2d::new(half + 1.0, half + 1.0);
+    let (x1, y1) = ((min.x.floor() as i32).max(0), (min.y.floor() as i32).max(0));
+    let x2 = (max.x.ceil() as i32).min(engine.get_screen_width() as i32 - 1);
+    let y2 = (max.y.ceil() as i32).min(engine.get_screen_height() as i32 - 1);
+
+    with_alpha_mode(engine, |engine| {
+        for y in y1..=y2 {
+            for x in x1..=x2 {
+                let p = Vf2d::new(x as f32, y as f32);
+                let distance = match cap {
+                    LineCap::Round => {
+                        let t = if length > 0.0 { ((p - p1).dot(dir) / length).clamp(0.0, 1.0) } else { 0.0 };
+                        (p - (p1 + delta * t)).length() - half
+                    }
+                    _ => {
+                        // Signed distance to the oriented box around the segment
+                        let local = p - center;
+                        let qx = local.dot(dir).abs() - (length / 2.0 + extension);
+                        let qy = local.cross(dir).abs() - half;
+                        let outside = Vf2d::new(qx.max(0.0), qy.max(0.0)).length();
+                        outside + qx.max(qy).min(0.0)
+                    }
+                };
+                plot_coverage(engine, x, y, pixel, 0.5 - distance);
+            }
+        }
+    });
+}
@@ -0,0 +1,171 @@
+// Aseprite sprite sheet JSON import, both the hash and the array frame layouts
+// https://www.aseprite.org/docs/sprite-sheet/#json-data
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::animation::{Animation, Frame, PlayMode};
+use crate::{Recti, Sprite, Vi2d, YapeError, YapeErrorKind, YapeResult};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SliceKey {
+    // Slice keys apply from this frame until the next key
+    pub frame: usize,
+    pub bounds: Recti,
+    // Inner area of 9-slices
+    pub center: Option<Recti>,
+    pub pivot: Option<Vi2d>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slice {
+    pub name: String,
+    // User data text
+    pub data: String,
+    pub keys: Vec<SliceKey>,
+}
+
+impl Slice {
+    pub fn key_at(&self, frame: usize) -> Option<&SliceKey> {
+        self.keys.iter().filter(|k| k.frame <= frame).max_by_key(|k| k.frame)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsepriteSheet {
+    // Sheet image, relative to the JSON file
+    pub image: PathBuf,
+    pub size: Vi2d,
+    pub frames: Vec<Frame>,
+    // One per frame tag
+    pub animations: Vec<Animation>,
+    pub slices: Vec<Slice>,
+}
+
+impl AsepriteSheet {
+    // Copy of the tag's animation, ready to play
+    pub fn animation(&self, tag: &str) -> Option<Animation> {
+        self.animations.iter().find(|a| a.name == tag).cloned()
+    }
+
+    // Every frame in sheet order, for exports without tags
+    pub fn all_frames(&self, mode: PlayMode) -> Animation {
+        let mut animation = Animation::new("", mode);
+        for frame in self.frames.iter() {
+            animation.push_frame(frame.clone());
+        }
+        animation
+    }
+
+    pub fn slice(&self, name: &str) -> Option<&Slice> {
+        self.slices.iter().find(|s| s.name == name)
+    }
+
+    pub fn load_sprite(&self) -> YapeResult<Sprite> {
+        Sprite::load_png(&self.image)
+    }
+}
+
+fn decode_error(message: &str) -> YapeError {
+    YapeError::new(YapeErrorKind::Decode, message)
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> YapeResult<AsepriteSheet> {
+    let path = path.as_ref();
+    let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    parse(&fs::read_to_string(path)?, &base_dir)
+}
+
+pub fn parse(text: &str, base_dir: &Path) -> YapeResult<AsepriteSheet> {
+    let root: Value = serde_json::from_str(text)
+        .map_err(|e| YapeError::with_source(YapeErrorKind::Decode, e.to_string().as_str(), e))?;
+    let frames = match root.get("frames") {
+        Some(Value::Array(frames)) => frames.iter().map(frame).collect::<YapeResult<Vec<_>>>()?,
+        Some(Value::Object(frames)) => frames.values().map(frame).collect::<YapeResult<Vec<_>>>()?,
+        _ => return Err(decode_error("missing frames")),
+    };
+    let meta = root.get("meta").unwrap_or(&Value::Null);
+
+    let mut animations = Vec::new();
+    for tag in array(meta, "frameTags") {
+        let name = str_or(tag, "name");
+        let (from, to) = (int(tag, "from")? as usize, int(tag, "to")? as usize);
+        if from > to || to >= frames.len() {
+            return Err(decode_error(format!("frame tag {} is out of range", name).as_str()));
+        }
+        let direction = str_or(tag, "direction");
+        let mode = match direction {
+            "pingpong" | "pingpong_reverse" => PlayMode::PingPong,
+            _ => PlayMode::Loop,
+        };
+        let mut animation = Animation::new(name, mode);
+        let mut indices: Vec<usize> = (from..=to).collect();
+        if direction == "reverse" || direction == "pingpong_reverse" {
+            indices.reverse();
+        }
+        for i in indices {
+            animation.push_frame(frames[i].clone());
+        }
+        animations.push(animation);
+    }
+
+    let mut slices = Vec::new();
+    for slice in array(meta, "slices") {
+        let keys = array(slice, "keys")
+            .iter()
+            .map(|key| {
+                Ok(SliceKey {
+                    frame: int(key, "frame")? as usize,
+                    bounds: rect(key.get("bounds").unwrap_or(&Value::Null))?,
+                    center: key.get("center").map(rect).transpose()?,
+                    pivot: key.get("pivot").map(|p| Ok::<_, YapeError>(Vi2d::new(int(p, "x")?, int(p, "y")?))).transpose()?,
+                })
+            })
+            .collect::<YapeResult<_>>()?;
+        slices.push(Slice { name: str_or(slice, "name").to_string(), data: str_or(slice, "data").to_string(), keys });
+    }
+
+    let size = meta.get("size").unwrap_or(&Value::Null);
+    Ok(AsepriteSheet {
+        image: base_dir.join(str_or(meta, "image")),
+        size: Vi2d::new(int(size, "w").unwrap_or(0), int(size, "h").unwrap_or(0)),
+        frames,
+        animations,
+        slices,
+    })
+}
+
+fn int(value: &Value, key: &str) -> YapeResult<i32> {
+    value
+        .get(key)
+        .and_then(Value::as_i64)
+        .map(|v| v as i32)
+        .ok_or_else(|| decode_error(format!("missing or invalid {}", key).as_str()))
+}
+
+fn str_or<'a>(value: &'a Value, key: &str) -> &'a str {
+    value.get(key).and_then(Value::as_str).unwrap_or("")
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value.get(key).and_then(Value::as_array).map_or(&[], |a| a.as_slice())
+}
+
+fn rect(value: &Value) -> YapeResult<Recti> {
+    Ok(Recti::new(int(value, "x")?, int(value, "y")?, int(value, "w")?, int(value, "h")?))
+}
+
+// Trimmed frames keep their place inside the untrimmed frame through the offset
+fn frame(value: &Value) -> YapeResult<Frame> {
+    if value.get("rotated").and_then(Value::as_bool) == Some(true) {
+        return Err(decode_error("rotated frames are not supported"));
+    }
+    let source = rect(value.get("frame").unwrap_or(&Value::Null))?;
+    let offset = match value.get("spriteSourceSize") {
+        Some(trim) => Vi2d::new(int(trim, "x")?, int(trim, "y")?),
+        None => Vi2d::new(0, 0),
+    };
+    let duration = int(value, "duration").unwrap_or(100) as f32 / 1000.0;
+    Ok(Frame { source, offset, duration, event: None })
+}
@@ -0,0 +1,241 @@
+// Texture atlas packing, sprites are placed with a skyline bottom left packer
+// http://pds25.egloos.com/pds/201504/21/98/RectangleBinPack.pdf
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::{Recti, Sprite, Vi2d, YapeEngineApi, YapeError, YapeErrorKind, YapeResult};
+
+// Top edge of the packed area over [x, x + width)
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    x: i32,
+    y: i32,
+    width: i32,
+}
+
+pub struct SkylinePacker {
+    width: i32,
+    max_height: Option<i32>,
+    skyline: Vec<Segment>,
+}
+
+impl SkylinePacker {
+    pub fn new(width: u32, max_height: Option<u32>) -> SkylinePacker {
+        SkylinePacker {
+            width: width as i32,
+            max_height: max_height.map(|h| h as i32),
+            skyline: vec![Segment { x: 0, y: 0, width: width as i32 }],
+        }
+    }
+
+    // Height used so far
+    pub fn height(&self) -> u32 {
+        self.skyline.iter().map(|s| s.y).max().unwrap_or(0) as u32
+    }
+
+    // Lowest position the rect fits at, ties go to the leftmost, None when it doesn't fit
+    pub fn pack(&mut self, w: u32, h: u32) -> Option<Vi2d> {
+        let (w, h) = (w as i32, h as i32);
+        let mut best: Option<(usize, Vi2d)> = None;
+        for i in 0..self.skyline.len() {
+            let x = self.skyline[i].x;
+            if x + w > self.width {
+                break;
+            }
+            let mut y = 0;
+            let mut covered = 0;
+            for segment in self.skyline[i..].iter() {
+                if covered >= w {
+                    break;
+                }
+                y = y.max(segment.y);
+                covered += segment.width;
+            }
+            if self.max_height.is_some_and(|max| y + h > max) {
+                continue;
+            }
+            if best.is_none_or(|(_, b)| y < b.y) {
+                best = Some((i, Vi2d::new(x, y)));
+            }
+        }
+        let (index, pos) = best?;
+        self.place(index, pos, w, h);
+        Some(pos)
+    }
+
+    fn place(&mut self, index: usize, pos: Vi2d, w: i32, h: i32) {
+        self.skyline.insert(index, Segment { x: pos.x, y: pos.y + h, width: w });
+        let right = pos.x + w;
+        // Cut the segments now under the new one
+        let mut i = index + 1;
+        while i < self.skyline.len() && self.skyline[i].x < right {
+            let segment = &mut self.skyline[i];
+            let end = segment.x + segment.width;
+            if end <= right {
+                self.skyline.remove(i);
+            } else {
+                segment.width = end - right;
+                segment.x = right;
+                i += 1;
+            }
+        }
+        // Merge neighbours of the same height
+        let mut i = 0;
+        while i + 1 < self.skyline.len() {
+            if self.skyline[i].y == self.skyline[i + 1].y {
+                self.skyline[i].width += self.skyline[i + 1].width;
+                self.skyline.remove(i + 1);
+            } else {
+                i += 1;
+            }
+        }
+    }
+}
+
+pub struct AtlasBuilder<'a> {
+    width: u32,
+    max_height: Option<u32>,
+    padding: u32,
+    extrude: u32,
+    sprites: Vec<(String, &'a Sprite)>,
+}
+
+impl<'a> AtlasBuilder<'a> {
+    pub fn new(width: u32) -> AtlasBuilder<'a> {
+        AtlasBuilder { width, max_height: None, padding: 0, extrude: 0, sprites: Vec::new() }
+    }
+
+    pub fn with_max_height(mut self, max_height: u32) -> AtlasBuilder<'a> {
+        self.max_height = Some(max_height);
+        self
+    }
+
+    // Empty pixels between neighbouring sprites
+    pub fn with_padding(mut self, padding: u32) -> AtlasBuilder<'a> {
+        self.padding = padding;
+        self
+    }
+
+    // Edge pixels repeated around every sprite, keeps bilinear sampling from bleeding neighbours in
+    pub fn with_extrusion(mut self, extrude: u32) -> AtlasBuilder<'a> {
+        self.extrude = extrude;
+        self
+    }
+
+    pub fn add(mut self, name: &str, sprite: &'a Sprite) -> AtlasBuilder<'a> {
+        self.sprites.push((name.to_string(), sprite));
+        self
+    }
+
+    // Tallest sprites go first, None when they don't all fit
+    pub fn build(self) -> Option<Atlas> {
+        let border = self.extrude * 2 + self.padding;
+        let mut order: Vec<usize> = (0..self.sprites.len()).collect();
+        order.sort_by_key(|&i| {
+            let s = self.sprites[i].1;
+            (std::cmp::Reverse(s.height), std::cmp::Reverse(s.width))
+        });
+
+        let mut packer = SkylinePacker::new(self.width, self.max_height);
+        let mut placed = vec![Vi2d::new(0, 0); self.sprites.len()];
+        for &i in order.iter() {
+            let sprite = self.sprites[i].1;
+            placed[i] = packer.pack(sprite.width + border, sprite.height + border)?;
+        }
+
+        let mut sprite = Sprite::new(self.width, packer.height());
+        let mut regions = Vec::with_capacity(self.sprites.len());
+        let e = self.extrude as i32;
+        for (i, (name, source)) in self.sprites.iter().enumerate() {
+            let pos = placed[i] + Vi2d::new(e, e);
+            let (w, h) = (source.width as i32, source.height as i32);
+            regions.push((name.clone(), Recti::new(pos.x, pos.y, w, h)));
+            if w == 0 || h == 0 {
+                continue;
+            }
+            for y in -e..h + e {
+                for x in -e..w + e {
+                    let texel = source.get_pixel(x.clamp(0, w - 1) as u32, y.clamp(0, h - 1) as u32);
+                    sprite.set_pixel((pos.x + x) as u32, (pos.y + y) as u32, texel);
+                }
+            }
+        }
+        Some(Atlas::new(sprite, regions))
+    }
+}
+
+pub struct Atlas {
+    pub sprite: Sprite,
+    regions: Vec<(String, Recti)>,
+    index: HashMap<String, usize>,
+}
+
+impl Atlas {
+    pub fn new(sprite: Sprite, regions: Vec<(String, Recti)>) -> Atlas {
+        let index = regions.iter().enumerate().map(|(i, (name, _))| (name.clone(), i)).collect();
+        Atlas { sprite, regions, index }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Recti> {
+        self.index.get(name).map(|&i| self.regions[i].1)
+    }
+
+    // In the order the sprites were added
+    pub fn regions(&self) -> &[(String, Recti)] {
+        &self.regions
+    }
+
+    pub fn draw<E: YapeEngineApi + ?Sized>(&self, engine: &mut E, name: &str, pos: Vi2d, scale: u32) {
+        if let Some(source) = self.get(name) {
+            engine.draw_partial_sprite(pos, &self.sprite, &source, scale);
+        }
+    }
+
+    // Writes the atlas image and a JSON file with the regions next to it, the image is referenced
+    // by file name so both can be moved together
+    pub fn save<P: AsRef<Path>, Q: AsRef<Path>>(&self, image_path: P, meta_path: Q) -> YapeResult<()> {
+        let image_path = image_path.as_ref();
+        self.sprite.save_png(image_path)?;
+        let regions: serde_json::Map<String, Value> = self
+            .regions
+            .iter()
+            .map(|(name, r)| (name.clone(), json!({ "x": r.x, "y": r.y, "w": r.w, "h": r.h })))
+            .collect();
+        let meta = json!({
+            "image": image_path.file_name().map(|f| f.to_string_lossy().into_owned()).unwrap_or_default(),
+            "size": { "w": self.sprite.width, "h": self.sprite.height },
+            "regions": regions,
+        });
+        let text = serde_json::to_string_pretty(&meta)
+            .map_err(|e| YapeError::with_source(YapeErrorKind::Encode, e.to_string().as_str(), e))?;
+        fs::write(meta_path, text)?;
+        Ok(())
+    }
+
+    // Reads back what save wrote
+    pub fn load<P: AsRef<Path>>(meta_path: P) -> YapeResult<Atlas> {
+        let meta_path = meta_path.as_ref();
+        let decode_error = |message: &str| YapeError::new(YapeErrorKind::Decode, message);
+        let meta: Value = serde_json::from_str(&fs::read_to_string(meta_path)?)
+            .map_err(|e| YapeError::with_source(YapeErrorKind::Decode, e.to_string().as_str(), e))?;
+        let image = meta.get("image").and_then(Value::as_str).ok_or_else(|| decode_error("missing image"))?;
+        let image = meta_path.parent().unwrap_or_else(|| Path::new("")).join(image);
+        let sprite = Sprite::load_png(&image)?;
+
+        let mut regions = Vec::new();
+        if let Some(list) = meta.get("regions").and_then(Value::as_object) {
+            for (name, r) in list {
+                let field = |key: &str| {
+                    r.get(key).and_then(Value::as_i64).map(|v| v as i32).ok_or_else(|| {
+                        decode_error(format!("invalid region {}", name).as_str())
+                    })
+                };
+                regions.push((name.clone(), Recti::new(field("x")?, field("y")?, field("w")?, field("h")?)));
+            }
+        }
+        Ok(Atlas::new(sprite, regions))
+    }
+}
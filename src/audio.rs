@@ -0,0 +1,80 @@
+use std::sync::{Arc, Mutex};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+use crate::synth::Synth;
+use crate::{YapeError, YapeErrorKind, YapeResult};
+
+pub(crate) struct AudioOutput {
+    _stream: cpal::Stream,
+    // Last error the stream reported from the audio thread
+    error: Arc<Mutex<Option<YapeError>>>,
+}
+
+fn audio_error<E: std::error::Error + Send + Sync + 'static>(error: E) -> YapeError {
+    YapeError::with_source(YapeErrorKind::Audio, error.to_string().as_str(), error)
+}
+
+impl AudioOutput {
+    pub(crate) fn open() -> YapeResult<(AudioOutput, Arc<Mutex<Synth>>)> {
+        let host = cpal::default_host();
+        let device = host
+            .default_output_device()
+            .ok_or_else(|| YapeError::new(YapeErrorKind::Audio, "no output device available"))?;
+        let supported = device.default_output_config().map_err(audio_error)?;
+        let config: cpal::StreamConfig = supported.config();
+        let synth = Arc::new(Mutex::new(Synth::new(config.sample_rate.0)));
+        let error = Arc::new(Mutex::new(None));
+
+        let stream = match supported.sample_format() {
+            cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config, synth.clone(), error.clone()),
+            cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config, synth.clone(), error.clone()),
+            cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config, synth.clone(), error.clone()),
+            format => return Err(YapeError::new(YapeErrorKind::Audio, format!("unsupported sample format {}", format).as_str())),
+        }
+        .map_err(audio_error)?;
+        stream.play().map_err(audio_error)?;
+
+        Ok((AudioOutput { _stream: stream, error }, synth))
+    }
+
+    pub(crate) fn take_error(&self) -> Option<YapeError> {
+        self.error.lock().ok().and_then(|mut error| error.take())
+    }
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    synth: Arc<Mutex<Synth>>,
+    error: Arc<Mutex<Option<YapeError>>>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: cpal::SizedSample + cpal::FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| match synth.lock() {
+            Ok(mut synth) => {
+                for frame in data.chunks_mut(channels) {
+                    let value = T::from_sample(synth.next_sample());
+                    for sample in frame.iter_mut() {
+                        *sample = value;
+                    }
+                }
+            }
+            Err(_) => {
+                for sample in data.iter_mut() {
+                    *sample = T::EQUILIBRIUM;
+                }
+            }
+        },
+        move |stream_error| {
+            if let Ok(mut error) = error.lock() {
+                *error = Some(audio_error(stream_error));
+            }
+        },
+        None,
+    )
+}
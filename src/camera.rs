@@ -0,0 +1,127 @@
+// 2D camera for world space drawing, the default camera maps world units 1:1 onto screen cells.
+// Games keep their cameras and pass one to the _w drawing methods
+use crate::polygon::{self, FillRule};
+use crate::transform::{self, Sampling, Transform2D};
+use crate::{Pixel, Rectf, Sprite, Vf2d, Vi2d, YapeEngineApi};
+
+const MIN_ZOOM: f32 = 1.0e-3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera2D {
+    // World point shown at origin
+    pub position: Vf2d,
+    // Screen point the camera looks through, the screen center for a centered view
+    pub origin: Vf2d,
+    // Screen cells per world unit
+    pub zoom: f32,
+    // Radians, rotating the camera clockwise turns the world counter clockwise on screen
+    pub rotation: f32,
+}
+
+impl Default for Camera2D {
+    fn default() -> Camera2D {
+        Camera2D { position: Vf2d::new(0.0, 0.0), origin: Vf2d::new(0.0, 0.0), zoom: 1.0, rotation: 0.0 }
+    }
+}
+
+impl Camera2D {
+    // Camera with position at the center of a screen of the given size
+    pub fn centered(position: Vf2d, screen_size: Vi2d) -> Camera2D {
+        Camera2D { position, origin: screen_size.to_f32() / 2.0, ..Camera2D::default() }
+    }
+
+    pub fn transform(&self) -> Transform2D {
+        Transform2D::new()
+            .translate(-self.position.x, -self.position.y)
+            .rotate(-self.rotation)
+            .scale(self.zoom, self.zoom)
+            .translate(self.origin.x, self.origin.y)
+    }
+
+    pub fn world_to_screen(&self, world: Vf2d) -> Vf2d {
+        (world - self.position).rotate(-self.rotation) * self.zoom + self.origin
+    }
+
+    pub fn screen_to_world(&self, screen: Vf2d) -> Vf2d {
+        ((screen - self.origin) / self.zoom).rotate(self.rotation) + self.position
+    }
+
+    // Moves the view by a distance in screen cells, as when dragging the map with the mouse
+    pub fn pan(&mut self, screen_delta: Vf2d) {
+        self.position -= (screen_delta / self.zoom).rotate(self.rotation);
+    }
+
+    // Multiplies the zoom while keeping the world point under the screen point in place
+    pub fn zoom_at(&mut self, screen: Vf2d, factor: f32) {
+        let before = self.screen_to_world(screen);
+        self.zoom = (self.zoom * factor).max(MIN_ZOOM);
+        self.position += before - self.screen_to_world(screen);
+    }
+
+    // World point under the mouse
+    pub fn mouse_to_world(&self, engine: &dyn YapeEngineApi) -> Vf2d {
+        self.screen_to_world(engine.get_mouse_pos().to_f32())
+    }
+
+    // Zooms around the mouse by factor per wheel step, returns true when the wheel moved
+    pub fn zoom_to_cursor(&mut self, engine: &dyn YapeEngineApi, factor: f32) -> bool {
+        let steps = engine.get_mouse_wheel();
+        if steps == 0 {
+            return false;
+        }
+        self.zoom_at(engine.get_mouse_pos().to_f32(), factor.powi(steps));
+        true
+    }
+
+    // World space rect that covers a screen of the given size
+    pub fn visible_area(&self, screen_size: Vi2d) -> Rectf {
+        let size = screen_size.to_f32();
+        let corners = [
+            self.screen_to_world(Vf2d::new(0.0, 0.0)),
+            self.screen_to_world(Vf2d::new(size.x, 0.0)),
+            self.screen_to_world(Vf2d::new(0.0, size.y)),
+            self.screen_to_world(size),
+        ];
+        let min = corners.iter().fold(corners[0], |a, c| a.min(*c));
+        let max = corners.iter().fold(corners[0], |a, c| a.max(*c));
+        Rectf::from_corners(min, max)
+    }
+}
+
+pub(crate) fn to_pixel(screen: Vf2d) -> Vi2d {
+    screen.floor().to_i32()
+}
+
+// World polygons go through the float scanline fill so rotated and zoomed shapes keep their edges
+pub(crate) fn fill_polygon<E: YapeEngineApi + ?Sized>(
+    engine: &mut E,
+    camera: &Camera2D,
+    points: &[Vf2d],
+    rule: FillRule,
+    pixel: &Pixel,
+) {
+    let points: Vec<Vf2d> = points.iter().map(|p| camera.world_to_screen(*p)).collect();
+    let height = engine.get_screen_height() as i32;
+    polygon::spans(&points, rule, height, |x1, x2, y| engine.fill_span(x1, x2, y, pixel));
+}
+
+pub(crate) fn rect_corners(rect: &Rectf) -> [Vf2d; 4] {
+    [
+        rect.pos(),
+        Vf2d::new(rect.right(), rect.y),
+        Vf2d::new(rect.right(), rect.bottom()),
+        Vf2d::new(rect.x, rect.bottom()),
+    ]
+}
+
+// Sprite with its top left corner at pos and one texel per world unit
+pub(crate) fn draw_sprite<E: YapeEngineApi + ?Sized>(
+    engine: &mut E,
+    camera: &Camera2D,
+    pos: Vf2d,
+    sprite: &Sprite,
+    sampling: Sampling,
+) {
+    let transform = Transform2D::new().translate(pos.x, pos.y).then(&camera.transform());
+    transform::draw_sprite_transformed(engine, sprite, &transform, sampling);
+}
@@ -0,0 +1,407 @@
+// Screenshots and frame sequence recording of the engine framebuffer, and PNG loading for sprites
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use crate::{Pixel, Sprite, YapeError, YapeErrorKind, YapeResult};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordFormat {
+    // Numbered files `frame_00000.png`, `frame_00001.png`... inside the given directory
+    PngSequence,
+    // A single looping animated GIF at the given path
+    Gif,
+}
+
+fn encode_error<E: std::error::Error + Send + Sync + 'static>(error: E) -> YapeError {
+    YapeError::with_source(YapeErrorKind::Encode, error.to_string().as_str(), error)
+}
+
+// RGB8 bytes of the sprite with every cell scaled up to pixel_w x pixel_h, alpha is dropped
+// as it is when the framebuffer is presented
+pub fn scaled_rgb(sprite: &Sprite, pixel_w: u32, pixel_h: u32) -> Vec<u8> {
+    let pixel_w = pixel_w.max(1) as usize;
+    let pixel_h = pixel_h.max(1) as usize;
+    let width = sprite.width as usize;
+    let mut rgb: Vec<u8> = Vec::with_capacity(sprite.data.len() * pixel_w * pixel_h * 3);
+    for row in sprite.data.chunks(width.max(1)) {
+        let start = rgb.len();
+        for p in row {
+            for _ in 0..pixel_w {
+                rgb.extend_from_slice(&[p.red, p.green, p.blue]);
+            }
+        }
+        let end = rgb.len();
+        for _ in 1..pixel_h {
+            rgb.extend_from_within(start..end);
+        }
+    }
+    rgb
+}
+
+pub fn save_png<P: AsRef<Path>>(sprite: &Sprite, pixel_w: u32, pixel_h: u32, path: P) -> YapeResult<()> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(file),
+        sprite.width * pixel_w.max(1),
+        sprite.height * pixel_h.max(1),
+    );
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(encode_error)?;
+    writer
+        .write_image_data(&scaled_rgb(sprite, pixel_w, pixel_h))
+        .map_err(encode_error)
+}
+
+// Sprite as RGBA, one pixel per texel
+pub fn save_sprite_png<P: AsRef<Path>>(sprite: &Sprite, path: P) -> YapeResult<()> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), sprite.width, sprite.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(encode_error)?;
+    let rgba: Vec<u8> = sprite.data.iter().flat_map(|p| [p.red, p.green, p.blue, p.alpha]).collect();
+    writer.write_image_data(&rgba).map_err(encode_error)
+}
+
+fn decode_error<E: std::error::Error + Send + Sync + 'static>(error: E) -> YapeError {
+    YapeError::with_source(YapeErrorKind::Decode, error.to_string().as_str(), error)
+}
+
+// Any PNG color type and depth, expanded to 8 bit RGBA
+pub fn load_png<P: AsRef<Path>>(path: P) -> YapeResult<Sprite> {
+    let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(decode_error)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(decode_error)?;
+    let bytes = &buffer[..info.buffer_size()];
+
+    let mut sprite = Sprite::new(info.width, info.height);
+    let channels = info.color_type.samples();
+    for (pixel, p) in sprite.data.iter_mut().zip(bytes.chunks_exact(channels)) {
+        *pixel = match info.color_type {
+            png::ColorType::Grayscale => Pixel::rgb(p[0], p[0], p[0]),
+            png::ColorType::GrayscaleAlpha => Pixel::rgba(p[0], p[0], p[0], p[1]),
+            png::ColorType::Rgb => Pixel::rgb(p[0], p[1], p[2]),
+            png::ColorType::Rgba => Pixel::rgba(p[0], p[1], p[2], p[3]),
+            png::ColorType::Indexed => {
+                return Err(YapeError::new(YapeErrorKind::Decode, "indexed PNG was not expanded"));
+            }
+        };
+    }
+    Ok(sprite)
+}
+
+pub(crate) struct FrameRecorder {
+    path: PathBuf,
+    pixel_w: u32,
+    pixel_h: u32,
+    remaining: u32,
+    index: u32,
+    gif: Option<gif::Encoder<BufWriter<File>>>,
+}
+
+impl FrameRecorder {
+    pub(crate) fn new(
+        path: &Path,
+        frames: u32,
+        format: RecordFormat,
+        sprite: &Sprite,
+        pixel_w: u32,
+        pixel_h: u32,
+    ) -> YapeResult<FrameRecorder> {
+        let gif = match format {
+            RecordFormat::PngSequence => {
+                fs::create_dir_all(path)?;
+                None
+            }
+            RecordFormat::Gif => {
+                let width = sprite.width * pixel_w.max(1);
+                let height = sprite.height * pixel_h.max(1);
+                if width > u16::MAX as u32 || height > u16::MAX as u32 {
+                    return Err(YapeError::new(YapeErrorKind::Encode, "frame too large for a GIF"));
+                }
+                let file = File::create(path)?;
+                let mut encoder = gif::Encoder::new(BufWriter::new(file), width as u16, height as u16, &[])
+                    .map_err(encode_error)?;
+                encoder.set_repeat(gif::Repeat::Infinite).map_err(encode_error)?;
+                Some(encoder)
+            }
+        };
+        Ok(FrameRecorder { path: path.to_path_buf(), pixel_w, pixel_h, remaining: frames, index: 0, gif })
+    }
+
+    // Captures one frame, returns false once the requested range has been recorded
+    pub(crate) fn capture(&mut self, sprite: &Sprite, elapsed: f32) -> YapeResult<bool> {
+        if self.remaining == 0 {
+            return Ok(false);
+        }
+        match self.gif.as_mut() {
+            Some(encoder) => {
+                let width = (sprite.width * self.pixel_w.max(1)) as u16;
+                let height = (sprite.height * self.pixel_h.max(1)) as u16;
+                let mut frame = gif::Frame::from_rgb_speed(
+                    width,
+                    height,
+                    &scaled_rgb(sprite, self.pixel_w, self.pixel_h),
+                    10,
+                );
+                frame.delay = (elapsed * 100.0).round().max(1.0) as u16;
+                encoder.write_frame(&frame).map_err(encode_error)?;
+            }
+            None => {
+                let file = self.path.join(format!("frame_{:05}.png", self.index));
+                save_png(sprite, self.pixel_w, self.pixel_h, file)?;
+            }
+        }
+        self.index += 1;
+        self.remaining -= 1;
+        Ok(self.remaining > 0)
+    }
+}
+
+struct GifFrame {
+    rgb: Vec<u8>,
+    elapsed: f32,
+}
+
+// Keeps the last `max_duration` seconds of frames in memory, encoded only when saved
+pub(crate) struct GifRecorder {
+    // Cleared when recording stops, the frames stay around to be saved
+    pub(crate) capturing: bool,
+    max_duration: f32,
+    duration: f32,
+    width: u32,
+    height: u32,
+    frames: VecDeque<GifFrame>,
+}
+
+impl GifRecorder {
+    pub(crate) fn new(max_duration: f32, width: u32, height: u32) -> GifRecorder {
+        GifRecorder { capturing: true, max_duration, duration: 0.0, width, height, frames: VecDeque::new() }
+    }
+
+    pub(crate) fn capture(&mut self, sprite: &Sprite, elapsed: f32) {
+        if !self.capturing {
+            return;
+        }
+        let rgb = scaled_rgb(sprite, 1, 1);
+        self.frames.push_back(GifFrame { rgb, elapsed });
+        self.duration += elapsed;
+        while self.duration > self.max_duration && self.frames.len() > 1 {
+            if let Some(frame) = self.frames.pop_front() {
+                self.duration -= frame.elapsed;
+            }
+        }
+    }
+
+    pub(crate) fn save(&self, path: &Path, pixel_w: u32, pixel_h: u32) -> YapeResult<()> {
+        let pixel_w = pixel_w.max(1);
+        let pixel_h = pixel_h.max(1);
+        let width = self.width * pixel_w;
+        let height = self.height * pixel_h;
+        if width > u16::MAX as u32 || height > u16::MAX as u32 {
+            return Err(YapeError::new(YapeErrorKind::Encode, "frame too large for a GIF"));
+        }
+        if self.frames.is_empty() {
+            return Err(YapeError::new(YapeErrorKind::Encode, "no frames recorded"));
+        }
+
+        let quantizer = Quantizer::new(self.frames.iter().map(|f| f.rgb.as_slice()));
+        let file = File::create(path)?;
+        let mut encoder = gif::Encoder::new(BufWriter::new(file), width as u16, height as u16, &quantizer.palette_bytes())
+            .map_err(encode_error)?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(encode_error)?;
+
+        // GIF delays are in centiseconds and most viewers clamp anything under 2cs,
+        // so short frames are merged into the next one and rounding error is carried over
+        let mut pending: f32 = 0.0;
+        let frame_count = self.frames.len();
+        for (i, frame) in self.frames.iter().enumerate() {
+            pending += frame.elapsed * 100.0;
+            let last = i + 1 == frame_count;
+            if pending < 2.0 && !last {
+                continue;
+            }
+            let delay = pending.round().max(2.0);
+            pending -= delay;
+
+            let indices = quantizer.scaled_indices(&frame.rgb, self.width, pixel_w, pixel_h);
+            let mut gif_frame = gif::Frame::from_indexed_pixels(width as u16, height as u16, indices, None);
+            gif_frame.delay = delay as u16;
+            encoder.write_frame(&gif_frame).map_err(encode_error)?;
+        }
+        Ok(())
+    }
+}
+
+// Global palette for a whole clip: exact when it has at most 256 colors, otherwise
+// median cut over a 5 bits per channel histogram
+// https://en.wikipedia.org/wiki/Median_cut
+struct Quantizer {
+    palette: Vec<[u8; 3]>,
+    exact: Option<HashMap<[u8; 3], u8>>,
+    reduced: Vec<u8>,
+}
+
+const MAX_COLORS: usize = 256;
+
+fn reduced_key(c: [u8; 3]) -> usize {
+    ((c[0] as usize >> 3) << 10) | ((c[1] as usize >> 3) << 5) | (c[2] as usize >> 3)
+}
+
+impl Quantizer {
+    fn new<'a, I: Iterator<Item = &'a [u8]>>(frames: I) -> Quantizer {
+        let mut exact: HashMap<[u8; 3], u32> = HashMap::new();
+        let mut histogram: Vec<u32> = vec![0; 1 << 15];
+        let mut overflow = false;
+        for rgb in frames {
+            for c in rgb.chunks_exact(3) {
+                let color = [c[0], c[1], c[2]];
+                histogram[reduced_key(color)] += 1;
+                if !overflow {
+                    *exact.entry(color).or_insert(0) += 1;
+                    overflow = exact.len() > MAX_COLORS;
+                }
+            }
+        }
+
+        if !overflow {
+            let palette: Vec<[u8; 3]> = exact.keys().cloned().collect();
+            let lookup = palette.iter().enumerate().map(|(i, c)| (*c, i as u8)).collect();
+            return Quantizer { palette, exact: Some(lookup), reduced: Vec::new() };
+        }
+
+        let colors: Vec<([u8; 3], u32)> = histogram
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(key, count)| {
+                let color = [((key >> 10) << 3) as u8 | 4, (((key >> 5) & 31) << 3) as u8 | 4, ((key & 31) << 3) as u8 | 4];
+                (color, *count)
+            })
+            .collect();
+        let palette = median_cut(colors, MAX_COLORS);
+        let reduced = (0..histogram.len())
+            .map(|key| {
+                if histogram[key] == 0 {
+                    return 0;
+                }
+                let color = [((key >> 10) << 3) as u8 | 4, (((key >> 5) & 31) << 3) as u8 | 4, ((key & 31) << 3) as u8 | 4];
+                nearest(&palette, color)
+            })
+            .collect();
+        Quantizer { palette, exact: None, reduced }
+    }
+
+    fn palette_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = self.palette.iter().flat_map(|c| c.iter().cloned()).collect();
+        // GIF palettes must have a power of two size
+        let size = self.palette.len().max(2).next_power_of_two();
+        bytes.resize(size * 3, 0);
+        bytes
+    }
+
+    fn index(&self, color: [u8; 3]) -> u8 {
+        match self.exact.as_ref() {
+            Some(lookup) => lookup[&color],
+            None => self.reduced[reduced_key(color)],
+        }
+    }
+
+    fn scaled_indices(&self, rgb: &[u8], width: u32, pixel_w: u32, pixel_h: u32) -> Vec<u8> {
+        let mut indices: Vec<u8> = Vec::with_capacity(rgb.len() / 3 * (pixel_w * pixel_h) as usize);
+        for row in rgb.chunks((width * 3).max(3) as usize) {
+            let start = indices.len();
+            for c in row.chunks_exact(3) {
+                let index = self.index([c[0], c[1], c[2]]);
+                for _ in 0..pixel_w {
+                    indices.push(index);
+                }
+            }
+            let end = indices.len();
+            for _ in 1..pixel_h {
+                indices.extend_from_within(start..end);
+            }
+        }
+        indices
+    }
+}
+
+fn nearest(palette: &[[u8; 3]], color: [u8; 3]) -> u8 {
+    let distance = |p: &[u8; 3]| -> i32 {
+        let dr = p[0] as i32 - color[0] as i32;
+        let dg = p[1] as i32 - color[1] as i32;
+        let db = p[2] as i32 - color[2] as i32;
+        dr * dr + dg * dg + db * db
+    };
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, p)| distance(p))
+        .map(|(i, _)| i as u8)
+        .unwrap_or(0)
+}
+
+fn median_cut(colors: Vec<([u8; 3], u32)>, max_colors: usize) -> Vec<[u8; 3]> {
+    let channel_range = |bucket: &[([u8; 3], u32)], channel: usize| -> u8 {
+        let min = bucket.iter().map(|(c, _)| c[channel]).min().unwrap_or(0);
+        let max = bucket.iter().map(|(c, _)| c[channel]).max().unwrap_or(0);
+        max - min
+    };
+    let widest = |bucket: &[([u8; 3], u32)]| -> (usize, u8) {
+        (0..3)
+            .map(|channel| (channel, channel_range(bucket, channel)))
+            .max_by_key(|(_, range)| *range)
+            .unwrap_or((0, 0))
+    };
+
+    let mut buckets: Vec<Vec<([u8; 3], u32)>> = vec![colors];
+    while buckets.len() < max_colors {
+        let candidate = buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, bucket)| bucket.len() > 1)
+            .max_by_key(|(_, bucket)| widest(bucket).1);
+        let index = match candidate {
+            Some((index, _)) => index,
+            None => break,
+        };
+        let mut bucket = buckets.swap_remove(index);
+        let (channel, _) = widest(&bucket);
+        bucket.sort_by_key(|(c, _)| c[channel]);
+
+        // Split at the pixel weighted median
+        let total: u64 = bucket.iter().map(|(_, count)| *count as u64).sum();
+        let mut accumulated: u64 = 0;
+        let mut split = bucket.len() / 2;
+        for (i, (_, count)) in bucket.iter().enumerate() {
+            accumulated += *count as u64;
+            if accumulated * 2 >= total {
+                split = i + 1;
+                break;
+            }
+        }
+        let split = split.max(1).min(bucket.len() - 1);
+        let upper = bucket.split_off(split);
+        buckets.push(bucket);
+        buckets.push(upper);
+    }
+
+    buckets
+        .iter()
+        .map(|bucket| {
+            let total: u64 = bucket.iter().map(|(_, count)| *count as u64).sum::<u64>().max(1);
+            let mut sum = [0u64; 3];
+            for (c, count) in bucket {
+                for channel in 0..3 {
+                    sum[channel] += c[channel] as u64 * *count as u64;
+                }
+            }
+            [(sum[0] / total) as u8, (sum[1] / total) as u8, (sum[2] / total) as u8]
+        })
+        .collect()
+}
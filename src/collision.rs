@@ -0,0 +1,442 @@
+// Collision detection between boxes, circles and convex polygons with SAT, plus swept boxes and
+// ray casts against shapes and tile grids
+// https://dyn4j.org/2010/01/sat/
+// http://www.cse.yorku.ca/~amana/research/grid.pdf
+use std::cmp::Ordering;
+
+use crate::math::{Rectf, Vf2d, Vi2d};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Circle {
+    pub center: Vf2d,
+    pub radius: f32,
+}
+
+impl Circle {
+    pub fn new(center: Vf2d, radius: f32) -> Circle {
+        Circle { center, radius }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Aabb(Rectf),
+    Circle(Circle),
+    // Convex, in either winding
+    Polygon(Vec<Vf2d>),
+}
+
+impl Shape {
+    pub fn bounds(&self) -> Rectf {
+        match self {
+            Shape::Aabb(rect) => *rect,
+            Shape::Circle(c) => {
+                let r = Vf2d::new(c.radius, c.radius);
+                Rectf::from_corners(c.center - r, c.center + r)
+            }
+            Shape::Polygon(points) => {
+                let first = points.first().copied().unwrap_or_default();
+                let (min, max) = points.iter().fold((first, first), |(min, max), p| (min.min(*p), max.max(*p)));
+                Rectf::from_corners(min, max)
+            }
+        }
+    }
+
+    pub fn translate(&self, offset: Vf2d) -> Shape {
+        match self {
+            Shape::Aabb(rect) => Shape::Aabb(rect.translate(offset)),
+            Shape::Circle(c) => Shape::Circle(Circle::new(c.center + offset, c.radius)),
+            Shape::Polygon(points) => Shape::Polygon(points.iter().map(|p| *p + offset).collect()),
+        }
+    }
+
+    pub fn contains(&self, point: Vf2d) -> bool {
+        match self {
+            Shape::Aabb(rect) => rect.contains(point),
+            Shape::Circle(c) => (point - c.center).length2() <= c.radius * c.radius,
+            Shape::Polygon(points) => !points.is_empty() && edges(points).all(|(a, _, n)| n.dot(point - a) <= 0.0),
+        }
+    }
+
+    pub fn overlaps(&self, other: &Shape) -> bool {
+        collide(self, other).is_some()
+    }
+}
+
+// How two overlapping shapes touch. The normal is a unit vector from the first shape toward the
+// second, moving the second by normal * depth (or the first by the opposite) separates them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contact {
+    pub normal: Vf2d,
+    pub depth: f32,
+    // One or two points of the overlap, where the shapes push on each other
+    pub points: Vec<Vf2d>,
+}
+
+impl Contact {
+    // The same contact seen from the other shape
+    pub fn flip(self) -> Contact {
+        Contact { normal: -self.normal, ..self }
+    }
+
+    // Offset that moves the second shape out of the first
+    pub fn penetration(&self) -> Vf2d {
+        self.normal * self.depth
+    }
+}
+
+// Where a ray or a swept box first touches something. Rays cover origin to origin + direction,
+// time is the fraction of that travelled and the normal is the surface's, facing the ray
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub time: f32,
+    pub point: Vf2d,
+    pub normal: Vf2d,
+}
+
+// None when the shapes only touch or are apart
+pub fn collide(a: &Shape, b: &Shape) -> Option<Contact> {
+    match (a, b) {
+        (Shape::Aabb(a), Shape::Aabb(b)) => aabb_vs_aabb(a, b),
+        (Shape::Circle(a), Shape::Circle(b)) => circle_vs_circle(a, b),
+        (Shape::Aabb(a), Shape::Circle(b)) => aabb_vs_circle(a, b),
+        (Shape::Circle(a), Shape::Aabb(b)) => aabb_vs_circle(b, a).map(Contact::flip),
+        (Shape::Polygon(a), Shape::Circle(b)) => polygon_vs_circle(a, b),
+        (Shape::Circle(a), Shape::Polygon(b)) => polygon_vs_circle(b, a).map(Contact::flip),
+        (Shape::Polygon(a), Shape::Polygon(b)) => polygon_vs_polygon(a, b),
+        (Shape::Aabb(a), Shape::Polygon(b)) => polygon_vs_polygon(&corners(a), b),
+        (Shape::Polygon(a), Shape::Aabb(b)) => polygon_vs_polygon(a, &corners(b)),
+    }
+}
+
+pub fn aabb_vs_aabb(a: &Rectf, b: &Rectf) -> Option<Contact> {
+    let overlap = a.clip(b)?;
+    let delta = b.center() - a.center();
+    // Push out along the axis that needs the smaller move, the contact points are the ends of the
+    // overlap's middle line across that axis
+    let (normal, depth, points) = if overlap.w < overlap.h {
+        let x = overlap.x + overlap.w / 2.0;
+        let normal = Vf2d::new(if delta.x < 0.0 { -1.0 } else { 1.0 }, 0.0);
+        (normal, overlap.w, vec![Vf2d::new(x, overlap.y), Vf2d::new(x, overlap.bottom())])
+    } else {
+        let y = overlap.y + overlap.h / 2.0;
+        let normal = Vf2d::new(0.0, if delta.y < 0.0 { -1.0 } else { 1.0 });
+        (normal, overlap.h, vec![Vf2d::new(overlap.x, y), Vf2d::new(overlap.right(), y)])
+    };
+    Some(Contact { normal, depth, points })
+}
+
+pub fn circle_vs_circle(a: &Circle, b: &Circle) -> Option<Contact> {
+    let delta = b.center - a.center;
+    let radii = a.radius + b.radius;
+    let distance2 = delta.length2();
+    if distance2 >= radii * radii {
+        return None;
+    }
+    let distance = distance2.sqrt();
+    let normal = if distance > 0.0 { delta / distance } else { Vf2d::new(1.0, 0.0) };
+    Some(Contact { normal, depth: radii - distance, points: vec![a.center + normal * a.radius] })
+}
+
+pub fn aabb_vs_circle(a: &Rectf, b: &Circle) -> Option<Contact> {
+    let closest = b.center.clamp(a.pos(), Vf2d::new(a.right(), a.bottom()));
+    let delta = b.center - closest;
+    let distance2 = delta.length2();
+    if distance2 > 0.0 {
+        if distance2 >= b.radius * b.radius {
+            return None;
+        }
+        let distance = distance2.sqrt();
+        return Some(Contact { normal: delta / distance, depth: b.radius - distance, points: vec![closest] });
+    }
+    // Center inside the box, leave through the nearest side
+    let sides = [
+        (b.center.x - a.x, Vf2d::new(-1.0, 0.0)),
+        (a.right() - b.center.x, Vf2d::new(1.0, 0.0)),
+        (b.center.y - a.y, Vf2d::new(0.0, -1.0)),
+        (a.bottom() - b.center.y, Vf2d::new(0.0, 1.0)),
+    ];
+    let (distance, normal) = sides.iter().copied().fold(sides[0], |best, side| if side.0 < best.0 { side } else { best });
+    Some(Contact { normal, depth: distance + b.radius, points: vec![b.center + normal * distance] })
+}
+
+pub fn polygon_vs_circle(a: &[Vf2d], b: &Circle) -> Option<Contact> {
+    if a.len() < 3 {
+        return None;
+    }
+    let (separation, normal) = edges(a)
+        .map(|(p, _, n)| (n.dot(b.center - p), n))
+        .fold((f32::MIN, Vf2d::new(0.0, 0.0)), |best, e| if e.0 > best.0 { e } else { best });
+    if separation > b.radius {
+        return None;
+    }
+    if separation <= 0.0 {
+        return Some(Contact { normal, depth: b.radius - separation, points: vec![b.center - normal * separation] });
+    }
+    // Outside, the closest boundary point decides between a face and a corner
+    let closest = edges(a)
+        .map(|(p, q, _)| closest_on_segment(p, q, b.center))
+        .min_by(|p, q| {
+            let (dp, dq) = ((*p - b.center).length2(), (*q - b.center).length2());
+            dp.partial_cmp(&dq).unwrap_or(Ordering::Equal)
+        })?;
+    let delta = b.center - closest;
+    let distance = delta.length();
+    if distance >= b.radius {
+        return None;
+    }
+    Some(Contact { normal: delta / distance, depth: b.radius - distance, points: vec![closest] })
+}
+
+// SAT over both polygons' edge normals, then the edge most facing the separating axis is clipped
+// against the other polygon's reference edge for up to two contact points
+pub fn polygon_vs_polygon(a: &[Vf2d], b: &[Vf2d]) -> Option<Contact> {
+    if a.len() < 3 || b.len() < 3 {
+        return None;
+    }
+    let (separation_a, edge_a) = least_penetration(a, b);
+    if separation_a >= 0.0 {
+        return None;
+    }
+    let (separation_b, edge_b) = least_penetration(b, a);
+    if separation_b >= 0.0 {
+        return None;
+    }
+    // Small bias so resting contacts don't switch reference polygon between frames
+    let (reference, incident, edge, flipped) = if separation_b > separation_a * 0.98 + 0.001 {
+        (b, a, edge_b, true)
+    } else {
+        (a, b, edge_a, false)
+    };
+
+    let (r1, r2, normal) = edges(reference).nth(edge)?;
+    let (i1, i2, _) = edges(incident)
+        .min_by(|e, f| e.2.dot(normal).partial_cmp(&f.2.dot(normal)).unwrap_or(Ordering::Equal))?;
+
+    let tangent = (r2 - r1).normalize();
+    let clipped = clip_segment(i1, i2, -tangent, -tangent.dot(r1))
+        .and_then(|(p, q)| clip_segment(p, q, tangent, tangent.dot(r2)))?;
+
+    let mut depth: f32 = 0.0;
+    let mut points = Vec::with_capacity(2);
+    for p in [clipped.0, clipped.1].iter() {
+        let separation = normal.dot(*p - r1);
+        if separation <= 0.0 {
+            depth = depth.max(-separation);
+            points.push(*p);
+        }
+    }
+    if points.is_empty() {
+        return None;
+    }
+    let normal = if flipped { -normal } else { normal };
+    Some(Contact { normal, depth, points })
+}
+
+// Slab test, rays starting inside the box or only grazing it don't hit it
+pub fn ray_vs_aabb(origin: Vf2d, direction: Vf2d, rect: &Rectf) -> Option<Hit> {
+    let mut enter = f32::MIN;
+    let mut exit = f32::MAX;
+    let mut normal = Vf2d::new(0.0, 0.0);
+    let axes = [
+        (origin.x, direction.x, rect.x, rect.right(), Vf2d::new(1.0, 0.0)),
+        (origin.y, direction.y, rect.y, rect.bottom(), Vf2d::new(0.0, 1.0)),
+    ];
+    for &(o, d, low, high, axis) in axes.iter() {
+        if d == 0.0 {
+            if o <= low || o >= high {
+                return None;
+            }
+            continue;
+        }
+        let (near, far, side) = if d > 0.0 {
+            ((low - o) / d, (high - o) / d, -axis)
+        } else {
+            ((high - o) / d, (low - o) / d, axis)
+        };
+        if near > enter {
+            enter = near;
+            normal = side;
+        }
+        exit = exit.min(far);
+    }
+    if enter >= exit || !(0.0..=1.0).contains(&enter) {
+        return None;
+    }
+    Some(Hit { time: enter, point: origin + direction * enter, normal })
+}
+
+pub fn ray_vs_circle(origin: Vf2d, direction: Vf2d, circle: &Circle) -> Option<Hit> {
+    let a = direction.length2();
+    if a == 0.0 {
+        return None;
+    }
+    let to_origin = origin - circle.center;
+    let b = to_origin.dot(direction);
+    let c = to_origin.length2() - circle.radius * circle.radius;
+    let discriminant = b * b - a * c;
+    if c < 0.0 || discriminant < 0.0 {
+        return None;
+    }
+    let time = (-b - discriminant.sqrt()) / a;
+    if !(0.0..=1.0).contains(&time) {
+        return None;
+    }
+    let point = origin + direction * time;
+    Some(Hit { time, point, normal: (point - circle.center).normalize() })
+}
+
+// Cyrus-Beck clipping against every edge
+pub fn ray_vs_polygon(origin: Vf2d, direction: Vf2d, polygon: &[Vf2d]) -> Option<Hit> {
+    if polygon.len() < 3 {
+        return None;
+    }
+    let mut enter = 0.0;
+    let mut exit = 1.0;
+    let mut normal = None;
+    for (p, _, n) in edges(polygon) {
+        let distance = n.dot(origin - p);
+        let speed = n.dot(direction);
+        if speed == 0.0 {
+            if distance > 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let time = -distance / speed;
+        if speed < 0.0 && time > enter {
+            enter = time;
+            normal = Some(n);
+        } else if speed > 0.0 {
+            exit = time.min(exit);
+        }
+        if enter > exit {
+            return None;
+        }
+    }
+    // Only edges crossed after the start count, so a ray starting inside has none
+    let normal = normal?;
+    Some(Hit { time: enter, point: origin + direction * enter, normal })
+}
+
+pub fn raycast(origin: Vf2d, direction: Vf2d, shape: &Shape) -> Option<Hit> {
+    match shape {
+        Shape::Aabb(rect) => ray_vs_aabb(origin, direction, rect),
+        Shape::Circle(circle) => ray_vs_circle(origin, direction, circle),
+        Shape::Polygon(points) => ray_vs_polygon(origin, direction, points),
+    }
+}
+
+// First time a box moving by velocity touches target. Boxes that already overlap hit at time 0
+// with the normal of the shortest way out
+pub fn sweep_aabb(moving: &Rectf, velocity: Vf2d, target: &Rectf) -> Option<Hit> {
+    if let Some(contact) = aabb_vs_aabb(moving, target) {
+        return Some(Hit { time: 0.0, point: moving.center(), normal: -contact.normal });
+    }
+    // Grow the target by the moving box and cast its center
+    let half = moving.size() / 2.0;
+    let expanded = Rectf::new(target.x - half.x, target.y - half.y, target.w + moving.w, target.h + moving.h);
+    ray_vs_aabb(moving.center(), velocity, &expanded)
+}
+
+// Walks the cells crossed by the ray in order and reports the first one is_solid accepts, with
+// cells of cell_size pixels and cell (0, 0) at the origin of the world. The cell the ray starts
+// in is skipped
+pub fn raycast_grid<F: FnMut(i32, i32) -> bool>(origin: Vf2d, direction: Vf2d, cell_size: Vf2d, mut is_solid: F) -> Option<(Vi2d, Hit)> {
+    if direction.length2() == 0.0 || cell_size.x <= 0.0 || cell_size.y <= 0.0 {
+        return None;
+    }
+    let start = (origin / cell_size).floor();
+    let end = ((origin + direction) / cell_size).floor();
+    let mut cell = start.to_i32();
+    let step = Vi2d::new(direction.x.signum() as i32, direction.y.signum() as i32);
+
+    // Time to cross one cell and time to reach the next cell border on each axis
+    let axis = |o: f32, d: f32, size: f32, c: f32| {
+        if d == 0.0 {
+            (f32::MAX, f32::MAX)
+        } else {
+            let border = if d > 0.0 { (c + 1.0) * size } else { c * size };
+            ((size / d).abs(), (border - o) / d)
+        }
+    };
+    let (delta_x, mut next_x) = axis(origin.x, direction.x, cell_size.x, start.x);
+    let (delta_y, mut next_y) = axis(origin.y, direction.y, cell_size.y, start.y);
+
+    let steps = (end.x - start.x).abs() as u32 + (end.y - start.y).abs() as u32;
+    for _ in 0..steps {
+        let (time, normal) = if next_x < next_y {
+            cell.x += step.x;
+            let time = next_x;
+            next_x += delta_x;
+            (time, Vf2d::new(-step.x as f32, 0.0))
+        } else {
+            cell.y += step.y;
+            let time = next_y;
+            next_y += delta_y;
+            (time, Vf2d::new(0.0, -step.y as f32))
+        };
+        if time > 1.0 {
+            break;
+        }
+        if is_solid(cell.x, cell.y) {
+            return Some((cell, Hit { time, point: origin + direction * time, normal }));
+        }
+    }
+    None
+}
+
+fn corners(rect: &Rectf) -> [Vf2d; 4] {
+    [
+        rect.pos(),
+        Vf2d::new(rect.right(), rect.y),
+        Vf2d::new(rect.right(), rect.bottom()),
+        Vf2d::new(rect.x, rect.bottom()),
+    ]
+}
+
+// Every edge as (start, end, outward unit normal), whatever the winding
+fn edges(points: &[Vf2d]) -> impl Iterator<Item = (Vf2d, Vf2d, Vf2d)> + '_ {
+    let area: f32 = (0..points.len()).map(|i| points[i].cross(points[(i + 1) % points.len()])).sum();
+    // With y down a positive area is clockwise on screen and the outward normal is to the left
+    let sign = if area < 0.0 { -1.0 } else { 1.0 };
+    (0..points.len()).map(move |i| {
+        let (p, q) = (points[i], points[(i + 1) % points.len()]);
+        let edge = q - p;
+        (p, q, Vf2d::new(edge.y, -edge.x).normalize() * sign)
+    })
+}
+
+// Largest separation of b from any edge of a, with that edge. Negative means overlap on every axis
+fn least_penetration(a: &[Vf2d], b: &[Vf2d]) -> (f32, usize) {
+    edges(a)
+        .enumerate()
+        .map(|(i, (p, _, n))| {
+            let support = b.iter().map(|v| n.dot(*v - p)).fold(f32::MAX, f32::min);
+            (support, i)
+        })
+        .fold((f32::MIN, 0), |best, e| if e.0 > best.0 { e } else { best })
+}
+
+// Keeps the part of the segment where dot(normal, p) <= offset
+fn clip_segment(p: Vf2d, q: Vf2d, normal: Vf2d, offset: f32) -> Option<(Vf2d, Vf2d)> {
+    let dp = normal.dot(p) - offset;
+    let dq = normal.dot(q) - offset;
+    if dp > 0.0 && dq > 0.0 {
+        return None;
+    }
+    if dp <= 0.0 && dq <= 0.0 {
+        return Some((p, q));
+    }
+    let cut = p + (q - p) * (dp / (dp - dq));
+    Some(if dp > 0.0 { (cut, q) } else { (p, cut) })
+}
+
+fn closest_on_segment(p: Vf2d, q: Vf2d, point: Vf2d) -> Vf2d {
+    let edge = q - p;
+    let length2 = edge.length2();
+    if length2 == 0.0 {
+        return p;
+    }
+    p + edge * ((point - p).dot(edge) / length2).clamp(0.0, 1.0)
+}
@@ -0,0 +1,221 @@
+// Curves, arcs and rounded rectangles on top of the line and circle rasterizers
+use std::cmp;
+use std::f32::consts::PI;
+
+use crate::raster;
+use crate::{Pixel, Vf2d, Vi2d, YapeEngineApi};
+
+// Control points closer to the chord than this many pixels end the subdivision
+const FLATNESS: f32 = 0.25;
+const MAX_DEPTH: u32 = 16;
+
+// Adaptive de Casteljau subdivision, pushes the end point of every flat enough piece
+// https://agg.sourceforge.net/antigrain.com/research/adaptive_bezier/index.html
+fn flatten_cubic(p0: Vf2d, p1: Vf2d, p2: Vf2d, p3: Vf2d, depth: u32, out: &mut Vec<Vf2d>) {
+    let chord = p3 - p0;
+    let length = chord.length();
+    let flat = if length > 0.0 {
+        (p1 - p0).cross(chord).abs() / length <= FLATNESS && (p2 - p0).cross(chord).abs() / length <= FLATNESS
+    } else {
+        p1.distance(p0) <= FLATNESS && p2.distance(p0) <= FLATNESS
+    };
+    if flat || depth >= MAX_DEPTH {
+        out.push(p3);
+        return;
+    }
+    let p01 = p0.lerp(p1, 0.5);
+    let p12 = p1.lerp(p2, 0.5);
+    let p23 = p2.lerp(p3, 0.5);
+    let p012 = p01.lerp(p12, 0.5);
+    let p123 = p12.lerp(p23, 0.5);
+    let mid = p012.lerp(p123, 0.5);
+    flatten_cubic(p0, p01, p012, mid, depth + 1, out);
+    flatten_cubic(mid, p123, p23, p3, depth + 1, out);
+}
+
+// Connected segments where each one skips its first pixel, the previous segment already drew it
+fn polyline<E: YapeEngineApi + ?Sized>(engine: &mut E, points: &[Vf2d], pixel: &Pixel) {
+    let points: Vec<Vi2d> = points.iter().map(|p| Vi2d::new(p.x.round() as i32, p.y.round() as i32)).collect();
+    if let Some(first) = points.first() {
+        raster::plot(engine, first.x, first.y, pixel);
+    }
+    for pair in points.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        if a == b {
+            continue;
+        }
+        raster::line(a.x, a.y, b.x, b.y, |x, y| {
+            if x != a.x || y != a.y {
+                raster::plot(engine, x, y, pixel);
+            }
+        });
+    }
+}
+
+pub(crate) fn cubic_bezier<E: YapeEngineApi + ?Sized>(
+    engine: &mut E,
+    p0: Vf2d,
+    p1: Vf2d,
+    p2: Vf2d,
+    p3: Vf2d,
+    pixel: &Pixel,
+) {
+    let mut points = vec![p0];
+    flatten_cubic(p0, p1, p2, p3, 0, &mut points);
+    polyline(engine, &points, pixel);
+}
+
+// Degree elevated to a cubic with the same shape
+pub(crate) fn quadratic_bezier<E: YapeEngineApi + ?Sized>(engine: &mut E, p0: Vf2d, p1: Vf2d, p2: Vf2d, pixel: &Pixel) {
+    let c1 = p0.lerp(p1, 2.0 / 3.0);
+    let c2 = p2.lerp(p1, 2.0 / 3.0);
+    cubic_bezier(engine, p0, c1, c2, p2, pixel);
+}
+
+// Angles are radians from the positive x axis, clockwise on screen, and the sector runs from
+// start to end in that direction. A sweep of a full turn or more is the whole circle
+struct Sector {
+    start: f32,
+    sweep: f32,
+}
+
+impl Sector {
+    fn new(start: f32, end: f32) -> Sector {
+        let sweep = end - start;
+        let sweep = if sweep >= 2.0 * PI { 2.0 * PI } else { sweep.rem_euclid(2.0 * PI) };
+        Sector { start, sweep }
+    }
+
+    fn contains(&self, dx: i32, dy: i32) -> bool {
+        if self.sweep >= 2.0 * PI || (dx == 0 && dy == 0) {
+            return true;
+        }
+        let angle = (dy as f32).atan2(dx as f32);
+        (angle - self.start).rem_euclid(2.0 * PI) <= self.sweep
+    }
+
+    fn point(&self, center: Vi2d, radius: i32, angle: f32) -> Vi2d {
+        let p = center.to_f32() + Vf2d::from_polar(radius as f32, angle);
+        Vi2d::new(p.x.round() as i32, p.y.round() as i32)
+    }
+}
+
+pub(crate) fn arc<E: YapeEngineApi + ?Sized>(
+    engine: &mut E,
+    center: Vi2d,
+    radius: i32,
+    start: f32,
+    end: f32,
+    pixel: &Pixel,
+) {
+    let sector = Sector::new(start, end);
+    raster::circle(center.x, center.y, radius, |x, y| {
+        if sector.contains(x - center.x, y - center.y) {
+            raster::plot(engine, x, y, pixel);
+        }
+    });
+}
+
+// Arc closed with the two radii
+pub(crate) fn pie<E: YapeEngineApi + ?Sized>(
+    engine: &mut E,
+    center: Vi2d,
+    radius: i32,
+    start: f32,
+    end: f32,
+    pixel: &Pixel,
+) {
+    let sector = Sector::new(start, end);
+    arc(engine, center, radius, start, end, pixel);
+    if sector.sweep < 2.0 * PI {
+        for &angle in [sector.start, sector.start + sector.sweep].iter() {
+            let p = sector.point(center, radius, angle);
+            raster::line(center.x, center.y, p.x, p.y, |x, y| raster::plot(engine, x, y, pixel));
+        }
+    }
+}
+
+// Rows of fill_circle cut into runs of pixels inside the sector
+pub(crate) fn fill_pie<E: YapeEngineApi + ?Sized>(
+    engine: &mut E,
+    center: Vi2d,
+    radius: i32,
+    start: f32,
+    end: f32,
+    pixel: &Pixel,
+) {
+    let sector = Sector::new(start, end);
+    let (width, height) = (engine.get_screen_width() as i32, engine.get_screen_height() as i32);
+    raster::circle_spans(center.x, center.y, radius, height, |x1, x2, y| {
+        let (x1, x2) = (cmp::max(x1, 0), cmp::min(x2, width - 1));
+        let mut run: Option<i32> = None;
+        for x in x1..=x2 + 1 {
+            let inside = x <= x2 && sector.contains(x - center.x, y - center.y);
+            match (inside, run) {
+                (true, None) => run = Some(x),
+                (false, Some(first)) => {
+                    engine.fill_span(first, x - 1, y, pixel);
+                    run = None;
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+// Same extents as draw_rect, the corners are quarters of draw_circle
+pub(crate) fn rounded_rect<E: YapeEngineApi + ?Sized>(
+    engine: &mut E,
+    pos: Vi2d,
+    size: Vi2d,
+    radius: i32,
+    pixel: &Pixel,
+) {
+    let end = pos + size;
+    let r = radius.min(size.x / 2).min(size.y / 2).max(0);
+    let mut line = |a: Vi2d, b: Vi2d| raster::line(a.x, a.y, b.x, b.y, |x, y| raster::plot(engine, x, y, pixel));
+    line(Vi2d::new(pos.x + r, pos.y), Vi2d::new(end.x - r, pos.y));
+    line(Vi2d::new(pos.x + r, end.y), Vi2d::new(end.x - r, end.y));
+    line(Vi2d::new(pos.x, pos.y + r), Vi2d::new(pos.x, end.y - r));
+    line(Vi2d::new(end.x, pos.y + r), Vi2d::new(end.x, end.y - r));
+    if r == 0 {
+        return;
+    }
+    raster::circle(0, 0, r, |dx, dy| {
+        let cx = if dx <= 0 { pos.x + r } else { end.x - r };
+        let cy = if dy <= 0 { pos.y + r } else { end.y - r };
+        raster::plot(engine, cx + dx, cy + dy, pixel);
+    });
+}
+
+// Same extents as fill_rect, the corners are quarters of fill_circle
+pub(crate) fn fill_rounded_rect<E: YapeEngineApi + ?Sized>(
+    engine: &mut E,
+    pos: Vi2d,
+    size: Vi2d,
+    radius: i32,
+    pixel: &Pixel,
+) {
+    if size.x <= 0 || size.y <= 0 {
+        return;
+    }
+    let (x1, y1) = (pos.x, pos.y);
+    let (x2, y2) = (pos.x + size.x - 1, pos.y + size.y - 1);
+    let r = radius.min((size.x - 1) / 2).min((size.y - 1) / 2).max(0);
+    let limit = (r * r + r) as i64;
+    for y in y1..=y2 {
+        let dy = if y < y1 + r {
+            y1 + r - y
+        } else if y > y2 - r {
+            y - (y2 - r)
+        } else {
+            0
+        } as i64;
+        let mut dx = ((limit - dy * dy) as f64).sqrt() as i64;
+        while dx * dx + dy * dy > limit {
+            dx -= 1;
+        }
+        let inset = r - dx as i32;
+        engine.fill_span(x1 + inset, x2 - inset, y, pixel);
+    }
+}
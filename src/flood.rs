@@ -0,0 +1,123 @@
+// Flood fill and region selection on a draw target
+use crate::{Pixel, Recti, Sprite};
+
+// One flag per pixel of a draw target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    pub width: u32,
+    pub height: u32,
+    data: Vec<bool>,
+}
+
+impl Mask {
+    pub fn new(width: u32, height: u32) -> Mask {
+        Mask { width, height, data: vec![false; (width * height) as usize] }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height && self.data[(y * self.width + x) as usize]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, value: bool) {
+        if x < self.width && y < self.height {
+            self.data[(y * self.width + x) as usize] = value;
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.data.iter().filter(|&&v| v).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.data.iter().any(|&v| v)
+    }
+
+    // Smallest rect around every set pixel
+    pub fn bounds(&self) -> Option<Recti> {
+        let mut bounds: Option<Recti> = None;
+        for (x1, x2, y) in self.spans() {
+            let row = Recti::new(x1 as i32, y as i32, (x2 - x1 + 1) as i32, 1);
+            bounds = Some(bounds.map_or(row, |b| b.union(&row)));
+        }
+        bounds
+    }
+
+    pub fn invert(&mut self) {
+        for value in self.data.iter_mut() {
+            *value = !*value;
+        }
+    }
+
+    // Runs of set pixels as inclusive (x1, x2, y), row by row
+    pub fn spans(&self) -> Vec<(u32, u32, u32)> {
+        let mut spans = Vec::new();
+        for y in 0..self.height {
+            let row = &self.data[(y * self.width) as usize..((y + 1) * self.width) as usize];
+            let mut x = 0;
+            while x < self.width {
+                if row[x as usize] {
+                    let start = x;
+                    while x + 1 < self.width && row[x as usize + 1] {
+                        x += 1;
+                    }
+                    spans.push((start, x, y));
+                }
+                x += 1;
+            }
+        }
+        spans
+    }
+}
+
+// Largest difference over the four channels
+fn distance(a: &Pixel, b: &Pixel) -> u8 {
+    a.red
+        .abs_diff(b.red)
+        .max(a.green.abs_diff(b.green))
+        .max(a.blue.abs_diff(b.blue))
+        .max(a.alpha.abs_diff(b.alpha))
+}
+
+// Connected pixels within tolerance of the seed color, 4-way connectivity.
+// Scanline fill with an explicit stack of seeds, one per run found above or below a filled run
+// https://en.wikipedia.org/wiki/Flood_fill#Span_filling
+pub(crate) fn select(target: &Sprite, x: u32, y: u32, tolerance: u8) -> Mask {
+    let (w, h) = (target.width, target.height);
+    let mut mask = Mask::new(w, h);
+    if x >= w || y >= h {
+        return mask;
+    }
+    let seed = *target.get_pixel(x, y);
+    let matches = |mask: &Mask, x: u32, y: u32| !mask.get(x, y) && distance(target.get_pixel(x, y), &seed) <= tolerance;
+
+    let mut stack = vec![(x, y)];
+    while let Some((x, y)) = stack.pop() {
+        if !matches(&mask, x, y) {
+            continue;
+        }
+        let mut x1 = x;
+        while x1 > 0 && matches(&mask, x1 - 1, y) {
+            x1 -= 1;
+        }
+        let mut x2 = x;
+        while x2 + 1 < w && matches(&mask, x2 + 1, y) {
+            x2 += 1;
+        }
+        for fx in x1..=x2 {
+            mask.set(fx, y, true);
+        }
+
+        let rows = [y.checked_sub(1), if y + 1 < h { Some(y + 1) } else { None }];
+        for row in rows.iter().flatten() {
+            let mut in_run = false;
+            for fx in x1..=x2 {
+                let hit = matches(&mask, fx, *row);
+                if hit && !in_run {
+                    stack.push((fx, *row));
+                }
+                in_run = hit;
+            }
+        }
+    }
+    mask
+}
@@ -1,44 +1,116 @@
-use minifb;
+use std::error;
 use std::fmt;
+use std::io;
 use std::convert;
 use std::time;
 use std::cmp;
-use std::collections::HashMap;
-use minifb::Key;
+use std::path::{Path, PathBuf};
+
+use antialias::LineCap;
+use camera::Camera2D;
+use capture::{FrameRecorder, GifRecorder, RecordFormat};
+use flood::Mask;
+use paint::Paint;
+use polygon::FillRule;
+use stroke::LineStyle;
+use transform::{Sampling, Transform2D};
+
+pub use math::{Rect, Rectf, Recti, V2d, Vf2d, Vi2d};
+
+pub mod animation;
+pub mod antialias;
+pub mod aseprite;
+pub mod atlas;
+pub mod camera;
+pub mod capture;
+pub mod collision;
+pub mod flood;
+pub mod math;
+pub mod paint;
+pub mod particles;
+pub mod physics;
+pub mod platformer;
+pub mod polygon;
+pub mod spatial;
+pub mod stroke;
+pub mod synth;
+pub mod tiled;
+pub mod tilemap;
+pub mod transform;
+mod curves;
+mod raster;
+#[cfg(feature = "audio")]
+mod audio;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum YapeErrorKind {
+    Window,
+    Io,
+    Decode,
+    Encode,
+    Audio,
+    User,
+}
 
 #[derive(Debug)]
 pub struct YapeError {
-    pub kind: String,
+    pub kind: YapeErrorKind,
     pub message: String,
+    source: Option<Box<dyn error::Error + Send + Sync + 'static>>,
 }
 
 impl YapeError {
-    fn new(kind: &str, message: &str) -> YapeError {
-        YapeError { kind: kind.to_string(), message: message.to_string() }
+    pub fn new(kind: YapeErrorKind, message: &str) -> YapeError {
+        YapeError { kind, message: message.to_string(), source: None }
+    }
+
+    pub fn with_source<E>(kind: YapeErrorKind, message: &str, source: E) -> YapeError
+    where
+        E: error::Error + Send + Sync + 'static,
+    {
+        YapeError { kind, message: message.to_string(), source: Some(Box::new(source)) }
+    }
+
+    // For errors raised by games from their callbacks
+    pub fn user(message: &str) -> YapeError {
+        YapeError::new(YapeErrorKind::User, message)
     }
 }
 
 impl fmt::Display for YapeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "YapeError[kind={}, message={}]", self.kind, self.message)
+        write!(f, "YapeError[kind={:?}, message={}]", self.kind, self.message)
+    }
+}
+
+impl error::Error for YapeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.source.as_ref().map(|e| e.as_ref() as &(dyn error::Error + 'static))
     }
 }
 
 impl convert::From<minifb::Error> for YapeError {
     fn from(error: minifb::Error) -> Self {
         match error {
-            minifb::Error::MenusNotSupported => YapeError::new("MenusNotSupported", ""),
-            minifb::Error::MenuExists(message) => YapeError::new("MenuExists", message.as_str()),
-            minifb::Error::WindowCreate(message) => YapeError::new("WindowCreate", message.as_str()),
-            minifb::Error::UpdateFailed(message) => YapeError::new("UpdateFailed", message.as_str()),
+            minifb::Error::MenusNotSupported => YapeError::new(YapeErrorKind::Window, "menus not supported"),
+            minifb::Error::MenuExists(message) => YapeError::new(YapeErrorKind::Window, format!("menu exists: {}", message).as_str()),
+            minifb::Error::WindowCreate(message) => YapeError::new(YapeErrorKind::Window, format!("window create: {}", message).as_str()),
+            minifb::Error::UpdateFailed(message) => YapeError::new(YapeErrorKind::Window, format!("update failed: {}", message).as_str()),
         }
     }
 }
 
+impl convert::From<io::Error> for YapeError {
+    fn from(error: io::Error) -> Self {
+        YapeError::with_source(YapeErrorKind::Io, error.to_string().as_str(), error)
+    }
+}
+
 pub type YapeResult<T> = Result<T, YapeError>;
 
 const BUTTON_STATE_EMPTY: ButtonState = ButtonState { pressed: false, released: false, held: false };
 
+#[derive(Clone, Copy)]
 pub struct ButtonState {
     pub pressed: bool,
     pub released: bool,
@@ -51,12 +123,6 @@ impl ButtonState {
     }
 }
 
-impl std::clone::Clone for ButtonState {
-    fn clone(&self) -> Self {
-        ButtonState { pressed: self.pressed, released: self.released, held: self.held }
-    }
-}
-
 #[derive(PartialEq, Eq, Hash, Copy, Clone)]
 pub enum KeyboardKey {
     Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9,
@@ -67,17 +133,21 @@ pub enum KeyboardKey {
     LeftShift, RightShift, LeftCtrl, RightCtrl, LeftAlt, RightAlt,
 }
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone)]
 pub enum MouseButton {
     Left, Middle, Right
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PixelMode {
     Normal,
     Mask,
     Alpha,
+    // Adds the color scaled by alpha and blend factor, for glows and particles
+    Additive,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct Pixel {
     pub red: u8,
     pub green: u8,
@@ -113,21 +183,30 @@ pub const YELLOW: Pixel = Pixel { red: 255, green: 255, blue: 0, alpha: 255 };
 impl Pixel {
 
     #[inline]
-    pub fn rbg_a(red: u8, green: u8, blue: u8, alpha: u8) -> Pixel {
+    pub fn rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Pixel {
         Pixel { red, green, blue, alpha }
     }
 
+    #[deprecated(note = "misspelled, use Pixel::rgba")]
+    #[inline]
+    pub fn rbg_a(red: u8, green: u8, blue: u8, alpha: u8) -> Pixel {
+        Pixel::rgba(red, green, blue, alpha)
+    }
+
     #[inline]
     pub fn rgb(red: u8, green: u8, blue: u8) -> Pixel {
         Pixel { red, green, blue, alpha: 255 }
     }
 
+    #[inline]
+    pub fn with_alpha(self, alpha: u8) -> Pixel {
+        Pixel { alpha, ..self }
+    }
+
+    // 0x00RRGGBB, the alpha is ignored and set to opaque
     #[inline]
     pub fn from_rgb(i: u32) -> Pixel {
-        let red = (i / (256 ^ 2)) as u8;
-        let green = ((i / 256) % 256) as u8;
-        let blue = (i % 256) as u8;
-        Pixel::rgb(red, green, blue)
+        Pixel::rgb((i >> 16) as u8, (i >> 8) as u8, i as u8)
     }
 
     #[inline]
@@ -136,6 +215,128 @@ impl Pixel {
         (r << 16) | (g << 8) | b
     }
 
+    // 0xAARRGGBB
+    #[inline]
+    pub fn from_argb(i: u32) -> Pixel {
+        Pixel::rgba((i >> 16) as u8, (i >> 8) as u8, i as u8, (i >> 24) as u8)
+    }
+
+    #[inline]
+    pub fn to_argb(&self) -> u32 {
+        ((self.alpha as u32) << 24) | self.to_rgb()
+    }
+
+    // 0xRRGGBBAA
+    #[inline]
+    pub fn from_rgba(i: u32) -> Pixel {
+        Pixel::rgba((i >> 24) as u8, (i >> 16) as u8, (i >> 8) as u8, i as u8)
+    }
+
+    #[inline]
+    pub fn to_rgba(&self) -> u32 {
+        (self.to_rgb() << 8) | self.alpha as u32
+    }
+
+    // "#rrggbb" or "#rrggbbaa", the leading '#' is optional
+    pub fn from_hex(hex: &str) -> YapeResult<Pixel> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        let invalid = || YapeError::new(YapeErrorKind::Decode, format!("invalid hex color '{}'", hex).as_str());
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let value = u32::from_str_radix(digits, 16).map_err(|_| invalid())?;
+        match digits.len() {
+            6 => Ok(Pixel::from_rgb(value)),
+            8 => Ok(Pixel::from_rgba(value)),
+            _ => Err(invalid()),
+        }
+    }
+
+    pub fn to_hex(&self) -> String {
+        format!("#{:08x}", self.to_rgba())
+    }
+
+    // Hue in degrees [0, 360), saturation and value in [0, 1]
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Pixel {
+        let chroma = value * saturation;
+        Pixel::from_hue_chroma(hue, chroma, value - chroma)
+    }
+
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+        (hue, saturation, max)
+    }
+
+    // Hue in degrees [0, 360), saturation and lightness in [0, 1]
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Pixel {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Pixel::from_hue_chroma(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min { 0.0 } else { (max - min) / (1.0 - (2.0 * lightness - 1.0).abs()) };
+        (hue, saturation, lightness)
+    }
+
+    // https://en.wikipedia.org/wiki/HSL_and_HSV#Color_conversion_formulae
+    fn from_hue_chroma(hue: f32, chroma: f32, m: f32) -> Pixel {
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let channel = |c: f32| ((c + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Pixel::rgb(channel(r), channel(g), channel(b))
+    }
+
+    fn hue_max_min(&self) -> (f32, f32, f32) {
+        let (r, g, b) = (self.red as f32 / 255.0, self.green as f32 / 255.0, self.blue as f32 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (hue, max, min)
+    }
+
+    // Per channel linear interpolation in sRGB space, t = 0 is self and t = 1 is other
+    pub fn lerp(&self, other: &Pixel, t: f32) -> Pixel {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Pixel::rgba(
+            mix(self.red, other.red),
+            mix(self.green, other.green),
+            mix(self.blue, other.blue),
+            mix(self.alpha, other.alpha),
+        )
+    }
+
+    // Interpolates in linear light, avoiding the dark band lerp gives between saturated colors
+    pub fn mix_gamma(&self, other: &Pixel, t: f32) -> Pixel {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| {
+            let (a, b) = (srgb_to_linear(a), srgb_to_linear(b));
+            linear_to_srgb(a + (b - a) * t)
+        };
+        let alpha = (self.alpha as f32 + (other.alpha as f32 - self.alpha as f32) * t).round() as u8;
+        Pixel::rgba(mix(self.red, other.red), mix(self.green, other.green), mix(self.blue, other.blue), alpha)
+    }
+
     #[inline]
     pub fn calculate_alpha(&self, depth: &Pixel, blend_factor: f32) -> Pixel {
         let a: f32 = (self.alpha as f32 / 255.0) * blend_factor;
@@ -145,14 +346,25 @@ impl Pixel {
         let b = (a * self.blue as f32 + c * depth.blue as f32) as u8;
         Pixel::rgb(r, g, b)
     }
-}
 
-impl std::clone::Clone for Pixel {
-    fn clone(&self) -> Self {
-        Pixel::rbg_a(self.red, self.green, self.blue, self.alpha)
+    pub fn calculate_additive(&self, depth: &Pixel, blend_factor: f32) -> Pixel {
+        let a: f32 = (self.alpha as f32 / 255.0) * blend_factor;
+        let add = |src: u8, dst: u8| (dst as f32 + a * src as f32).min(255.0) as u8;
+        Pixel::rgb(add(self.red, depth.red), add(self.green, depth.green), add(self.blue, depth.blue))
     }
 }
 
+// https://en.wikipedia.org/wiki/SRGB#Transformation
+fn srgb_to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(c: f32) -> u8 {
+    let c = if c <= 0.003_130_8 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+    (c * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
 pub enum SpriteMode {
     Normal, Periodic
 }
@@ -175,6 +387,14 @@ impl Sprite {
         }
     }
 
+    pub fn load_png<P: AsRef<Path>>(path: P) -> YapeResult<Sprite> {
+        capture::load_png(path)
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> YapeResult<()> {
+        capture::save_sprite_png(self, path)
+    }
+
     pub fn get_pixel(&self, x: u32, y: u32) -> &Pixel {
         match self.mode {
             SpriteMode::Normal => {
@@ -186,7 +406,7 @@ impl Sprite {
                 }
             }
             _ => {
-                let index = ((y % self.height) + (x % self.width)) as usize;
+                let index = ((y % self.height) * self.width + (x % self.width)) as usize;
                 &self.data[index]
             }
         }
@@ -195,7 +415,7 @@ impl Sprite {
     pub fn set_pixel(&mut self, x: u32, y: u32, pixel: &Pixel) {
         if x < self.width && y < self.height {
             let index = (y * self.width + x) as usize;
-            self.data[index] = pixel.clone();
+            self.data[index] = *pixel;
         }
     }
 
@@ -205,12 +425,36 @@ impl Sprite {
         self.get_pixel(sx, sy)
     }
 
-    pub fn clear(&mut self, pixel: &Pixel) {
-        for i in self.data.iter_mut() {
-            *i = pixel.clone()
+    // Nearest texel at normalized coordinates, (0, 0) top left and (1, 1) bottom right
+    pub fn sample_nearest(&self, x: f32, y: f32) -> &Pixel {
+        if self.width == 0 || self.height == 0 {
+            return &BLANK;
+        }
+        let sx: u32 = cmp::min((x * self.width as f32) as u32, self.width - 1);
+        let sy: u32 = cmp::min((y * self.height as f32) as u32, self.height - 1);
+        self.get_pixel(sx, sy)
+    }
+
+    pub fn sample_bilinear(&self, x: f32, y: f32) -> Pixel {
+        transform::bilinear(self, x, y)
+    }
+
+    // Region connected to (x, y) whose colors are within tolerance of its color on every channel
+    pub fn select_region(&self, x: u32, y: u32, tolerance: u8) -> Mask {
+        flood::select(self, x, y, tolerance)
+    }
+
+    // Packs the sprite as 0RGB u32s, the format minifb presents
+    pub fn write_rgb(&self, out: &mut [u32]) {
+        for (rgb, p) in out.iter_mut().zip(self.data.iter()) {
+            *rgb = u32::from_be_bytes([p.red, p.green, p.blue, p.alpha]) >> 8;
         }
     }
 
+    pub fn clear(&mut self, pixel: &Pixel) {
+        self.data.fill(*pixel);
+    }
+
 }
 
 pub trait YapeEngineApi {
@@ -231,11 +475,282 @@ pub trait YapeEngineApi {
     fn draw_line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, pixel: &Pixel);
     fn draw_circle(&mut self, x: u32, y: u32, radius: u32, pixel: &Pixel);
     fn draw_rect(&mut self, x: u32, y: u32, w: u32, h: u32, pixel: &Pixel);
+    #[allow(clippy::too_many_arguments)]
     fn draw_triangle(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, x3: u32, y3: u32, pixel: &Pixel);
     fn fill_circle(&mut self, x: u32, y: u32, radius: u32, pixel: &Pixel);
     fn fill_rect(&mut self, x: u32, y: u32, w: u32, h: u32, pixel: &Pixel);
+    #[allow(clippy::too_many_arguments)]
     fn fill_triangle(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, x3: u32, y3: u32, pixel: &Pixel);
     fn clear(&mut self, pixel: &Pixel);
+
+    // The methods below have default bodies so implementors written against older versions of
+    // the trait keep compiling, engines override them with faster or stateful versions
+
+    // Horizontal run from x1 to x2 (inclusive) on row y, clipped to the screen
+    fn fill_span(&mut self, x1: i32, x2: i32, y: i32, pixel: &Pixel) {
+        let (w, h) = (self.get_screen_width() as i32, self.get_screen_height() as i32);
+        if y < 0 || y >= h {
+            return;
+        }
+        for x in cmp::max(cmp::min(x1, x2), 0)..=cmp::min(cmp::max(x1, x2), w - 1) {
+            self.draw_pixel(x as u32, y as u32, pixel);
+        }
+    }
+
+    fn draw_partial_sprite(&mut self, pos: Vi2d, sprite: &Sprite, source: &Recti, scale: u32) {
+        let bounds = Recti::new(0, 0, sprite.width as i32, sprite.height as i32);
+        let source = match source.clip(&bounds) {
+            Some(source) => source,
+            None => return,
+        };
+        let scale = cmp::max(scale, 1) as i32;
+        for sy in 0..source.h {
+            for sx in 0..source.w {
+                let pixel = *sprite.get_pixel((source.x + sx) as u32, (source.y + sy) as u32);
+                let x = pos.x + sx * scale;
+                let y = pos.y + sy * scale;
+                for row in y..y + scale {
+                    self.fill_span(x, x + scale - 1, row, &pixel);
+                }
+            }
+        }
+    }
+
+    fn draw_line_aa(&mut self, p1: Vf2d, p2: Vf2d, pixel: &Pixel) {
+        antialias::line(self, p1, p2, pixel);
+    }
+
+    fn draw_circle_aa(&mut self, center: Vi2d, radius: f32, pixel: &Pixel) {
+        antialias::circle(self, center, radius, pixel);
+    }
+
+    fn draw_thick_line(&mut self, p1: Vf2d, p2: Vf2d, width: f32, cap: LineCap, pixel: &Pixel) {
+        antialias::thick_line(self, p1, p2, width, cap, pixel);
+    }
+
+    // Closed outline through all points
+    fn draw_polygon(&mut self, points: &[Vi2d], pixel: &Pixel) {
+        polygon::draw(self, points, pixel);
+    }
+
+    fn fill_polygon(&mut self, points: &[Vi2d], rule: FillRule, pixel: &Pixel) {
+        polygon::fill(self, points, rule, pixel);
+    }
+
+    fn draw_quadratic_bezier(&mut self, p0: Vf2d, p1: Vf2d, p2: Vf2d, pixel: &Pixel) {
+        curves::quadratic_bezier(self, p0, p1, p2, pixel);
+    }
+
+    fn draw_cubic_bezier(&mut self, p0: Vf2d, p1: Vf2d, p2: Vf2d, p3: Vf2d, pixel: &Pixel) {
+        curves::cubic_bezier(self, p0, p1, p2, p3, pixel);
+    }
+
+    // Angles in radians, clockwise on screen from the positive x axis
+    fn draw_arc(&mut self, center: Vi2d, radius: i32, start_angle: f32, end_angle: f32, pixel: &Pixel) {
+        curves::arc(self, center, radius, start_angle, end_angle, pixel);
+    }
+
+    fn draw_pie(&mut self, center: Vi2d, radius: i32, start_angle: f32, end_angle: f32, pixel: &Pixel) {
+        curves::pie(self, center, radius, start_angle, end_angle, pixel);
+    }
+
+    fn fill_pie(&mut self, center: Vi2d, radius: i32, start_angle: f32, end_angle: f32, pixel: &Pixel) {
+        curves::fill_pie(self, center, radius, start_angle, end_angle, pixel);
+    }
+
+    fn draw_rounded_rect(&mut self, pos: Vi2d, size: Vi2d, radius: i32, pixel: &Pixel) {
+        curves::rounded_rect(self, pos, size, radius, pixel);
+    }
+
+    fn fill_rounded_rect(&mut self, pos: Vi2d, size: Vi2d, radius: i32, pixel: &Pixel) {
+        curves::fill_rounded_rect(self, pos, size, radius, pixel);
+    }
+
+    fn draw_sprite(&mut self, pos: Vi2d, sprite: &Sprite, scale: u32) {
+        let source = Recti::new(0, 0, sprite.width as i32, sprite.height as i32);
+        self.draw_partial_sprite(pos, sprite, &source, scale);
+    }
+
+    fn draw_sprite_transformed(&mut self, sprite: &Sprite, transform: &Transform2D, sampling: Sampling) {
+        transform::draw_sprite_transformed(self, sprite, transform, sampling);
+    }
+
+    fn get_mouse_pos(&self) -> Vi2d {
+        Vi2d::new(self.get_mouse_x() as i32, self.get_mouse_y() as i32)
+    }
+
+    fn get_screen_size(&self) -> Vi2d {
+        Vi2d::new(self.get_screen_width() as i32, self.get_screen_height() as i32)
+    }
+
+    // World space drawing through a camera, _w variants take world coordinates
+
+    fn draw_line_w(&mut self, camera: &Camera2D, p1: Vf2d, p2: Vf2d, pixel: &Pixel) {
+        let (a, b) = (camera::to_pixel(camera.world_to_screen(p1)), camera::to_pixel(camera.world_to_screen(p2)));
+        self.draw_line_v(a, b, pixel);
+    }
+
+    fn draw_circle_w(&mut self, camera: &Camera2D, center: Vf2d, radius: f32, pixel: &Pixel) {
+        let center = camera::to_pixel(camera.world_to_screen(center));
+        let radius = (radius * camera.zoom).round() as i32;
+        self.draw_circle_v(center, radius, pixel);
+    }
+
+    fn fill_circle_w(&mut self, camera: &Camera2D, center: Vf2d, radius: f32, pixel: &Pixel) {
+        let center = camera::to_pixel(camera.world_to_screen(center));
+        let radius = (radius * camera.zoom).round() as i32;
+        self.fill_circle_v(center, radius, pixel);
+    }
+
+    fn draw_rect_w(&mut self, camera: &Camera2D, rect: &Rectf, pixel: &Pixel) {
+        self.draw_polygon_w(camera, &camera::rect_corners(rect), pixel);
+    }
+
+    fn fill_rect_w(&mut self, camera: &Camera2D, rect: &Rectf, pixel: &Pixel) {
+        camera::fill_polygon(self, camera, &camera::rect_corners(rect), FillRule::NonZero, pixel);
+    }
+
+    fn fill_triangle_w(&mut self, camera: &Camera2D, p1: Vf2d, p2: Vf2d, p3: Vf2d, pixel: &Pixel) {
+        camera::fill_polygon(self, camera, &[p1, p2, p3], FillRule::NonZero, pixel);
+    }
+
+    fn draw_polygon_w(&mut self, camera: &Camera2D, points: &[Vf2d], pixel: &Pixel) {
+        let points: Vec<Vi2d> = points.iter().map(|p| camera::to_pixel(camera.world_to_screen(*p))).collect();
+        self.draw_polygon(&points, pixel);
+    }
+
+    fn fill_polygon_w(&mut self, camera: &Camera2D, points: &[Vf2d], rule: FillRule, pixel: &Pixel) {
+        camera::fill_polygon(self, camera, points, rule, pixel);
+    }
+
+    fn draw_sprite_w(&mut self, camera: &Camera2D, pos: Vf2d, sprite: &Sprite, sampling: Sampling) {
+        camera::draw_sprite(self, camera, pos, sprite, sampling);
+    }
+
+    fn draw_pixel_v(&mut self, pos: Vi2d, pixel: &Pixel) {
+        raster::plot(self, pos.x, pos.y, pixel);
+    }
+
+    fn draw_line_v(&mut self, p1: Vi2d, p2: Vi2d, pixel: &Pixel) {
+        raster::line(p1.x, p1.y, p2.x, p2.y, |x, y| raster::plot(self, x, y, pixel));
+    }
+
+    fn draw_circle_v(&mut self, center: Vi2d, radius: i32, pixel: &Pixel) {
+        raster::circle(center.x, center.y, radius, |x, y| raster::plot(self, x, y, pixel));
+    }
+
+    fn draw_rect_v(&mut self, pos: Vi2d, size: Vi2d, pixel: &Pixel) {
+        let end = pos + size;
+        self.draw_line_v(pos, Vi2d::new(end.x, pos.y), pixel);
+        self.draw_line_v(pos, Vi2d::new(pos.x, end.y), pixel);
+        self.draw_line_v(end, Vi2d::new(end.x, pos.y), pixel);
+        self.draw_line_v(end, Vi2d::new(pos.x, end.y), pixel);
+    }
+
+    fn draw_rect_r(&mut self, rect: &Recti, pixel: &Pixel) {
+        self.draw_rect_v(rect.pos(), rect.size(), pixel);
+    }
+
+    fn draw_line_pattern(&mut self, p1: Vi2d, p2: Vi2d, pattern: u32, pixel: &Pixel) {
+        stroke::polyline(self, &[p1, p2], LineStyle::Pattern(pattern), pixel);
+    }
+
+    fn draw_line_styled(&mut self, p1: Vi2d, p2: Vi2d, style: LineStyle, pixel: &Pixel) {
+        stroke::polyline(self, &[p1, p2], style, pixel);
+    }
+
+    // Open path through all points, repeat the first point to close it
+    fn draw_polyline_styled(&mut self, points: &[Vi2d], style: LineStyle, pixel: &Pixel) {
+        stroke::polyline(self, points, style, pixel);
+    }
+
+    fn draw_rect_styled(&mut self, pos: Vi2d, size: Vi2d, style: LineStyle, pixel: &Pixel) {
+        stroke::rect(self, pos, size, style, pixel);
+    }
+
+    fn draw_circle_styled(&mut self, center: Vi2d, radius: i32, style: LineStyle, pixel: &Pixel) {
+        stroke::circle(self, center, radius, style, pixel);
+    }
+
+    fn draw_triangle_v(&mut self, p1: Vi2d, p2: Vi2d, p3: Vi2d, pixel: &Pixel) {
+        self.draw_line_v(p1, p2, pixel);
+        self.draw_line_v(p2, p3, pixel);
+        self.draw_line_v(p3, p1, pixel);
+    }
+
+    fn fill_circle_v(&mut self, center: Vi2d, radius: i32, pixel: &Pixel) {
+        let height = self.get_screen_height() as i32;
+        raster::circle_spans(center.x, center.y, radius, height, |x1, x2, y| self.fill_span(x1, x2, y, pixel));
+    }
+
+    fn fill_rect_v(&mut self, pos: Vi2d, size: Vi2d, pixel: &Pixel) {
+        let height = self.get_screen_height() as i32;
+        raster::rect_spans(pos.x, pos.y, size.x, size.y, height, |x1, x2, y| self.fill_span(x1, x2, y, pixel));
+    }
+
+    fn fill_rect_r(&mut self, rect: &Recti, pixel: &Pixel) {
+        self.fill_rect_v(rect.pos(), rect.size(), pixel);
+    }
+
+    fn fill_triangle_v(&mut self, p1: Vi2d, p2: Vi2d, p3: Vi2d, pixel: &Pixel) {
+        let height = self.get_screen_height() as i32;
+        raster::triangle_spans((p1.x, p1.y), (p2.x, p2.y), (p3.x, p3.y), height, |x1, x2, y| {
+            self.fill_span(x1, x2, y, pixel)
+        });
+    }
+
+    fn fill_rect_with(&mut self, pos: Vi2d, size: Vi2d, paint: &Paint) {
+        let height = self.get_screen_height() as i32;
+        raster::rect_spans(pos.x, pos.y, size.x, size.y, height, |x1, x2, y| paint::span(self, x1, x2, y, paint));
+    }
+
+    fn fill_circle_with(&mut self, center: Vi2d, radius: i32, paint: &Paint) {
+        let height = self.get_screen_height() as i32;
+        raster::circle_spans(center.x, center.y, radius, height, |x1, x2, y| paint::span(self, x1, x2, y, paint));
+    }
+
+    fn fill_triangle_with(&mut self, p1: Vi2d, p2: Vi2d, p3: Vi2d, paint: &Paint) {
+        let height = self.get_screen_height() as i32;
+        raster::triangle_spans((p1.x, p1.y), (p2.x, p2.y), (p3.x, p3.y), height, |x1, x2, y| {
+            paint::span(self, x1, x2, y, paint)
+        });
+    }
+
+    fn fill_polygon_with(&mut self, points: &[Vi2d], rule: FillRule, paint: &Paint) {
+        paint::fill_polygon(self, points, rule, paint);
+    }
+
+    fn fill_mask(&mut self, mask: &Mask, pixel: &Pixel) {
+        for (x1, x2, y) in mask.spans() {
+            self.fill_span(x1 as i32, x2 as i32, y as i32, pixel);
+        }
+    }
+
+    fn save_screenshot(&self, _path: &Path) -> YapeResult<()> {
+        Err(YapeError::new(YapeErrorKind::Encode, "screenshots are not supported by this engine"))
+    }
+
+    fn record_frames(&mut self, _path: &Path, _frames: u32, _format: RecordFormat) -> YapeResult<()> {
+        Err(YapeError::new(YapeErrorKind::Encode, "recording is not supported by this engine"))
+    }
+
+    fn is_recording(&self) -> bool {
+        false
+    }
+
+    fn start_gif_recording(&mut self, _max_duration: f32) -> YapeResult<()> {
+        Err(YapeError::new(YapeErrorKind::Encode, "gif recording is not supported by this engine"))
+    }
+
+    fn stop_gif_recording(&mut self) {}
+
+    fn is_gif_recording(&self) -> bool {
+        false
+    }
+
+    fn save_gif(&self, _path: &Path) -> YapeResult<()> {
+        Err(YapeError::new(YapeErrorKind::Encode, "gif recording is not supported by this engine"))
+    }
 }
 
 pub trait YapeCallback {
@@ -243,6 +758,9 @@ pub trait YapeCallback {
     fn on_update(&mut self, engine: &mut dyn YapeEngineApi, time_elapsed: f32) -> YapeResult<bool>;
 }
 
+const KEY_COUNT: usize = KeyboardKey::RightAlt as usize + 1;
+const MOUSE_BUTTON_COUNT: usize = MouseButton::Right as usize + 1;
+
 pub struct YapeEngine {
     app_name: String,
     screen_w: u32,
@@ -252,16 +770,22 @@ pub struct YapeEngine {
     pixel_mode: PixelMode,
     blend_factor: f32,
     active: bool,
-    minifb_window: minifb::Window,
+    minifb_window: Option<minifb::Window>,
     is_window_active: bool,
     buffer: Sprite,
     last_fps: u32,
     mouse_pos_x: u32,
     mouse_pos_y: u32,
     mouse_wheel_delta: i32,
-    mouse_button_state: HashMap<MouseButton, (ButtonState, bool)>,
-    keyboard_state: HashMap<KeyboardKey, ButtonState>,
-    minifb_key_mapping: HashMap<minifb::Key, KeyboardKey>,
+    mouse_button_state: [(ButtonState, bool); MOUSE_BUTTON_COUNT],
+    keyboard_state: [ButtonState; KEY_COUNT],
+    minifb_key_mapping: Vec<(minifb::Key, KeyboardKey)>,
+    screenshot_key: Option<KeyboardKey>,
+    frame_recorder: Option<FrameRecorder>,
+    gif_recorder: Option<GifRecorder>,
+    capture_error: Option<YapeError>,
+    #[cfg(feature = "audio")]
+    audio_output: Option<audio::AudioOutput>,
 }
 
 impl YapeEngine {
@@ -281,48 +805,60 @@ impl YapeEngine {
             options
         )?;
 
-        let mouse_button_state: HashMap<MouseButton, (ButtonState, bool)> = vec![
-            (MouseButton::Left, (ButtonState::empty(), false)),
-            (MouseButton::Middle, (ButtonState::empty(), false)),
-            (MouseButton::Right, (ButtonState::empty(), false)),
-        ].into_iter().collect();
+        let mut engine = YapeEngine::construct_headless(screen_w, screen_h);
+        engine.app_name = app_name.to_string();
+        engine.pixel_w = pixel_w;
+        engine.pixel_h = pixel_h;
+        engine.minifb_window = Some(window);
+        Ok(engine)
+    }
 
-        let minifb_key_mapping: HashMap<Key, KeyboardKey> = YapeEngine::build_minifb_key_mapping();
-        let mut keyboard_state: HashMap<KeyboardKey, ButtonState> = HashMap::new();
-        for (_, v) in minifb_key_mapping.iter() {
-            keyboard_state.insert(v.clone(), ButtonState::empty());
+    // An engine without a window, for drawing off screen, benchmarks and tests. It can't be started.
+    pub fn construct_headless(screen_w: u32, screen_h: u32) -> YapeEngine {
+        YapeEngine {
+            app_name: String::new(),
+            screen_w,
+            screen_h,
+            pixel_w: 1,
+            pixel_h: 1,
+            pixel_mode: PixelMode::Normal,
+            blend_factor: 1.0,
+            active: true,
+            minifb_window: None,
+            is_window_active: false,
+            buffer: Sprite::new(screen_w, screen_h),
+            last_fps: 0,
+            mouse_pos_x: 0,
+            mouse_pos_y: 0,
+            mouse_wheel_delta: 0,
+            mouse_button_state: [(BUTTON_STATE_EMPTY, false); MOUSE_BUTTON_COUNT],
+            keyboard_state: [BUTTON_STATE_EMPTY; KEY_COUNT],
+            minifb_key_mapping: YapeEngine::build_minifb_key_mapping(),
+            screenshot_key: None,
+            frame_recorder: None,
+            gif_recorder: None,
+            capture_error: None,
+            #[cfg(feature = "audio")]
+            audio_output: None,
         }
-
-        Ok(
-            YapeEngine {
-                app_name: app_name.to_string(),
-                screen_w,
-                screen_h,
-                pixel_w,
-                pixel_h,
-                pixel_mode: PixelMode::Normal,
-                blend_factor: 1.0,
-                active: true,
-                minifb_window: window,
-                is_window_active: false,
-                buffer: Sprite::new(screen_w, screen_h),
-                last_fps: 0,
-                mouse_pos_x: 0,
-                mouse_pos_y: 0,
-                mouse_wheel_delta: 0,
-                mouse_button_state,
-                keyboard_state,
-                minifb_key_mapping
-            }
-        )
     }
 
     pub fn start(&mut self, callback: &mut dyn YapeCallback) -> YapeResult<()> {
+        let mut window = match self.minifb_window.take() {
+            Some(window) => window,
+            None => return Err(YapeError::new(YapeErrorKind::Window, "a headless engine can't be started")),
+        };
+        let result = self.run(&mut window, callback);
+        self.minifb_window = Some(window);
+        result
+    }
+
+    fn run(&mut self, window: &mut minifb::Window, callback: &mut dyn YapeCallback) -> YapeResult<()> {
         if !callback.on_create()? {
             self.active = false;
         }
 
-        self.minifb_window.limit_update_rate(None);
+        window.limit_update_rate(None);
 
         let mut last_frame = time::Instant::now();
         let mut frame_counter: u32 = 0;
@@ -330,27 +866,24 @@ impl YapeEngine {
 
         let mut rgb_buffer: Vec<u32> = vec![0; self.buffer.data.len()];
 
-        while self.active && self.minifb_window.is_open() {
+        while self.active && window.is_open() {
             let current_frame = time::Instant::now();
             let elapsed = (current_frame - last_frame).as_secs_f32();
             last_frame = current_frame;
 
-            self.is_window_active = self.minifb_window.is_active();
+            self.is_window_active = window.is_active();
 
-            if let Some((screen_x, screen_y)) = self.minifb_window.get_mouse_pos(minifb::MouseMode::Discard) {
+            if let Some((screen_x, screen_y)) = window.get_mouse_pos(minifb::MouseMode::Discard) {
                 self.mouse_pos_x = screen_x as u32 / self.pixel_w;
                 self.mouse_pos_y = screen_y as u32 / self.pixel_h;
             }
-            if let Some((_wheel_x, wheel_y)) = self.minifb_window.get_scroll_wheel() {
+            if let Some((_wheel_x, wheel_y)) = window.get_scroll_wheel() {
                 self.mouse_wheel_delta = wheel_y as i32;
             }
 
-            for (button, (state, old_state)) in self.mouse_button_state.iter_mut() {
-                let new_state = match button {
-                    MouseButton::Left => self.minifb_window.get_mouse_down(minifb::MouseButton::Left),
-                    MouseButton::Middle => self.minifb_window.get_mouse_down(minifb::MouseButton::Middle),
-                    MouseButton::Right => self.minifb_window.get_mouse_down(minifb::MouseButton::Right),
-                };
+            let minifb_buttons = [minifb::MouseButton::Left, minifb::MouseButton::Middle, minifb::MouseButton::Right];
+            for ((state, old_state), minifb_button) in self.mouse_button_state.iter_mut().zip(minifb_buttons.iter()) {
+                let new_state = window.get_mouse_down(*minifb_button);
                 state.pressed = false;
                 state.released = false;
                 if new_state != *old_state {
@@ -366,24 +899,30 @@ impl YapeEngine {
             }
 
             for (minifb_key, key) in self.minifb_key_mapping.iter() {
-                match self.keyboard_state.get_mut(key) {
-                    Some(state) => {
-                        state.held = self.minifb_window.is_key_down(minifb_key.clone());
-                        state.released = self.minifb_window.is_key_released(minifb_key.clone());
-                        state.pressed = self.minifb_window.is_key_pressed(minifb_key.clone(), minifb::KeyRepeat::No);
-                    },
-                    _ => (),
-                }
+                let state = &mut self.keyboard_state[*key as usize];
+                state.held = window.is_key_down(*minifb_key);
+                state.released = window.is_key_released(*minifb_key);
+                state.pressed = window.is_key_pressed(*minifb_key, minifb::KeyRepeat::No);
             }
 
             if !callback.on_update(self, elapsed)? {
                 self.active = false;
             }
 
-            for (i, p) in self.buffer.data.iter().enumerate() {
-                rgb_buffer[i] = p.to_rgb();
+            // A failed capture shouldn't end the game, keep the error for take_capture_error
+            if let Some(key) = self.screenshot_key {
+                if self.get_key_state(&key).pressed {
+                    if let Err(error) = self.save_screenshot(&self.screenshot_file_name()) {
+                        self.capture_error = Some(error);
+                    }
+                }
             }
-            self.minifb_window.update_with_buffer(&rgb_buffer, self.screen_w as usize, self.screen_h as usize)?;
+            if let Err(error) = self.capture_frame(elapsed) {
+                self.capture_error = Some(error);
+            }
+
+            self.buffer.write_rgb(&mut rgb_buffer);
+            window.update_with_buffer(&rgb_buffer, self.screen_w as usize, self.screen_h as usize)?;
 
             self.mouse_wheel_delta = 0;
 
@@ -394,13 +933,79 @@ impl YapeEngine {
                 frame_timer -= 1.0;
                 frame_counter = 0;
                 let new_title = format!("{} - {} fps", self.app_name, self.last_fps);
-                self.minifb_window.set_title(new_title.as_str());
+                window.set_title(new_title.as_str());
+            }
+        }
+        Ok(())
+    }
+
+    // Hands the frame drawn so far to the active recorders, the game loop calls it after every
+    // update and headless engines call it themselves. A frame range recording that fails to
+    // write is stopped
+    pub fn capture_frame(&mut self, time_elapsed: f32) -> YapeResult<()> {
+        if let Some(recorder) = self.gif_recorder.as_mut() {
+            recorder.capture(&self.buffer, time_elapsed);
+        }
+        if let Some(recorder) = self.frame_recorder.as_mut() {
+            match recorder.capture(&self.buffer, time_elapsed) {
+                Ok(true) => {}
+                Ok(false) => self.frame_recorder = None,
+                Err(error) => {
+                    self.frame_recorder = None;
+                    return Err(error);
+                }
             }
         }
         Ok(())
     }
 
-    fn build_minifb_key_mapping() -> HashMap<minifb::Key, KeyboardKey> {
+    pub fn get_draw_target(&self) -> &Sprite {
+        &self.buffer
+    }
+
+    // Fills the region select_region finds on the screen and returns it
+    pub fn flood_fill(&mut self, x: u32, y: u32, pixel: &Pixel, tolerance: u8) -> Mask {
+        let mask = self.buffer.select_region(x, y, tolerance);
+        self.fill_mask(&mask, pixel);
+        mask
+    }
+
+    // The last screenshot or recording error the game loop hit since the previous call
+    pub fn take_capture_error(&mut self) -> Option<YapeError> {
+        self.capture_error.take()
+    }
+
+    pub fn set_screenshot_key(&mut self, key: Option<KeyboardKey>) {
+        self.screenshot_key = key;
+    }
+
+    fn screenshot_file_name(&self) -> PathBuf {
+        let millis = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        let name: String = self.app_name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        PathBuf::from(format!("{}_{}.png", name, millis))
+    }
+
+    // Opens the default output device, the returned synth is mixed into it until the engine is dropped
+    #[cfg(feature = "audio")]
+    pub fn enable_sound(&mut self) -> YapeResult<std::sync::Arc<std::sync::Mutex<synth::Synth>>> {
+        let (output, synth) = audio::AudioOutput::open()?;
+        self.audio_output = Some(output);
+        Ok(synth)
+    }
+
+    // Errors the output stream hit since the last call, they happen on the audio thread
+    #[cfg(feature = "audio")]
+    pub fn take_sound_error(&mut self) -> Option<YapeError> {
+        self.audio_output.as_ref().and_then(|output| output.take_error())
+    }
+
+    fn build_minifb_key_mapping() -> Vec<(minifb::Key, KeyboardKey)> {
         vec![
             (minifb::Key::Key0, KeyboardKey::Key0),
             (minifb::Key::Key1, KeyboardKey::Key1),
@@ -474,7 +1079,7 @@ impl YapeEngine {
             (minifb::Key::RightCtrl, KeyboardKey::RightCtrl),
             (minifb::Key::LeftAlt, KeyboardKey::LeftAlt),
             (minifb::Key::RightAlt, KeyboardKey::RightAlt),
-        ].into_iter().collect()
+        ]
     }
 }
 
@@ -484,16 +1089,11 @@ impl YapeEngineApi for YapeEngine {
     }
 
     fn get_key_state(&self, key: &KeyboardKey) -> &ButtonState {
-        &self.keyboard_state
-            .get(key)
-            .unwrap_or(&BUTTON_STATE_EMPTY)
+        &self.keyboard_state[*key as usize]
     }
 
     fn get_mouse_button_state(&self, button: &MouseButton) -> &ButtonState {
-        &self.mouse_button_state
-            .get(button)
-            .map(|(button, _)| button)
-            .unwrap_or(&BUTTON_STATE_EMPTY)
+        &self.mouse_button_state[*button as usize].0
     }
 
     fn get_mouse_x(&self) -> u32 {
@@ -540,180 +1140,170 @@ impl YapeEngineApi for YapeEngine {
         match self.pixel_mode {
             PixelMode::Normal => self.buffer.set_pixel(x, y, pixel),
             PixelMode::Alpha => {
-                let background = self.buffer.get_pixel(x, y).clone();
+                let background = *self.buffer.get_pixel(x, y);
                 self.buffer.set_pixel(x, y, &pixel.calculate_alpha(&background, self.blend_factor));
             },
             PixelMode::Mask => if pixel.alpha == 255 {
                 self.buffer.set_pixel(x, y, pixel);
+            },
+            PixelMode::Additive => {
+                let background = *self.buffer.get_pixel(x, y);
+                self.buffer.set_pixel(x, y, &pixel.calculate_additive(&background, self.blend_factor));
             }
         }
     }
 
-    fn draw_line(&mut self, _x1: u32, _y1: u32, _x2: u32, _y2: u32, pixel: &Pixel) {
-        // Bresenham's line algorithm
-        // https://www.freecodecamp.org/news/how-to-code-your-first-algorithm-draw-a-line-ca121f9a1395/
-        let x1: u32 = _x1;
-        let y1: u32 = _y1;
-        let x2: u32 = _x2;
-        let y2: u32 = _y2;
-
-        let mut x: u32;
-        let mut y: u32;
-
-        // Calculate line deltas
-        let dx: i32 = x2 as i32 - x1 as i32;
-        let dy: i32 = y2 as i32 - y1 as i32;
-        // Create a positive copy of deltas (makes iterating easier)
-        let dx1: i32 = i32::abs(dx);
-        let dy1: i32 = i32::abs(dy);
-        // Calculate error intervals for both axis
-        let mut px: i32 = 2 * dy1 - dx1;
-        let mut py: i32 = 2 * dx1 - dy1;
-        let xe: u32;
-        let ye: u32;
-
-        // The line is X-axis dominant
-        if dy1 <= dx1 {
-
-            if dx >= 0 {
-                // Line is drawn left to right
-                x = x1; y = y1; xe = x2;
-            } else {
-                // Line is drawn right to left (swap ends)
-                x = x2; y = y2; xe = x1;
-            }
+    fn draw_line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, pixel: &Pixel) {
+        raster::line(x1 as i32, y1 as i32, x2 as i32, y2 as i32, |x, y| raster::plot(self, x, y, pixel));
+    }
 
-            self.draw_pixel(x, y, pixel); // Draw first pixel
+    fn draw_circle(&mut self, x: u32, y: u32, radius: u32, pixel: &Pixel) {
+        raster::circle(x as i32, y as i32, radius as i32, |x, y| raster::plot(self, x, y, pixel));
+    }
+
+    fn draw_rect(&mut self, x: u32, y: u32, w: u32, h: u32, _pixel: &Pixel) {
+        self.draw_line(x, y, x + w, y, _pixel);
+        self.draw_line(x, y, x, y + h, _pixel);
+        self.draw_line(x + w, y + h, x + w, y, _pixel);
+        self.draw_line(x + w, y + h, x, y + h, _pixel);
+    }
 
-            // Rasterize the line
-            for _ in x..xe {
-                x = x + 1;
+    fn draw_triangle(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, x3: u32, y3: u32, pixel: &Pixel) {
+        self.draw_line(x1, y1, x2, y2, pixel);
+        self.draw_line(x2, y2, x3, y3, pixel);
+        self.draw_line(x3, y3, x1, y1, pixel);
+    }
 
-                // Deal with octants...
-                if px < 0 {
-                    px = px + 2 * dy1;
-                } else {
-                    if (dx < 0 && dy < 0) || (dx > 0 && dy > 0) {
-                        y = y + 1;
-                    } else {
-                        y = y - 1;
-                    }
-                    px = px + 2 * (dy1 - dx1);
+    fn fill_span(&mut self, x1: i32, x2: i32, y: i32, pixel: &Pixel) {
+        // Clip once, then specialize the whole row on the pixel mode
+        let (x1, x2) = (cmp::min(x1, x2), cmp::max(x1, x2));
+        let (w, h) = (self.buffer.width as i32, self.buffer.height as i32);
+        if y < 0 || y >= h || x2 < 0 || x1 >= w {
+            return;
+        }
+        let start = (y * w + cmp::max(x1, 0)) as usize;
+        let end = (y * w + cmp::min(x2, w - 1)) as usize;
+        let row = &mut self.buffer.data[start..=end];
+        match self.pixel_mode {
+            PixelMode::Normal => row.fill(*pixel),
+            PixelMode::Mask => if pixel.alpha == 255 {
+                row.fill(*pixel);
+            },
+            PixelMode::Alpha => {
+                let a: f32 = (pixel.alpha as f32 / 255.0) * self.blend_factor;
+                let c = 1.0 - a;
+                let (r, g, b) = (a * pixel.red as f32, a * pixel.green as f32, a * pixel.blue as f32);
+                for p in row.iter_mut() {
+                    *p = Pixel::rgb(
+                        (r + c * p.red as f32) as u8,
+                        (g + c * p.green as f32) as u8,
+                        (b + c * p.blue as f32) as u8,
+                    );
                 }
-
-                // Draw pixel from line span at
-                // currently rasterized position
-                self.draw_pixel(x, y, pixel);
             }
-
-        } else { // The line is Y-axis dominant
-
-            // Line is drawn bottom to top
-            if dy >= 0 {
-                x = x1; y = y1; ye = y2;
-            } else { // Line is drawn top to bottom
-                x = x2; y = y2; ye = y1;
+            PixelMode::Additive => {
+                for p in row.iter_mut() {
+                    *p = pixel.calculate_additive(p, self.blend_factor);
+                }
             }
+        }
+    }
 
-            self.draw_pixel(x, y, pixel); // Draw first pixel
+    fn fill_circle(&mut self, x: u32, y: u32, radius: u32, pixel: &Pixel) {
+        let height = self.buffer.height as i32;
+        let (x, y, radius) = (raster::saturate(x as i64), raster::saturate(y as i64), raster::saturate(radius as i64));
+        raster::circle_spans(x, y, radius, height, |x1, x2, y| self.fill_span(x1, x2, y, pixel));
+    }
 
-            // Rasterize the line
-            for _ in y..ye {
-                y = y + 1;
+    fn fill_rect(&mut self, x: u32, y: u32, w: u32, h: u32, pixel: &Pixel) {
+        let height = self.buffer.height as i32;
+        let (x, y) = (raster::saturate(x as i64), raster::saturate(y as i64));
+        let (w, h) = (raster::saturate(w as i64), raster::saturate(h as i64));
+        raster::rect_spans(x, y, w, h, height, |x1, x2, y| self.fill_span(x1, x2, y, pixel));
+    }
 
-                // Deal with octants...
-                if py <= 0 {
-                    py = py + 2 * dx1;
-                } else {
-                    if (dx < 0 && dy<0) || (dx > 0 && dy > 0) {
-                        x = x + 1;
-                    } else {
-                        x = x - 1;
-                    }
-                    py = py + 2 * (dx1 - dy1);
-                }
+    fn fill_triangle(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, x3: u32, y3: u32, pixel: &Pixel) {
+        let height = self.buffer.height as i32;
+        let point = |x: u32, y: u32| (raster::saturate(x as i64), raster::saturate(y as i64));
+        raster::triangle_spans(point(x1, y1), point(x2, y2), point(x3, y3), height, |x1, x2, y| {
+            self.fill_span(x1, x2, y, pixel)
+        });
+    }
 
-                // Draw pixel from line span at
-                // currently rasterized position
-                self.draw_pixel(x, y, pixel);
-            }
-        }
+    fn clear(&mut self, pixel: &Pixel) {
+        self.buffer.clear(pixel);
     }
 
-    fn draw_circle(&mut self, x: u32, y: u32, radius: u32, pixel: &Pixel) {
-        // Midpoint circle algorithm
-        // https://rosettacode.org/wiki/Bitmap/Midpoint_circle_algorithm#C.23
-        let ix: i32 = x as i32;
-        let iy: i32 = y as i32;
-        let iradius: i32 = radius as i32;
-        let mut ddf_x: i32 = 0;
-        let mut ddf_y: i32 = -2 * iradius;
-        let mut f: i32 = 1 - iradius;
-        let mut _x: i32 = 0;
-        let mut _y: i32 = iradius;
-
-        let mut safe_draw = |px: i32, py: i32| {
-          if px >= 0 && py >= 0 {
-              self.draw_pixel(px as u32, py as u32, pixel);
-          }
+    fn draw_partial_sprite(&mut self, pos: Vi2d, sprite: &Sprite, source: &Recti, scale: u32) {
+        let bounds = Recti::new(0, 0, sprite.width as i32, sprite.height as i32);
+        let source = match source.clip(&bounds) {
+            Some(source) => source,
+            None => return,
         };
-
-        safe_draw(ix, iy + iradius);
-        safe_draw(ix, iy - iradius);
-        safe_draw(ix + iradius, iy);
-        safe_draw(ix - iradius, iy);
-
-        while _x < _y {
-            if f >= 0 {
-                _y -= 1;
-                ddf_y += 2;
-                f += ddf_y;
+        let scale = cmp::max(scale, 1) as i32;
+        if scale == 1 && self.pixel_mode == PixelMode::Normal {
+            // Straight row copies, clipped once against the target
+            let target = Recti::new(0, 0, self.buffer.width as i32, self.buffer.height as i32);
+            let visible = match Recti::from_pos_size(pos, source.size()).clip(&target) {
+                Some(visible) => visible,
+                None => return,
+            };
+            let offset = visible.pos() - pos + source.pos();
+            for row in 0..visible.h {
+                let src = ((offset.y + row) * sprite.width as i32 + offset.x) as usize;
+                let dst = ((visible.y + row) * target.w + visible.x) as usize;
+                let len = visible.w as usize;
+                self.buffer.data[dst..dst + len].copy_from_slice(&sprite.data[src..src + len]);
+            }
+            return;
+        }
+        for sy in 0..source.h {
+            for sx in 0..source.w {
+                let pixel = *sprite.get_pixel((source.x + sx) as u32, (source.y + sy) as u32);
+                let x = pos.x + sx * scale;
+                let y = pos.y + sy * scale;
+                for row in y..y + scale {
+                    self.fill_span(x, x + scale - 1, row, &pixel);
+                }
             }
-            _x += 1;
-            ddf_x += 2;
-            f += ddf_x + 1;
-            safe_draw(ix + _x, iy + _y);
-            safe_draw(ix - _x, iy + _y);
-            safe_draw(ix + _x, iy - _y);
-            safe_draw(ix - _x, iy - _y);
-            safe_draw(ix + _y, iy + _x);
-            safe_draw(ix - _y, iy + _x);
-            safe_draw(ix + _y, iy - _x);
-            safe_draw(ix - _y, iy - _x);
         }
     }
 
-    fn draw_rect(&mut self, x: u32, y: u32, w: u32, h: u32, _pixel: &Pixel) {
-        self.draw_line(x, y, x + w, y, _pixel);
-        self.draw_line(x, y, x, y + h, _pixel);
-        self.draw_line(x + w, y + h, x + w, y, _pixel);
-        self.draw_line(x + w, y + h, x, y + h, _pixel);
+    fn save_screenshot(&self, path: &Path) -> YapeResult<()> {
+        capture::save_png(&self.buffer, self.pixel_w, self.pixel_h, path)
     }
 
-    fn draw_triangle(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, x3: u32, y3: u32, pixel: &Pixel) {
-        self.draw_line(x1, y1, x2, y2, pixel);
-        self.draw_line(x2, y2, x3, y3, pixel);
-        self.draw_line(x3, y3, x1, y1, pixel);
+    fn record_frames(&mut self, path: &Path, frames: u32, format: RecordFormat) -> YapeResult<()> {
+        let recorder = FrameRecorder::new(path, frames, format, &self.buffer, self.pixel_w, self.pixel_h)?;
+        self.frame_recorder = Some(recorder);
+        Ok(())
     }
 
-    fn fill_circle(&mut self, _x: u32, _y: u32, _radius: u32, _pixel: &Pixel) {
-        unimplemented!()
+    fn is_recording(&self) -> bool {
+        self.frame_recorder.is_some()
     }
 
-    fn fill_rect(&mut self, x: u32, y: u32, w: u32, h: u32, pixel: &Pixel) {
-        for i in x..x + w {
-            for j in y..y + h {
-                self.draw_pixel(i, j, pixel);
-            }
+    // Drops the frames of any earlier recording
+    fn start_gif_recording(&mut self, max_duration: f32) -> YapeResult<()> {
+        self.gif_recorder = Some(GifRecorder::new(max_duration, self.screen_w, self.screen_h));
+        Ok(())
+    }
+
+    // The recorded frames are kept for save_gif until the next start
+    fn stop_gif_recording(&mut self) {
+        if let Some(recorder) = self.gif_recorder.as_mut() {
+            recorder.capturing = false;
         }
     }
 
-    fn fill_triangle(&mut self, _x1: u32, _y1: u32, _x2: u32, _y2: u32, _x3: u32, _y3: u32, _pixel: &Pixel) {
-        // http://www.sunshine2k.de/coding/java/TriangleRasterization/TriangleRasterization.html
-        unimplemented!()
+    fn is_gif_recording(&self) -> bool {
+        self.gif_recorder.as_ref().is_some_and(|recorder| recorder.capturing)
     }
 
-    fn clear(&mut self, pixel: &Pixel) {
-        self.buffer.clear(pixel);
+    fn save_gif(&self, path: &Path) -> YapeResult<()> {
+        match self.gif_recorder.as_ref() {
+            Some(recorder) => recorder.save(path, self.pixel_w, self.pixel_h),
+            None => Err(YapeError::new(YapeErrorKind::Encode, "gif recording was not started")),
+        }
     }
 }
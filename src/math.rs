@@ -0,0 +1,335 @@
+// 2D vector and rectangle types, after olcPixelGameEngine's v2d_generic
+use std::cmp::PartialOrd;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+pub trait Scalar:
+    Copy + Default + PartialOrd + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self>
+{
+}
+
+impl<T> Scalar for T where
+    T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T>
+{
+}
+
+fn partial_min<T: PartialOrd>(a: T, b: T) -> T {
+    if b < a { b } else { a }
+}
+
+fn partial_max<T: PartialOrd>(a: T, b: T) -> T {
+    if b > a { b } else { a }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct V2d<T> {
+    pub x: T,
+    pub y: T,
+}
+
+pub type Vi2d = V2d<i32>;
+pub type Vf2d = V2d<f32>;
+
+impl<T: Scalar> V2d<T> {
+    pub fn new(x: T, y: T) -> V2d<T> {
+        V2d { x, y }
+    }
+
+    pub fn dot(&self, other: V2d<T>) -> T {
+        self.x * other.x + self.y * other.y
+    }
+
+    // z component of the 3D cross product, positive when other is counter clockwise of self
+    // in a y-up system (clockwise on screen)
+    pub fn cross(&self, other: V2d<T>) -> T {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn length2(&self) -> T {
+        self.dot(*self)
+    }
+
+    pub fn min(&self, other: V2d<T>) -> V2d<T> {
+        V2d::new(partial_min(self.x, other.x), partial_min(self.y, other.y))
+    }
+
+    pub fn max(&self, other: V2d<T>) -> V2d<T> {
+        V2d::new(partial_max(self.x, other.x), partial_max(self.y, other.y))
+    }
+
+    pub fn clamp(&self, low: V2d<T>, high: V2d<T>) -> V2d<T> {
+        self.max(low).min(high)
+    }
+}
+
+impl<T: Scalar + Neg<Output = T>> V2d<T> {
+    pub fn perp(&self) -> V2d<T> {
+        V2d::new(-self.y, self.x)
+    }
+}
+
+macro_rules! float_vector {
+    ($t:ty) => {
+        impl V2d<$t> {
+            pub fn length(&self) -> $t {
+                self.length2().sqrt()
+            }
+
+            pub fn normalize(&self) -> V2d<$t> {
+                let length = self.length();
+                if length == 0.0 {
+                    *self
+                } else {
+                    V2d::new(self.x / length, self.y / length)
+                }
+            }
+
+            pub fn distance(&self, other: V2d<$t>) -> $t {
+                (other - *self).length()
+            }
+
+            pub fn lerp(&self, other: V2d<$t>, t: $t) -> V2d<$t> {
+                *self + (other - *self) * t
+            }
+
+            pub fn floor(&self) -> V2d<$t> {
+                V2d::new(self.x.floor(), self.y.floor())
+            }
+
+            pub fn ceil(&self) -> V2d<$t> {
+                V2d::new(self.x.ceil(), self.y.ceil())
+            }
+
+            // Reflection against a surface with the given unit normal
+            pub fn reflect(&self, normal: V2d<$t>) -> V2d<$t> {
+                *self - normal * (2.0 * self.dot(normal))
+            }
+
+            // Angle in radians from the positive x axis
+            pub fn angle(&self) -> $t {
+                self.y.atan2(self.x)
+            }
+
+            pub fn from_polar(length: $t, angle: $t) -> V2d<$t> {
+                V2d::new(length * angle.cos(), length * angle.sin())
+            }
+
+            pub fn rotate(&self, angle: $t) -> V2d<$t> {
+                let (sin, cos) = angle.sin_cos();
+                V2d::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+            }
+
+            pub fn to_i32(&self) -> Vi2d {
+                V2d::new(self.x as i32, self.y as i32)
+            }
+        }
+    };
+}
+
+float_vector!(f32);
+float_vector!(f64);
+
+impl Vi2d {
+    pub fn to_f32(&self) -> Vf2d {
+        V2d::new(self.x as f32, self.y as f32)
+    }
+}
+
+impl From<Vi2d> for Vf2d {
+    fn from(v: Vi2d) -> Vf2d {
+        v.to_f32()
+    }
+}
+
+impl<T> From<(T, T)> for V2d<T> {
+    fn from((x, y): (T, T)) -> V2d<T> {
+        V2d { x, y }
+    }
+}
+
+impl<T: Scalar> Add for V2d<T> {
+    type Output = V2d<T>;
+    fn add(self, other: V2d<T>) -> V2d<T> {
+        V2d::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Scalar> Sub for V2d<T> {
+    type Output = V2d<T>;
+    fn sub(self, other: V2d<T>) -> V2d<T> {
+        V2d::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+// Element wise
+impl<T: Scalar> Mul for V2d<T> {
+    type Output = V2d<T>;
+    fn mul(self, other: V2d<T>) -> V2d<T> {
+        V2d::new(self.x * other.x, self.y * other.y)
+    }
+}
+
+impl<T: Scalar> Div for V2d<T> {
+    type Output = V2d<T>;
+    fn div(self, other: V2d<T>) -> V2d<T> {
+        V2d::new(self.x / other.x, self.y / other.y)
+    }
+}
+
+impl<T: Scalar> Mul<T> for V2d<T> {
+    type Output = V2d<T>;
+    fn mul(self, scalar: T) -> V2d<T> {
+        V2d::new(self.x * scalar, self.y * scalar)
+    }
+}
+
+impl<T: Scalar> Div<T> for V2d<T> {
+    type Output = V2d<T>;
+    fn div(self, scalar: T) -> V2d<T> {
+        V2d::new(self.x / scalar, self.y / scalar)
+    }
+}
+
+impl<T: Scalar + Neg<Output = T>> Neg for V2d<T> {
+    type Output = V2d<T>;
+    fn neg(self) -> V2d<T> {
+        V2d::new(-self.x, -self.y)
+    }
+}
+
+impl<T: Scalar> AddAssign for V2d<T> {
+    fn add_assign(&mut self, other: V2d<T>) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Scalar> SubAssign for V2d<T> {
+    fn sub_assign(&mut self, other: V2d<T>) {
+        *self = *self - other;
+    }
+}
+
+impl<T: Scalar> MulAssign<T> for V2d<T> {
+    fn mul_assign(&mut self, scalar: T) {
+        *self = *self * scalar;
+    }
+}
+
+impl<T: Scalar> DivAssign<T> for V2d<T> {
+    fn div_assign(&mut self, scalar: T) {
+        *self = *self / scalar;
+    }
+}
+
+// Axis aligned rectangle, covers [x, x + w) x [y, y + h)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rect<T> {
+    pub x: T,
+    pub y: T,
+    pub w: T,
+    pub h: T,
+}
+
+pub type Recti = Rect<i32>;
+pub type Rectf = Rect<f32>;
+
+impl<T: Scalar> Rect<T> {
+    pub fn new(x: T, y: T, w: T, h: T) -> Rect<T> {
+        Rect { x, y, w, h }
+    }
+
+    pub fn from_pos_size(pos: V2d<T>, size: V2d<T>) -> Rect<T> {
+        Rect::new(pos.x, pos.y, size.x, size.y)
+    }
+
+    // Smallest rect with both corners in it
+    pub fn from_corners(a: V2d<T>, b: V2d<T>) -> Rect<T> {
+        let min = a.min(b);
+        let max = a.max(b);
+        Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
+    pub fn pos(&self) -> V2d<T> {
+        V2d::new(self.x, self.y)
+    }
+
+    pub fn size(&self) -> V2d<T> {
+        V2d::new(self.w, self.h)
+    }
+
+    pub fn left(&self) -> T {
+        self.x
+    }
+
+    pub fn top(&self) -> T {
+        self.y
+    }
+
+    pub fn right(&self) -> T {
+        self.x + self.w
+    }
+
+    pub fn bottom(&self) -> T {
+        self.y + self.h
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !(self.w > T::default() && self.h > T::default())
+    }
+
+    pub fn contains(&self, point: V2d<T>) -> bool {
+        point.x >= self.x && point.x < self.right() && point.y >= self.y && point.y < self.bottom()
+    }
+
+    pub fn contains_rect(&self, other: &Rect<T>) -> bool {
+        other.x >= self.x && other.right() <= self.right() && other.y >= self.y && other.bottom() <= self.bottom()
+    }
+
+    // Shares some area with other, rects that only touch or are empty never intersect
+    pub fn intersects(&self, other: &Rect<T>) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+
+    // Bounding rect of both, empty rects are ignored
+    pub fn union(&self, other: &Rect<T>) -> Rect<T> {
+        if other.is_empty() {
+            return *self;
+        }
+        if self.is_empty() {
+            return *other;
+        }
+        let min = self.pos().min(other.pos());
+        let max = V2d::new(self.right(), self.bottom()).max(V2d::new(other.right(), other.bottom()));
+        Rect::from_corners(min, max)
+    }
+
+    // Part of self inside bounds, None when they don't overlap
+    pub fn clip(&self, bounds: &Rect<T>) -> Option<Rect<T>> {
+        if !self.intersects(bounds) {
+            return None;
+        }
+        let min = self.pos().max(bounds.pos());
+        let max = V2d::new(self.right(), self.bottom()).min(V2d::new(bounds.right(), bounds.bottom()));
+        Some(Rect::from_corners(min, max))
+    }
+
+    pub fn translate(&self, offset: V2d<T>) -> Rect<T> {
+        Rect::new(self.x + offset.x, self.y + offset.y, self.w, self.h)
+    }
+}
+
+impl Rectf {
+    pub fn center(&self) -> Vf2d {
+        V2d::new(self.x + self.w / 2.0, self.y + self.h / 2.0)
+    }
+}
+
+impl Recti {
+    pub fn center(&self) -> Vi2d {
+        V2d::new(self.x + self.w / 2, self.y + self.h / 2)
+    }
+}
@@ -0,0 +1,129 @@
+// Paints give every pixel of a fill its own color, the fill routines hand their spans to span()
+use crate::polygon::{self, FillRule};
+use crate::raster;
+use crate::{Pixel, Sprite, Vf2d, Vi2d, YapeEngineApi};
+
+// Normalized 4x4 Bayer matrix for ordered dithering
+// https://en.wikipedia.org/wiki/Ordered_dithering
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientStop {
+    pub offset: f32,
+    pub pixel: Pixel,
+}
+
+// Colors along [0, 1], stops are kept sorted by offset
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    stops: Vec<GradientStop>,
+}
+
+impl Gradient {
+    pub fn new(from: Pixel, to: Pixel) -> Gradient {
+        Gradient { stops: vec![GradientStop { offset: 0.0, pixel: from }, GradientStop { offset: 1.0, pixel: to }] }
+    }
+
+    pub fn with_stop(mut self, offset: f32, pixel: Pixel) -> Gradient {
+        let offset = offset.clamp(0.0, 1.0);
+        let index = self.stops.iter().position(|s| s.offset > offset).unwrap_or(self.stops.len());
+        self.stops.insert(index, GradientStop { offset, pixel });
+        self
+    }
+
+    pub fn stops(&self) -> &[GradientStop] {
+        &self.stops
+    }
+
+    pub fn sample(&self, t: f32) -> Pixel {
+        let t = t.clamp(0.0, 1.0);
+        let next = match self.stops.iter().position(|s| s.offset >= t) {
+            Some(0) => return self.stops[0].pixel,
+            Some(next) => next,
+            None => return self.stops[self.stops.len() - 1].pixel,
+        };
+        let (a, b) = (&self.stops[next - 1], &self.stops[next]);
+        let span = b.offset - a.offset;
+        if span <= 0.0 {
+            b.pixel
+        } else {
+            a.pixel.lerp(&b.pixel, (t - a.offset) / span)
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum Paint<'a> {
+    Solid(Pixel),
+    // Projected onto the line from start to end, clamped past both ends
+    LinearGradient { start: Vf2d, end: Vf2d, gradient: Gradient },
+    // Distance from center over radius
+    RadialGradient { center: Vf2d, radius: f32, gradient: Gradient },
+    // Sprite tiled over the screen, offset moves the tiling origin
+    Pattern { sprite: &'a Sprite, offset: Vi2d },
+    // Ordered dither between two colors, level 0 is all first and 1 is all second
+    Dither { first: Pixel, second: Pixel, level: f32 },
+}
+
+impl<'a> Paint<'a> {
+    // Color at a screen pixel, gradients are evaluated at the pixel center
+    pub fn color_at(&self, x: i32, y: i32) -> Pixel {
+        match self {
+            Paint::Solid(pixel) => *pixel,
+            Paint::LinearGradient { start, end, gradient } => {
+                let axis = *end - *start;
+                let length2 = axis.length2();
+                if length2 == 0.0 {
+                    return gradient.sample(0.0);
+                }
+                let p = Vf2d::new(x as f32 + 0.5, y as f32 + 0.5);
+                gradient.sample((p - *start).dot(axis) / length2)
+            }
+            Paint::RadialGradient { center, radius, gradient } => {
+                if *radius <= 0.0 {
+                    return gradient.sample(1.0);
+                }
+                let p = Vf2d::new(x as f32 + 0.5, y as f32 + 0.5);
+                gradient.sample(p.distance(*center) / radius)
+            }
+            Paint::Pattern { sprite, offset } => {
+                if sprite.width == 0 || sprite.height == 0 {
+                    return Pixel::default();
+                }
+                let u = (x - offset.x).rem_euclid(sprite.width as i32) as u32;
+                let v = (y - offset.y).rem_euclid(sprite.height as i32) as u32;
+                *sprite.get_pixel(u, v)
+            }
+            Paint::Dither { first, second, level } => {
+                let threshold = (BAYER_4X4[(y & 3) as usize][(x & 3) as usize] as f32 + 0.5) / 16.0;
+                if *level > threshold {
+                    *second
+                } else {
+                    *first
+                }
+            }
+        }
+    }
+}
+
+// Solid paints keep the single color fast path of fill_span
+pub(crate) fn span<E: YapeEngineApi + ?Sized>(engine: &mut E, x1: i32, x2: i32, y: i32, paint: &Paint) {
+    if let Paint::Solid(pixel) = paint {
+        engine.fill_span(x1, x2, y, pixel);
+        return;
+    }
+    if y < 0 || y >= engine.get_screen_height() as i32 {
+        return;
+    }
+    let x1 = x1.max(0);
+    let x2 = x2.min(engine.get_screen_width() as i32 - 1);
+    for x in x1..=x2 {
+        raster::plot(engine, x, y, &paint.color_at(x, y));
+    }
+}
+
+pub(crate) fn fill_polygon<E: YapeEngineApi + ?Sized>(engine: &mut E, points: &[Vi2d], rule: FillRule, paint: &Paint) {
+    let points: Vec<Vf2d> = points.iter().map(|p| p.to_f32()).collect();
+    let height = engine.get_screen_height() as i32;
+    polygon::spans(&points, rule, height, |x1, x2, y| span(engine, x1, x2, y, paint));
+}
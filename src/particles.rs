@@ -0,0 +1,211 @@
+// Particle emitters, every emitter owns a seeded generator so runs can be replayed exactly
+use std::f32::consts::PI;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::paint::Gradient;
+use crate::raster;
+use crate::{Pixel, PixelMode, Vf2d, Vi2d, YapeEngineApi, BLANK, WHITE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleBlend {
+    Alpha,
+    Additive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleShape {
+    // One pixel whatever the size
+    Pixel,
+    // Size is the side
+    Square,
+    // Size is the diameter
+    Circle,
+}
+
+// Ranges are (min, max) and every particle picks its own value inside them when spawned
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmitterConfig {
+    pub position: Vf2d,
+    // Particles per second, 0 to only emit bursts
+    pub spawn_rate: f32,
+    pub max_particles: usize,
+    // Seconds
+    pub lifetime: (f32, f32),
+    // Pixels per second
+    pub speed: (f32, f32),
+    // Radians, the spawn velocity points in direction with up to half of spread either side
+    pub direction: f32,
+    pub spread: f32,
+    // Along the particle's velocity, negative values slow it down
+    pub acceleration: (f32, f32),
+    // Added to every particle's velocity, pixels per second squared
+    pub gravity: Vf2d,
+    // Fraction of the velocity lost per second
+    pub damping: f32,
+    // Size at spawn and at the end of the particle's life
+    pub size: (f32, f32),
+    pub end_size: (f32, f32),
+    // Sampled from spawn (0) to death (1)
+    pub color: Gradient,
+    pub blend: ParticleBlend,
+    pub shape: ParticleShape,
+}
+
+impl Default for EmitterConfig {
+    fn default() -> EmitterConfig {
+        EmitterConfig {
+            position: Vf2d::new(0.0, 0.0),
+            spawn_rate: 50.0,
+            max_particles: 1000,
+            lifetime: (1.0, 1.0),
+            speed: (20.0, 40.0),
+            direction: -PI / 2.0,
+            spread: PI / 4.0,
+            acceleration: (0.0, 0.0),
+            gravity: Vf2d::new(0.0, 0.0),
+            damping: 0.0,
+            size: (1.0, 1.0),
+            end_size: (1.0, 1.0),
+            color: Gradient::new(WHITE, BLANK),
+            blend: ParticleBlend::Alpha,
+            shape: ParticleShape::Pixel,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    pub position: Vf2d,
+    pub velocity: Vf2d,
+    pub acceleration: f32,
+    pub age: f32,
+    pub lifetime: f32,
+    pub size: f32,
+    pub end_size: f32,
+}
+
+impl Particle {
+    // 0 at spawn, 1 at death
+    pub fn life(&self) -> f32 {
+        if self.lifetime > 0.0 {
+            (self.age / self.lifetime).min(1.0)
+        } else {
+            1.0
+        }
+    }
+
+    pub fn current_size(&self) -> f32 {
+        self.size + (self.end_size - self.size) * self.life()
+    }
+}
+
+pub struct ParticleEmitter {
+    pub config: EmitterConfig,
+    // Stops continuous spawning, live particles keep going
+    pub active: bool,
+    particles: Vec<Particle>,
+    rng: StdRng,
+    pending: f32,
+}
+
+fn pick(rng: &mut StdRng, (min, max): (f32, f32)) -> f32 {
+    if max > min {
+        rng.gen_range(min, max)
+    } else {
+        min
+    }
+}
+
+impl ParticleEmitter {
+    pub fn new(config: EmitterConfig, seed: u64) -> ParticleEmitter {
+        ParticleEmitter { config, active: true, particles: Vec::new(), rng: StdRng::seed_from_u64(seed), pending: 0.0 }
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.pending = 0.0;
+    }
+
+    // Spawns count particles at once, up to max_particles
+    pub fn burst(&mut self, count: usize) {
+        for _ in 0..count {
+            if self.particles.len() >= self.config.max_particles {
+                break;
+            }
+            let particle = self.spawn();
+            self.particles.push(particle);
+        }
+    }
+
+    fn spawn(&mut self) -> Particle {
+        let config = &self.config;
+        let rng = &mut self.rng;
+        let angle = config.direction + pick(rng, (-config.spread / 2.0, config.spread / 2.0));
+        Particle {
+            position: config.position,
+            velocity: Vf2d::from_polar(pick(rng, config.speed), angle),
+            acceleration: pick(rng, config.acceleration),
+            age: 0.0,
+            lifetime: pick(rng, config.lifetime),
+            size: pick(rng, config.size),
+            end_size: pick(rng, config.end_size),
+        }
+    }
+
+    pub fn update(&mut self, time_elapsed: f32) {
+        let dt = time_elapsed.max(0.0);
+        let gravity = self.config.gravity;
+        let damping = (1.0 - self.config.damping * dt).max(0.0);
+        for p in self.particles.iter_mut() {
+            p.age += dt;
+            let along = p.velocity.normalize() * p.acceleration;
+            p.velocity = (p.velocity + (gravity + along) * dt) * damping;
+            p.position += p.velocity * dt;
+        }
+        self.particles.retain(|p| p.age < p.lifetime);
+
+        if self.active && self.config.spawn_rate > 0.0 {
+            self.pending += self.config.spawn_rate * dt;
+            let count = self.pending.floor();
+            self.pending -= count;
+            self.burst(count as usize);
+        }
+    }
+
+    // Oldest particles first, so newer ones end up on top
+    pub fn draw<E: YapeEngineApi + ?Sized>(&self, engine: &mut E) {
+        let mode = *engine.get_pixel_mode();
+        engine.set_pixel_mode(match self.config.blend {
+            ParticleBlend::Alpha => PixelMode::Alpha,
+            ParticleBlend::Additive => PixelMode::Additive,
+        });
+        for p in self.particles.iter() {
+            let pixel: Pixel = self.config.color.sample(p.life());
+            let center = p.position.floor().to_i32();
+            let size = p.current_size();
+            match self.config.shape {
+                ParticleShape::Pixel => raster::plot(engine, center.x, center.y, &pixel),
+                ParticleShape::Square => {
+                    let side = size.round().max(1.0) as i32;
+                    engine.fill_rect_v(center - Vi2d::new(side / 2, side / 2), Vi2d::new(side, side), &pixel);
+                }
+                ParticleShape::Circle => engine.fill_circle_v(center, (size / 2.0).round() as i32, &pixel),
+            }
+        }
+        engine.set_pixel_mode(mode);
+    }
+}
@@ -0,0 +1,531 @@
+// Impulse based rigid bodies over the collision shapes, stepped with a fixed time step and a
+// spatial hash broad phase
+// https://gamedevelopment.tutsplus.com/tutorials/how-to-create-a-custom-2d-physics-engine-the-basics-and-impulse-resolution--gamedev-6331
+use std::collections::HashMap;
+
+use crate::camera::Camera2D;
+use crate::collision::{self, Circle, Contact, Hit, Shape};
+use crate::math::{Rectf, Vf2d};
+use crate::spatial::SpatialHash;
+use crate::{Pixel, YapeEngineApi, DARK_GREY, GREY, RED, WHITE, YELLOW};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyType {
+    // Never moves
+    Static,
+    // Moves with its velocity and pushes dynamic bodies, nothing pushes it back
+    Kinematic,
+    Dynamic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BodyId(u32);
+
+// Shapes are relative to the body position and turn around it, so they should be centered on
+// it. Boxes never turn
+#[derive(Debug, Clone, PartialEq)]
+pub struct Body {
+    pub position: Vf2d,
+    // Radians, clockwise on screen
+    pub angle: f32,
+    pub velocity: Vf2d,
+    pub angular_velocity: f32,
+    // 0 stops dead, 1 bounces back at full speed
+    pub restitution: f32,
+    pub friction: f32,
+    pub gravity_scale: f32,
+    // Fraction of the velocity lost per second
+    pub linear_damping: f32,
+    body_type: BodyType,
+    shape: Shape,
+    density: f32,
+    fixed_rotation: bool,
+    inv_mass: f32,
+    inv_inertia: f32,
+    force: Vf2d,
+    torque: f32,
+}
+
+impl Body {
+    pub fn new(body_type: BodyType, shape: Shape) -> Body {
+        let mut body = Body {
+            position: Vf2d::new(0.0, 0.0),
+            angle: 0.0,
+            velocity: Vf2d::new(0.0, 0.0),
+            angular_velocity: 0.0,
+            restitution: 0.2,
+            friction: 0.4,
+            gravity_scale: 1.0,
+            linear_damping: 0.0,
+            body_type,
+            shape,
+            density: 1.0,
+            fixed_rotation: false,
+            inv_mass: 0.0,
+            inv_inertia: 0.0,
+            force: Vf2d::new(0.0, 0.0),
+            torque: 0.0,
+        };
+        body.update_mass();
+        body
+    }
+
+    pub fn with_position(mut self, position: Vf2d) -> Body {
+        self.position = position;
+        self
+    }
+
+    pub fn with_velocity(mut self, velocity: Vf2d) -> Body {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn with_restitution(mut self, restitution: f32) -> Body {
+        self.restitution = restitution;
+        self
+    }
+
+    pub fn with_friction(mut self, friction: f32) -> Body {
+        self.friction = friction;
+        self
+    }
+
+    // Mass per square pixel
+    pub fn with_density(mut self, density: f32) -> Body {
+        self.density = density;
+        self.update_mass();
+        self
+    }
+
+    pub fn with_fixed_rotation(mut self) -> Body {
+        self.fixed_rotation = true;
+        self.update_mass();
+        self
+    }
+
+    pub fn body_type(&self) -> BodyType {
+        self.body_type
+    }
+
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    // 0 for bodies that don't move from impulses
+    pub fn mass(&self) -> f32 {
+        if self.inv_mass > 0.0 {
+            1.0 / self.inv_mass
+        } else {
+            0.0
+        }
+    }
+
+    fn update_mass(&mut self) {
+        if self.body_type != BodyType::Dynamic {
+            self.inv_mass = 0.0;
+            self.inv_inertia = 0.0;
+            return;
+        }
+        let (mass, inertia) = match &self.shape {
+            Shape::Aabb(rect) => (rect.w * rect.h * self.density, 0.0),
+            Shape::Circle(c) => {
+                let mass = std::f32::consts::PI * c.radius * c.radius * self.density;
+                (mass, mass * (c.radius * c.radius / 2.0 + c.center.length2()))
+            }
+            Shape::Polygon(points) => {
+                // Sum of the triangles fanning out of the body position
+                let (mut area, mut inertia) = (0.0, 0.0);
+                for i in 0..points.len() {
+                    let (p, q) = (points[i], points[(i + 1) % points.len()]);
+                    let cross = p.cross(q);
+                    area += cross / 2.0;
+                    inertia += cross * (p.dot(p) + p.dot(q) + q.dot(q)) / 12.0;
+                }
+                (area.abs() * self.density, inertia.abs() * self.density)
+            }
+        };
+        self.inv_mass = if mass > 0.0 { 1.0 / mass } else { 0.0 };
+        self.inv_inertia = if inertia > 0.0 && !self.fixed_rotation { 1.0 / inertia } else { 0.0 };
+    }
+
+    // The shape where the body is now
+    pub fn world_shape(&self) -> Shape {
+        match &self.shape {
+            Shape::Aabb(rect) => Shape::Aabb(rect.translate(self.position)),
+            Shape::Circle(c) => Shape::Circle(Circle::new(self.position + c.center.rotate(self.angle), c.radius)),
+            Shape::Polygon(points) => Shape::Polygon(points.iter().map(|p| self.position + p.rotate(self.angle)).collect()),
+        }
+    }
+
+    pub fn bounds(&self) -> Rectf {
+        self.world_shape().bounds()
+    }
+
+    // Applied over the next step
+    pub fn apply_force(&mut self, force: Vf2d) {
+        self.force += force;
+    }
+
+    pub fn apply_torque(&mut self, torque: f32) {
+        self.torque += torque;
+    }
+
+    // Instant change of momentum at a world point
+    pub fn apply_impulse(&mut self, impulse: Vf2d, point: Vf2d) {
+        self.velocity += impulse * self.inv_mass;
+        self.angular_velocity += (point - self.position).cross(impulse) * self.inv_inertia;
+    }
+
+    // Velocity of a world point of the body, including its spin
+    pub fn velocity_at(&self, point: Vf2d) -> Vf2d {
+        let r = point - self.position;
+        self.velocity + Vf2d::new(-r.y, r.x) * self.angular_velocity
+    }
+}
+
+#[derive(Default)]
+struct ContactPoint {
+    normal_impulse: f32,
+    tangent_impulse: f32,
+    // Separating speed restitution asks for
+    bounce: f32,
+}
+
+struct Manifold {
+    a: usize,
+    b: usize,
+    contact: Contact,
+    points: Vec<ContactPoint>,
+}
+
+// Both bodies of a pair, a and b differ
+fn pair_mut(bodies: &mut [(BodyId, Body)], a: usize, b: usize) -> (&mut Body, &mut Body) {
+    if a < b {
+        let (first, second) = bodies.split_at_mut(b);
+        (&mut first[a].1, &mut second[0].1)
+    } else {
+        let (first, second) = bodies.split_at_mut(a);
+        (&mut second[0].1, &mut first[b].1)
+    }
+}
+
+pub struct PhysicsWorld {
+    pub gravity: Vf2d,
+    // Velocity solver passes per step, more keeps stacks steadier
+    pub iterations: u32,
+    fixed_dt: f32,
+    accumulator: f32,
+    next_id: u32,
+    bodies: Vec<(BodyId, Body)>,
+    index: HashMap<BodyId, usize>,
+    broad_phase: SpatialHash<BodyId>,
+    manifolds: Vec<Manifold>,
+}
+
+// Steps a single update may run, so a long frame slows the simulation down instead of piling up
+const MAX_STEPS: u32 = 8;
+// Overlap left alone and the share of the rest pushed apart per step, keeps resting bodies still
+const SLOP: f32 = 0.05;
+const CORRECTION: f32 = 0.4;
+
+impl PhysicsWorld {
+    pub fn new(gravity: Vf2d) -> PhysicsWorld {
+        PhysicsWorld {
+            gravity,
+            iterations: 8,
+            fixed_dt: 1.0 / 60.0,
+            accumulator: 0.0,
+            next_id: 0,
+            bodies: Vec::new(),
+            index: HashMap::new(),
+            broad_phase: SpatialHash::new(64.0),
+            manifolds: Vec::new(),
+        }
+    }
+
+    pub fn with_fixed_dt(mut self, fixed_dt: f32) -> PhysicsWorld {
+        self.fixed_dt = fixed_dt.max(f32::EPSILON);
+        self
+    }
+
+    pub fn with_iterations(mut self, iterations: u32) -> PhysicsWorld {
+        self.iterations = iterations;
+        self
+    }
+
+    // Broad phase cells, around the size of a typical body
+    pub fn with_cell_size(mut self, cell_size: f32) -> PhysicsWorld {
+        self.broad_phase = SpatialHash::new(cell_size);
+        for (id, body) in self.bodies.iter() {
+            self.broad_phase.insert(*id, body.bounds());
+        }
+        self
+    }
+
+    pub fn fixed_dt(&self) -> f32 {
+        self.fixed_dt
+    }
+
+    pub fn add(&mut self, body: Body) -> BodyId {
+        let id = BodyId(self.next_id);
+        self.next_id += 1;
+        self.broad_phase.insert(id, body.bounds());
+        self.index.insert(id, self.bodies.len());
+        self.bodies.push((id, body));
+        id
+    }
+
+    pub fn remove(&mut self, id: BodyId) -> Option<Body> {
+        let i = self.index.remove(&id)?;
+        let (_, body) = self.bodies.swap_remove(i);
+        if let Some((moved, _)) = self.bodies.get(i) {
+            self.index.insert(*moved, i);
+        }
+        self.broad_phase.remove(id);
+        self.manifolds.clear();
+        Some(body)
+    }
+
+    pub fn body(&self, id: BodyId) -> Option<&Body> {
+        self.index.get(&id).map(|&i| &self.bodies[i].1)
+    }
+
+    pub fn body_mut(&mut self, id: BodyId) -> Option<&mut Body> {
+        let i = *self.index.get(&id)?;
+        Some(&mut self.bodies[i].1)
+    }
+
+    // In the order they were added, apart from removals filling gaps
+    pub fn bodies(&self) -> impl Iterator<Item = (BodyId, &Body)> {
+        self.bodies.iter().map(|(id, body)| (*id, body))
+    }
+
+    pub fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+    }
+
+    // Touching pairs found by the last step, the normal points from the first body to the second
+    pub fn contacts(&self) -> impl Iterator<Item = (BodyId, BodyId, &Contact)> {
+        self.manifolds.iter().map(move |m| (self.bodies[m.a].0, self.bodies[m.b].0, &m.contact))
+    }
+
+    // Runs as many fixed steps as time_elapsed covers, the remainder carries over to the next
+    // update. Returns the number of steps run
+    pub fn update(&mut self, time_elapsed: f32) -> u32 {
+        self.accumulator += time_elapsed.max(0.0);
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_dt && steps < MAX_STEPS {
+            self.step();
+            self.accumulator -= self.fixed_dt;
+            steps += 1;
+        }
+        if steps == MAX_STEPS {
+            self.accumulator = self.accumulator.min(self.fixed_dt);
+        }
+        steps
+    }
+
+    pub fn step(&mut self) {
+        let dt = self.fixed_dt;
+        for (_, body) in self.bodies.iter_mut() {
+            if body.body_type == BodyType::Dynamic {
+                body.velocity += (self.gravity * body.gravity_scale + body.force * body.inv_mass) * dt;
+                body.velocity *= (1.0 - body.linear_damping * dt).max(0.0);
+                body.angular_velocity += body.torque * body.inv_inertia * dt;
+            }
+            body.force = Vf2d::new(0.0, 0.0);
+            body.torque = 0.0;
+        }
+
+        self.find_contacts();
+        for m in self.manifolds.iter() {
+            let (body_a, body_b) = pair_mut(&mut self.bodies, m.a, m.b);
+            let (normal, tangent) = (m.contact.normal, m.contact.normal.perp());
+            for (point, state) in m.contact.points.iter().zip(m.points.iter()) {
+                let impulse = normal * state.normal_impulse + tangent * state.tangent_impulse;
+                body_a.apply_impulse(-impulse, *point);
+                body_b.apply_impulse(impulse, *point);
+            }
+        }
+        for _ in 0..self.iterations {
+            for i in 0..self.manifolds.len() {
+                self.resolve(i);
+            }
+        }
+
+        for (_, body) in self.bodies.iter_mut() {
+            if body.body_type != BodyType::Static {
+                body.position += body.velocity * dt;
+                if !matches!(body.shape, Shape::Aabb(_)) {
+                    body.angle += body.angular_velocity * dt;
+                }
+            }
+        }
+        self.correct_positions();
+    }
+
+    // Pairs still touching since the last step start from last step's impulses, the solver then
+    // only has to correct them instead of rebuilding a whole stack's support every step
+    fn find_contacts(&mut self) {
+        let previous: HashMap<(usize, usize), Vec<ContactPoint>> =
+            self.manifolds.drain(..).map(|m| ((m.a, m.b), m.points)).collect();
+        let shapes: Vec<Shape> = self.bodies.iter().map(|(_, body)| body.world_shape()).collect();
+        // Also catches bodies moved through body_mut
+        for ((id, _), shape) in self.bodies.iter().zip(shapes.iter()) {
+            self.broad_phase.update(*id, shape.bounds());
+        }
+        for (a, (_, body)) in self.bodies.iter().enumerate() {
+            if body.body_type == BodyType::Static {
+                continue;
+            }
+            for other in self.broad_phase.query_rect(&shapes[a].bounds()) {
+                let b = self.index[&other];
+                let other_type = self.bodies[b].1.body_type;
+                // Pairs of moving bodies are checked once, from the lower index
+                if b == a || other_type != BodyType::Static && b < a {
+                    continue;
+                }
+                if body.body_type != BodyType::Dynamic && other_type != BodyType::Dynamic {
+                    continue;
+                }
+                if let Some(contact) = collision::collide(&shapes[a], &shapes[b]) {
+                    let mut points: Vec<ContactPoint> =
+                        contact.points.iter().map(|p| self.bounce(a, b, contact.normal, *p)).collect();
+                    if let Some(old) = previous.get(&(a, b)).filter(|old| old.len() == points.len()) {
+                        for (point, old) in points.iter_mut().zip(old.iter()) {
+                            point.normal_impulse = old.normal_impulse;
+                            point.tangent_impulse = old.tangent_impulse;
+                        }
+                    }
+                    self.manifolds.push(Manifold { a, b, contact, points });
+                }
+            }
+        }
+    }
+
+    fn bounce(&self, a: usize, b: usize, normal: Vf2d, point: Vf2d) -> ContactPoint {
+        let (body_a, body_b) = (&self.bodies[a].1, &self.bodies[b].1);
+        let approach = (body_b.velocity_at(point) - body_a.velocity_at(point)).dot(normal);
+        // Slow contacts don't bounce, or resting bodies would never settle
+        let resting = (self.gravity * self.fixed_dt).length() * 2.0;
+        // The bouncier body wins, so a rubber ball bounces on any floor
+        let bounce = if -approach > resting { -approach * body_a.restitution.max(body_b.restitution) } else { 0.0 };
+        ContactPoint { bounce, ..ContactPoint::default() }
+    }
+
+    // One sequential impulse pass over a manifold. Impulses add up over the step's passes and
+    // are clamped as totals, which lets stacks settle in a few passes
+    fn resolve(&mut self, m: usize) {
+        let manifold = &mut self.manifolds[m];
+        let (body_a, body_b) = pair_mut(&mut self.bodies, manifold.a, manifold.b);
+        let normal = manifold.contact.normal;
+        let tangent = normal.perp();
+        let friction = (body_a.friction * body_b.friction).sqrt();
+        let inv_masses = body_a.inv_mass + body_b.inv_mass;
+        let (inv_inertia_a, inv_inertia_b) = (body_a.inv_inertia, body_b.inv_inertia);
+
+        for (k, &point) in manifold.contact.points.iter().enumerate() {
+            let ra = point - body_a.position;
+            let rb = point - body_b.position;
+            let mass_along = |direction: Vf2d| {
+                let (ca, cb) = (ra.cross(direction), rb.cross(direction));
+                inv_masses + ca * ca * inv_inertia_a + cb * cb * inv_inertia_b
+            };
+            let (inv_normal, inv_tangent) = (mass_along(normal), mass_along(tangent));
+            if inv_normal <= 0.0 {
+                continue;
+            }
+            let point_state = &mut manifold.points[k];
+
+            let relative = body_b.velocity_at(point) - body_a.velocity_at(point);
+            let j = (point_state.bounce - relative.dot(normal)) / inv_normal;
+            let total = (point_state.normal_impulse + j).max(0.0);
+            let j = total - point_state.normal_impulse;
+            point_state.normal_impulse = total;
+            body_a.apply_impulse(normal * -j, point);
+            body_b.apply_impulse(normal * j, point);
+
+            // Coulomb friction, limited by how hard the point is pressed
+            let relative = body_b.velocity_at(point) - body_a.velocity_at(point);
+            let limit = point_state.normal_impulse * friction;
+            let total = (point_state.tangent_impulse - relative.dot(tangent) / inv_tangent).clamp(-limit, limit);
+            let jt = total - point_state.tangent_impulse;
+            point_state.tangent_impulse = total;
+            body_a.apply_impulse(tangent * -jt, point);
+            body_b.apply_impulse(tangent * jt, point);
+        }
+    }
+
+    // Pushes overlapping bodies apart in proportion to their inverse mass, so sinking from
+    // velocity errors doesn't build up
+    fn correct_positions(&mut self) {
+        for m in self.manifolds.iter() {
+            let (inv_a, inv_b) = (self.bodies[m.a].1.inv_mass, self.bodies[m.b].1.inv_mass);
+            if inv_a + inv_b <= 0.0 {
+                continue;
+            }
+            let correction = m.contact.normal * ((m.contact.depth - SLOP).max(0.0) / (inv_a + inv_b) * CORRECTION);
+            self.bodies[m.a].1.position -= correction * inv_a;
+            self.bodies[m.b].1.position += correction * inv_b;
+        }
+        for (id, body) in self.bodies.iter() {
+            if body.body_type != BodyType::Static {
+                self.broad_phase.update(*id, body.bounds());
+            }
+        }
+    }
+
+    // Bodies whose shape contains the point
+    pub fn query_point(&self, point: Vf2d) -> Vec<BodyId> {
+        self.broad_phase
+            .query_rect(&Rectf::new(point.x, point.y, 0.0, 0.0))
+            .into_iter()
+            .filter(|id| self.body(*id).is_some_and(|b| b.world_shape().contains(point)))
+            .collect()
+    }
+
+    // First body along the ray from origin to origin + direction
+    pub fn raycast(&self, origin: Vf2d, direction: Vf2d) -> Option<(BodyId, Hit)> {
+        self.broad_phase
+            .query_rect(&Rectf::from_corners(origin, origin + direction))
+            .into_iter()
+            .filter_map(|id| Some((id, collision::raycast(origin, direction, &self.body(id)?.world_shape())?)))
+            .fold(None, |best: Option<(BodyId, Hit)>, hit| match best {
+                Some(best) if best.1.time <= hit.1.time => Some(best),
+                _ => Some(hit),
+            })
+    }
+
+    // Outlines every body in world space, grey for static, yellow for kinematic and white for
+    // dynamic ones with their angle marked, plus the last step's contact points in red
+    pub fn debug_draw<E: YapeEngineApi + ?Sized>(&self, engine: &mut E, camera: &Camera2D) {
+        for (_, body) in self.bodies.iter() {
+            let pixel: &Pixel = match body.body_type {
+                BodyType::Static => &GREY,
+                BodyType::Kinematic => &YELLOW,
+                BodyType::Dynamic => &WHITE,
+            };
+            match body.world_shape() {
+                Shape::Aabb(rect) => engine.draw_rect_w(camera, &rect, pixel),
+                Shape::Circle(c) => {
+                    engine.draw_circle_w(camera, c.center, c.radius, pixel);
+                    let edge = c.center + Vf2d::from_polar(c.radius, body.angle);
+                    engine.draw_line_w(camera, c.center, edge, pixel);
+                }
+                Shape::Polygon(points) => {
+                    engine.draw_polygon_w(camera, &points, pixel);
+                    engine.draw_line_w(camera, body.position, points.first().copied().unwrap_or(body.position), &DARK_GREY);
+                }
+            }
+        }
+        for m in self.manifolds.iter() {
+            for point in m.contact.points.iter() {
+                engine.draw_line_w(camera, *point, *point + m.contact.normal * 4.0, &RED);
+            }
+        }
+    }
+}
@@ -0,0 +1,360 @@
+// Kinematic platformer movement against the flags of a tile map. The hitbox moves one axis at
+// a time, slopes are followed by the floor under the hitbox's center and small ledges are
+// stepped onto
+use crate::math::{Rectf, Vf2d};
+use crate::tilemap::{TileFlags, TileMap};
+use crate::{KeyboardKey, YapeEngineApi};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ControllerConfig {
+    // Hitbox in pixels
+    pub size: Vf2d,
+    // Pixels per second
+    pub run_speed: f32,
+    // Pixels per second squared
+    pub ground_acceleration: f32,
+    pub ground_deceleration: f32,
+    pub air_acceleration: f32,
+    pub gravity: f32,
+    pub max_fall_speed: f32,
+    pub jump_speed: f32,
+    // Share of the upward speed kept when jump is let go early, for short hops
+    pub jump_cut: f32,
+    // Seconds after walking off a ledge during which jumping still works
+    pub coyote_time: f32,
+    // Seconds a jump press is remembered while in the air
+    pub jump_buffer: f32,
+    // Ledges up to this many pixels high are walked onto
+    pub step_height: f32,
+}
+
+impl Default for ControllerConfig {
+    fn default() -> ControllerConfig {
+        ControllerConfig {
+            size: Vf2d::new(12.0, 14.0),
+            run_speed: 90.0,
+            ground_acceleration: 900.0,
+            ground_deceleration: 1200.0,
+            air_acceleration: 600.0,
+            gravity: 900.0,
+            max_fall_speed: 400.0,
+            jump_speed: 300.0,
+            jump_cut: 0.5,
+            coyote_time: 0.1,
+            jump_buffer: 0.1,
+            step_height: 4.0,
+        }
+    }
+}
+
+// Buttons held this frame, presses are found by comparing with the previous update
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ControllerInput {
+    pub left: bool,
+    pub right: bool,
+    pub jump: bool,
+    // Drops through one way platforms
+    pub down: bool,
+}
+
+impl ControllerInput {
+    // Arrow keys to move, space to jump
+    pub fn from_keyboard<E: YapeEngineApi + ?Sized>(engine: &E) -> ControllerInput {
+        ControllerInput {
+            left: engine.get_key_state(&KeyboardKey::Left).held,
+            right: engine.get_key_state(&KeyboardKey::Right).held,
+            jump: engine.get_key_state(&KeyboardKey::Space).held,
+            down: engine.get_key_state(&KeyboardKey::Down).held,
+        }
+    }
+}
+
+// What happened during one update
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ControllerEvents {
+    pub jumped: bool,
+    pub landed: bool,
+    pub hit_ceiling: bool,
+    pub hit_wall: bool,
+}
+
+pub struct PlatformerController {
+    pub config: ControllerConfig,
+    // Top left corner of the hitbox in map pixels
+    pub position: Vf2d,
+    pub velocity: Vf2d,
+    on_ground: bool,
+    on_slope: bool,
+    // Time left to jump after leaving the ground, and to act on the last jump press
+    coyote: f32,
+    buffer: f32,
+    jump_held: bool,
+    // Going up from a jump that can still be cut short
+    rising: bool,
+}
+
+// Tile geometry shared by the collision queries
+struct Grid<'a> {
+    map: &'a TileMap,
+    tile: Vf2d,
+}
+
+impl<'a> Grid<'a> {
+    fn new(map: &'a TileMap) -> Grid<'a> {
+        Grid { map, tile: map.tileset.tile_size.to_f32() }
+    }
+
+    fn column(&self, x: f32) -> i32 {
+        (x / self.tile.x).floor() as i32
+    }
+
+    fn row(&self, y: f32) -> i32 {
+        (y / self.tile.y).floor() as i32
+    }
+
+    // Last column or row a span ending at x or y (excluded) covers
+    fn last_column(&self, x: f32) -> i32 {
+        (x / self.tile.x).ceil() as i32 - 1
+    }
+
+    fn last_row(&self, y: f32) -> i32 {
+        (y / self.tile.y).ceil() as i32 - 1
+    }
+
+    fn flags(&self, x: i32, y: i32) -> TileFlags {
+        self.map.flags_at(x, y)
+    }
+
+    fn is_wall(&self, x: i32, y: i32) -> bool {
+        let flags = self.flags(x, y);
+        flags.contains(TileFlags::SOLID) && !is_slope(flags)
+    }
+
+    // Highest floor surface between y_min and y_max under the columns from left to right, with
+    // slopes only counting under center. Also tells if that floor is a slope
+    fn floor(&self, left: f32, right: f32, center: f32, y_min: f32, y_max: f32, one_way: bool) -> Option<(f32, bool)> {
+        let center_column = self.column(center);
+        let mut best: Option<(f32, bool)> = None;
+        for row in self.row(y_min)..=self.row(y_max) {
+            let top = row as f32 * self.tile.y;
+            for column in self.column(left)..=self.last_column(right) {
+                let flags = self.flags(column, row);
+                let surface = if is_slope(flags) {
+                    if column != center_column {
+                        continue;
+                    }
+                    let along = ((center - column as f32 * self.tile.x) / self.tile.x).clamp(0.0, 1.0);
+                    let rise = if flags.contains(TileFlags::SLOPE_UP_RIGHT) { along } else { 1.0 - along };
+                    (top + self.tile.y * (1.0 - rise), true)
+                } else if flags.contains(TileFlags::SOLID) || one_way && flags.contains(TileFlags::ONE_WAY) {
+                    (top, false)
+                } else {
+                    continue;
+                };
+                if surface.0 >= y_min && surface.0 <= y_max && best.is_none_or(|b| surface.0 < b.0) {
+                    best = Some(surface);
+                }
+            }
+        }
+        best
+    }
+}
+
+fn is_slope(flags: TileFlags) -> bool {
+    flags.contains(TileFlags::SLOPE_UP_RIGHT) || flags.contains(TileFlags::SLOPE_UP_LEFT)
+}
+
+// Moves value toward target by at most step
+fn approach(value: f32, target: f32, step: f32) -> f32 {
+    if value < target {
+        (value + step).min(target)
+    } else {
+        (value - step).max(target)
+    }
+}
+
+impl PlatformerController {
+    pub fn new(config: ControllerConfig, position: Vf2d) -> PlatformerController {
+        PlatformerController {
+            config,
+            position,
+            velocity: Vf2d::new(0.0, 0.0),
+            on_ground: false,
+            on_slope: false,
+            coyote: 0.0,
+            buffer: 0.0,
+            jump_held: false,
+            rising: false,
+        }
+    }
+
+    pub fn bounds(&self) -> Rectf {
+        Rectf::from_pos_size(self.position, self.config.size)
+    }
+
+    pub fn is_on_ground(&self) -> bool {
+        self.on_ground
+    }
+
+    pub fn is_on_slope(&self) -> bool {
+        self.on_slope
+    }
+
+    pub fn update(&mut self, map: &TileMap, input: ControllerInput, time_elapsed: f32) -> ControllerEvents {
+        let dt = time_elapsed.max(0.0);
+        let config = self.config;
+        let grid = Grid::new(map);
+        let mut events = ControllerEvents::default();
+
+        self.buffer = (self.buffer - dt).max(0.0);
+        if input.jump && !self.jump_held {
+            self.buffer = config.jump_buffer;
+        }
+        self.jump_held = input.jump;
+        if !self.on_ground {
+            self.coyote = (self.coyote - dt).max(0.0);
+        }
+
+        let direction = input.right as i32 - input.left as i32;
+        let acceleration = match (direction != 0, self.on_ground) {
+            (true, true) => config.ground_acceleration,
+            (false, true) => config.ground_deceleration,
+            _ => config.air_acceleration,
+        };
+        self.velocity.x = approach(self.velocity.x, direction as f32 * config.run_speed, acceleration * dt);
+
+        if self.buffer > 0.0 && (self.on_ground || self.coyote > 0.0) {
+            self.velocity.y = -config.jump_speed;
+            self.buffer = 0.0;
+            self.coyote = 0.0;
+            self.on_ground = false;
+            self.on_slope = false;
+            self.rising = true;
+            events.jumped = true;
+        }
+        if self.rising && (!input.jump || self.velocity.y >= 0.0) {
+            if self.velocity.y < 0.0 {
+                self.velocity.y *= config.jump_cut;
+            }
+            self.rising = false;
+        }
+        self.velocity.y = (self.velocity.y + config.gravity * dt).min(config.max_fall_speed);
+
+        let was_on_ground = self.on_ground;
+        self.move_x(&grid, self.velocity.x * dt, !input.down, &mut events);
+        self.move_y(&grid, self.velocity.y * dt, !input.down, &mut events);
+        events.landed = self.on_ground && !was_on_ground;
+        if self.on_ground {
+            self.coyote = config.coyote_time;
+        }
+        events
+    }
+
+    fn move_x(&mut self, grid: &Grid, dx: f32, one_way: bool, events: &mut ControllerEvents) {
+        if dx == 0.0 {
+            return;
+        }
+        let size = self.config.size;
+        // Standing walkers ignore what's under their step height, on a slope the floor under the
+        // leading edge can also be up to half the width higher than under the center
+        let allowance = if self.on_ground {
+            self.config.step_height + if self.on_slope { size.x / 2.0 } else { 0.0 }
+        } else {
+            0.0
+        };
+        let top = self.position.y;
+        let feet = self.position.y + size.y - allowance;
+        // Only cells wholly past the hitbox's edge count, cells it already overlaps were let in by
+        // the step allowance
+        let blocked = |column: i32| feet > top && (grid.row(top)..=grid.last_row(feet)).any(|row| grid.is_wall(column, row));
+
+        if dx > 0.0 {
+            let right = self.position.x + size.x;
+            let wall = (grid.last_column(right) + 1..=grid.last_column(right + dx)).find(|&c| blocked(c));
+            match wall {
+                Some(column) => {
+                    self.position.x = column as f32 * grid.tile.x - size.x;
+                    self.velocity.x = 0.0;
+                    events.hit_wall = true;
+                }
+                None => self.position.x += dx,
+            }
+        } else {
+            let left = self.position.x;
+            let wall = (grid.column(left + dx)..grid.column(left)).rev().find(|&c| blocked(c));
+            match wall {
+                Some(column) => {
+                    self.position.x = (column + 1) as f32 * grid.tile.x;
+                    self.velocity.x = 0.0;
+                    events.hit_wall = true;
+                }
+                None => self.position.x += dx,
+            }
+        }
+
+        // Keep walkers on the floor up and down slopes and steps
+        if self.on_ground {
+            let bottom = self.position.y + size.y;
+            let reach = self.config.step_height + dx.abs();
+            // Leaving a ledge edge onto a slope drops the floor under the center by up to half
+            // the width at once
+            let floor = self.floor(grid, bottom - reach, bottom + reach, one_way).or_else(|| {
+                self.floor(grid, bottom, bottom + reach + size.x / 2.0, one_way).filter(|(_, slope)| *slope)
+            });
+            match floor {
+                Some((surface, slope)) => {
+                    self.position.y = surface - size.y;
+                    self.on_slope = slope;
+                }
+                None => {
+                    self.on_ground = false;
+                    self.on_slope = false;
+                }
+            }
+        }
+    }
+
+    fn move_y(&mut self, grid: &Grid, dy: f32, one_way: bool, events: &mut ControllerEvents) {
+        let size = self.config.size;
+        if dy < 0.0 {
+            let top = self.position.y;
+            let (left, right) = (self.position.x, self.position.x + size.x);
+            let ceiling = (grid.row(top + dy)..grid.row(top)).rev().find(|&row| {
+                (grid.column(left)..=grid.last_column(right)).any(|c| {
+                    let flags = grid.flags(c, row);
+                    flags.contains(TileFlags::SOLID) || is_slope(flags)
+                })
+            });
+            match ceiling {
+                Some(row) => {
+                    self.position.y = (row + 1) as f32 * grid.tile.y;
+                    self.velocity.y = 0.0;
+                    events.hit_ceiling = true;
+                }
+                None => self.position.y += dy,
+            }
+            self.on_ground = false;
+            self.on_slope = false;
+            return;
+        }
+        let bottom = self.position.y + size.y;
+        match self.floor(grid, bottom, bottom + dy, one_way) {
+            Some((surface, slope)) => {
+                self.position.y = surface - size.y;
+                self.velocity.y = 0.0;
+                self.on_ground = true;
+                self.on_slope = slope;
+            }
+            None => {
+                self.position.y += dy;
+                self.on_ground = false;
+                self.on_slope = false;
+            }
+        }
+    }
+
+    fn floor(&self, grid: &Grid, y_min: f32, y_max: f32, one_way: bool) -> Option<(f32, bool)> {
+        let (left, right) = (self.position.x, self.position.x + self.config.size.x);
+        grid.floor(left, right, (left + right) / 2.0, y_min, y_max, one_way)
+    }
+}
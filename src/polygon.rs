@@ -0,0 +1,110 @@
+// Arbitrary polygons, filled with an active edge table scanline algorithm
+// https://www.cs.rit.edu/~icss571/filling/
+use std::cmp::{self, Ordering};
+
+use crate::raster;
+use crate::{Pixel, Vf2d, Vi2d, YapeEngineApi};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillRule {
+    // Inside when a ray crosses the outline an odd number of times, self overlaps become holes
+    EvenOdd,
+    // Inside when the outline winds around the point, self overlaps stay filled
+    NonZero,
+}
+
+struct Edge {
+    y_min: f32,
+    y_max: f32,
+    x: f32,
+    dx_dy: f32,
+    winding: i32,
+}
+
+// Spans of a polygon, points are pixel corners and a pixel is filled when its center is inside,
+// so a rectangle polygon covers the same pixels as fill_rect. Only rows inside 0..height are
+// scanned
+pub(crate) fn spans<F: FnMut(i32, i32, i32)>(points: &[Vf2d], rule: FillRule, height: i32, mut span: F) {
+    if points.len() < 3 {
+        return;
+    }
+    let mut edges: Vec<Edge> = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .filter(|(a, b)| a.y != b.y)
+        .map(|(a, b)| {
+            let (top, bottom, winding) = if a.y < b.y { (a, b, 1) } else { (b, a, -1) };
+            Edge {
+                y_min: top.y,
+                y_max: bottom.y,
+                x: top.x,
+                dx_dy: (bottom.x - top.x) / (bottom.y - top.y),
+                winding,
+            }
+        })
+        .collect();
+    if edges.is_empty() {
+        return;
+    }
+    edges.sort_by(|a, b| a.y_min.partial_cmp(&b.y_min).unwrap_or(Ordering::Equal));
+
+    let first_row = cmp::max((edges[0].y_min - 0.5).ceil() as i32, 0);
+    let last_row = edges.iter().map(|e| e.y_max).fold(f32::MIN, f32::max);
+    let last_row = cmp::min((last_row - 0.5).ceil() as i32 - 1, height - 1);
+
+    let mut next_edge = 0;
+    let mut active: Vec<usize> = Vec::new();
+    let mut crossings: Vec<(f32, i32)> = Vec::new();
+
+    for y in first_row..=last_row {
+        let center = y as f32 + 0.5;
+        while next_edge < edges.len() && edges[next_edge].y_min <= center {
+            active.push(next_edge);
+            next_edge += 1;
+        }
+        active.retain(|&i| edges[i].y_max > center);
+
+        crossings.clear();
+        for &i in active.iter() {
+            let edge = &edges[i];
+            if edge.y_min <= center {
+                crossings.push((edge.x + (center - edge.y_min) * edge.dx_dy, edge.winding));
+            }
+        }
+        crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+        let mut winding = 0;
+        for pair in crossings.windows(2) {
+            winding += pair[0].1;
+            let inside = match rule {
+                FillRule::EvenOdd => winding % 2 != 0,
+                FillRule::NonZero => winding != 0,
+            };
+            if inside {
+                let x1 = (pair[0].0 - 0.5).ceil() as i32;
+                let x2 = (pair[1].0 - 0.5).ceil() as i32 - 1;
+                if x1 <= x2 {
+                    span(x1, x2, y);
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn draw<E: YapeEngineApi + ?Sized>(engine: &mut E, points: &[Vi2d], pixel: &Pixel) {
+    if points.len() < 2 {
+        if let Some(p) = points.first() {
+            raster::plot(engine, p.x, p.y, pixel);
+        }
+        return;
+    }
+    for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+        raster::line(a.x, a.y, b.x, b.y, |x, y| raster::plot(engine, x, y, pixel));
+    }
+}
+
+pub(crate) fn fill<E: YapeEngineApi + ?Sized>(engine: &mut E, points: &[Vi2d], rule: FillRule, pixel: &Pixel) {
+    let points: Vec<Vf2d> = points.iter().map(|p| p.to_f32()).collect();
+    let height = engine.get_screen_height() as i32;
+    spans(&points, rule, height, |x1, x2, y| engine.fill_span(x1, x2, y, pixel));
+}
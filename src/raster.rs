@@ -0,0 +1,216 @@
+// Rasterizers shared by the drawing routines, they work on signed coordinates and leave
+// clipping to the caller (see plot and YapeEngineApi::fill_span)
+use std::cmp;
+
+use crate::{Pixel, YapeEngineApi};
+
+#[inline]
+pub(crate) fn plot<E: YapeEngineApi + ?Sized>(engine: &mut E, x: i32, y: i32, pixel: &Pixel) {
+    if x >= 0 && y >= 0 {
+        engine.draw_pixel(x as u32, y as u32, pixel);
+    }
+}
+
+pub(crate) fn line<F: FnMut(i32, i32)>(x1: i32, y1: i32, x2: i32, y2: i32, mut plot: F) {
+    // Bresenham's line algorithm
+    // https://www.freecodecamp.org/news/how-to-code-your-first-algorithm-draw-a-line-ca121f9a1395/
+    let mut x: i32;
+    let mut y: i32;
+
+    // Calculate line deltas
+    let dx: i32 = x2 - x1;
+    let dy: i32 = y2 - y1;
+    // Create a positive copy of deltas (makes iterating easier)
+    let dx1: i32 = i32::abs(dx);
+    let dy1: i32 = i32::abs(dy);
+    // Calculate error intervals for both axis
+    let mut px: i32 = 2 * dy1 - dx1;
+    let mut py: i32 = 2 * dx1 - dy1;
+    let xe: i32;
+    let ye: i32;
+
+    // The line is X-axis dominant
+    if dy1 <= dx1 {
+
+        if dx >= 0 {
+            // Line is drawn left to right
+            x = x1; y = y1; xe = x2;
+        } else {
+            // Line is drawn right to left (swap ends)
+            x = x2; y = y2; xe = x1;
+        }
+
+        plot(x, y); // Draw first pixel
+
+        // Rasterize the line
+        while x < xe {
+            x += 1;
+
+            // Deal with octants...
+            if px < 0 {
+                px += 2 * dy1;
+            } else {
+                if (dx < 0 && dy < 0) || (dx > 0 && dy > 0) {
+                    y += 1;
+                } else {
+                    y -= 1;
+                }
+                px += 2 * (dy1 - dx1);
+            }
+
+            // Draw pixel from line span at
+            // currently rasterized position
+            plot(x, y);
+        }
+
+    } else { // The line is Y-axis dominant
+
+        // Line is drawn bottom to top
+        if dy >= 0 {
+            x = x1; y = y1; ye = y2;
+        } else { // Line is drawn top to bottom
+            x = x2; y = y2; ye = y1;
+        }
+
+        plot(x, y); // Draw first pixel
+
+        // Rasterize the line
+        while y < ye {
+            y += 1;
+
+            // Deal with octants...
+            if py <= 0 {
+                py += 2 * dx1;
+            } else {
+                if (dx < 0 && dy < 0) || (dx > 0 && dy > 0) {
+                    x += 1;
+                } else {
+                    x -= 1;
+                }
+                py += 2 * (dx1 - dy1);
+            }
+
+            // Draw pixel from line span at
+            // currently rasterized position
+            plot(x, y);
+        }
+    }
+}
+
+pub(crate) fn circle<F: FnMut(i32, i32)>(ix: i32, iy: i32, iradius: i32, mut plot: F) {
+    // Midpoint circle algorithm
+    // https://rosettacode.org/wiki/Bitmap/Midpoint_circle_algorithm#C.23
+    let mut ddf_x: i32 = 0;
+    let mut ddf_y: i32 = -2 * iradius;
+    let mut f: i32 = 1 - iradius;
+    let mut _x: i32 = 0;
+    let mut _y: i32 = iradius;
+
+    plot(ix, iy + iradius);
+    plot(ix, iy - iradius);
+    plot(ix + iradius, iy);
+    plot(ix - iradius, iy);
+
+    while _x < _y {
+        if f >= 0 {
+            _y -= 1;
+            ddf_y += 2;
+            f += ddf_y;
+        }
+        _x += 1;
+        ddf_x += 2;
+        f += ddf_x + 1;
+        plot(ix + _x, iy + _y);
+        plot(ix - _x, iy + _y);
+        plot(ix + _x, iy - _y);
+        plot(ix - _x, iy - _y);
+        plot(ix + _y, iy + _x);
+        plot(ix - _y, iy + _x);
+        plot(ix + _y, iy - _x);
+        plot(ix - _y, iy - _x);
+    }
+}
+
+// Scanline generators for the fill routines, they emit inclusive spans (x1, x2, y) in no
+// particular order and never emit the same pixel twice, so blended fills don't overdraw
+
+pub(crate) fn rect_spans<F: FnMut(i32, i32, i32)>(x: i32, y: i32, w: i32, h: i32, height: i32, mut span: F) {
+    if w <= 0 {
+        return;
+    }
+    let top = cmp::max(y as i64, 0);
+    let bottom = cmp::min(y as i64 + h as i64, height as i64);
+    for row in top..bottom {
+        span(x, saturate(x as i64 + w as i64 - 1), row as i32);
+    }
+}
+
+// Rows of a filled circle, the half width of each row is the largest dx with
+// dx^2 + dy^2 <= r^2 + r, which matches the midpoint outline of draw_circle. Only rows inside
+// 0..height are emitted, so large circles don't walk their off screen rows
+pub(crate) fn circle_spans<F: FnMut(i32, i32, i32)>(cx: i32, cy: i32, radius: i32, height: i32, mut span: F) {
+    if radius < 0 {
+        return;
+    }
+    let (cx, cy, radius) = (cx as i64, cy as i64, radius as i64);
+    let limit = radius * radius + radius;
+    let top = cmp::max(cy - radius, 0);
+    let bottom = cmp::min(cy + radius, height as i64 - 1);
+    for y in top..=bottom {
+        let dy = y - cy;
+        // The float root can be off by one either way for large radii
+        let mut dx = ((limit - dy * dy) as f64).sqrt() as i64;
+        while dx > 0 && dx * dx + dy * dy > limit {
+            dx -= 1;
+        }
+        while (dx + 1) * (dx + 1) + dy * dy <= limit {
+            dx += 1;
+        }
+        span(saturate(cx - dx), saturate(cx + dx), y as i32);
+    }
+}
+
+pub(crate) fn saturate(x: i64) -> i32 {
+    cmp::min(cmp::max(x, i32::MIN as i64), i32::MAX as i64) as i32
+}
+
+// Standard scanline triangle fill, the edges are interpolated on every row between the top
+// and bottom vertex. Only rows inside 0..height are emitted
+// http://www.sunshine2k.de/coding/java/TriangleRasterization/TriangleRasterization.html
+pub(crate) fn triangle_spans<F: FnMut(i32, i32, i32)>(
+    p1: (i32, i32),
+    p2: (i32, i32),
+    p3: (i32, i32),
+    height: i32,
+    mut span: F,
+) {
+    let mut v = [p1, p2, p3];
+    v.sort_by_key(|p| p.1);
+    let [(x1, y1), (x2, y2), (x3, y3)] = v;
+    if y1 == y3 {
+        if y1 >= 0 && y1 < height {
+            span(cmp::min(x1, cmp::min(x2, x3)), cmp::max(x1, cmp::max(x2, x3)), y1);
+        }
+        return;
+    }
+
+    let edge_x = |xa: i32, ya: i32, xb: i32, yb: i32, y: i32| -> i32 {
+        if yb == ya {
+            xa
+        } else {
+            // The product of two i32 differences can overflow i64, the result lies between xa and xb
+            let (xa, ya, xb, yb, y) = (xa as i128, ya as i128, xb as i128, yb as i128, y as i128);
+            (xa + (xb - xa) * (y - ya) / (yb - ya)) as i32
+        }
+    };
+
+    for y in cmp::max(y1, 0)..=cmp::min(y3, height - 1) {
+        let long = edge_x(x1, y1, x3, y3, y);
+        let short = if y < y2 || (y == y2 && y1 != y2) {
+            edge_x(x1, y1, x2, y2, y)
+        } else {
+            edge_x(x2, y2, x3, y3, y)
+        };
+        span(cmp::min(long, short), cmp::max(long, short), y);
+    }
+}
@@ -0,0 +1,443 @@
+// Spatial indexes for many moving entities: a uniform grid hash for objects of similar size and
+// a quadtree for mixed sizes. Entities are any copyable key with a bounding rect in world
+// units, queries return keys whose rect touches the query area
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::camera::Camera2D;
+use crate::math::{Rectf, Vf2d};
+use crate::{Pixel, YapeEngineApi};
+
+// Squared distance from a point to the closest point of a rect, 0 inside
+fn distance2(rect: &Rectf, point: Vf2d) -> f32 {
+    let closest = point.clamp(rect.pos(), Vf2d::new(rect.right(), rect.bottom()));
+    (point - closest).length2()
+}
+
+// Unlike Rect::intersects, rects touching on an edge and empty rects still match
+fn touches(a: &Rectf, b: &Rectf) -> bool {
+    a.x <= b.right() && b.x <= a.right() && a.y <= b.bottom() && b.y <= a.bottom()
+}
+
+fn closer<T>(best: Option<(f32, T)>, id: T, distance2: f32) -> Option<(f32, T)> {
+    match best {
+        Some((d, _)) if d <= distance2 => best,
+        _ => Some((distance2, id)),
+    }
+}
+
+// Cells at exactly ring steps from (x, y)
+fn ring_cells_around(x: i32, y: i32, ring: i32) -> Vec<(i32, i32)> {
+    if ring == 0 {
+        return vec![(x, y)];
+    }
+    let mut cells = Vec::with_capacity(ring as usize * 8);
+    for dx in -ring..=ring {
+        cells.push((x + dx, y - ring));
+        cells.push((x + dx, y + ring));
+    }
+    for dy in 1 - ring..ring {
+        cells.push((x - ring, y + dy));
+        cells.push((x + ring, y + dy));
+    }
+    cells
+}
+
+pub struct SpatialHash<T> {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<T>>,
+    items: HashMap<T, Rectf>,
+}
+
+impl<T: Copy + Eq + Hash> SpatialHash<T> {
+    // Cells a little larger than the typical entity keep every entity in at most four cells
+    pub fn new(cell_size: f32) -> SpatialHash<T> {
+        SpatialHash { cell_size: cell_size.max(f32::EPSILON), cells: HashMap::new(), items: HashMap::new() }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.items.clear();
+    }
+
+    pub fn bounds(&self, id: T) -> Option<Rectf> {
+        self.items.get(&id).copied()
+    }
+
+    // Inclusive range of cells a rect covers
+    fn cell_range(&self, rect: &Rectf) -> (i32, i32, i32, i32) {
+        let cell = |v: f32| (v / self.cell_size).floor() as i32;
+        (cell(rect.x), cell(rect.y), cell(rect.right()), cell(rect.bottom()))
+    }
+
+    fn cells_of(&self, rect: &Rectf) -> impl Iterator<Item = (i32, i32)> {
+        let (x1, y1, x2, y2) = self.cell_range(rect);
+        (y1..=y2).flat_map(move |y| (x1..=x2).map(move |x| (x, y)))
+    }
+
+    // Inserting a key that is already there moves it
+    pub fn insert(&mut self, id: T, bounds: Rectf) {
+        self.remove(id);
+        for cell in self.cells_of(&bounds).collect::<Vec<_>>() {
+            self.cells.entry(cell).or_default().push(id);
+        }
+        self.items.insert(id, bounds);
+    }
+
+    pub fn remove(&mut self, id: T) -> bool {
+        let bounds = match self.items.remove(&id) {
+            Some(bounds) => bounds,
+            None => return false,
+        };
+        for cell in self.cells_of(&bounds).collect::<Vec<_>>() {
+            if let Some(list) = self.cells.get_mut(&cell) {
+                list.retain(|other| *other != id);
+                if list.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+        true
+    }
+
+    // Only touches the cell lists when the entity changed cells, returns false for unknown keys
+    pub fn update(&mut self, id: T, bounds: Rectf) -> bool {
+        let old = match self.items.get(&id) {
+            Some(old) => *old,
+            None => return false,
+        };
+        if self.cell_range(&old) == self.cell_range(&bounds) {
+            self.items.insert(id, bounds);
+        } else {
+            self.insert(id, bounds);
+        }
+        true
+    }
+
+    pub fn query_rect(&self, area: &Rectf) -> Vec<T> {
+        // Areas covering more cells than there are entities are cheaper to check entity by entity
+        let (x1, y1, x2, y2) = self.cell_range(area);
+        let area_cells = (x2 as i64 - x1 as i64 + 1).saturating_mul(y2 as i64 - y1 as i64 + 1);
+        if area_cells > self.items.len() as i64 * 4 {
+            return self.items.iter().filter(|(_, bounds)| touches(bounds, area)).map(|(id, _)| *id).collect();
+        }
+        let mut seen = HashSet::new();
+        let mut found = Vec::new();
+        for cell in self.cells_of(area) {
+            for id in self.cells.get(&cell).into_iter().flatten() {
+                if seen.insert(*id) && touches(&self.items[id], area) {
+                    found.push(*id);
+                }
+            }
+        }
+        found
+    }
+
+    pub fn query_radius(&self, center: Vf2d, radius: f32) -> Vec<T> {
+        let area = Rectf::new(center.x - radius, center.y - radius, radius * 2.0, radius * 2.0);
+        let mut found = self.query_rect(&area);
+        found.retain(|id| distance2(&self.items[id], center) <= radius * radius);
+        found
+    }
+
+    // Closest entity by distance to its rect, searching rings of cells outward from the point
+    pub fn nearest(&self, point: Vf2d) -> Option<T> {
+        if self.items.is_empty() {
+            return None;
+        }
+        let (cx, cy, _, _) = self.cell_range(&Rectf::new(point.x, point.y, 0.0, 0.0));
+        // Rings past the occupied cells can't find anything new
+        let (x1, y1, x2, y2) = self.cells.keys().fold((cx, cy, cx, cy), |(x1, y1, x2, y2), &(x, y)| {
+            (x1.min(x), y1.min(y), x2.max(x), y2.max(y))
+        });
+        let (cx64, cy64) = (cx as i64, cy as i64);
+        let max_ring = (cx64 - x1 as i64).max(x2 as i64 - cx64).max(cy64 - y1 as i64).max(y2 as i64 - cy64);
+        let mut best: Option<(f32, T)> = None;
+
+        // Far from a sparse crowd checking every entity beats walking empty rings
+        let ring_cells = (2 * max_ring + 1).saturating_pow(2);
+        if ring_cells > self.items.len() as i64 * 4 {
+            for (id, bounds) in self.items.iter() {
+                best = closer(best, *id, distance2(bounds, point));
+            }
+            return best.map(|(_, id)| id);
+        }
+        for ring in 0..=max_ring as i32 {
+            // Everything in this ring or further is at least this far away
+            let ring_distance = (ring - 1).max(0) as f32 * self.cell_size;
+            if best.is_some_and(|(d, _)| d < ring_distance * ring_distance) {
+                break;
+            }
+            for (x, y) in ring_cells_around(cx, cy, ring) {
+                for id in self.cells.get(&(x, y)).into_iter().flatten() {
+                    best = closer(best, *id, distance2(&self.items[id], point));
+                }
+            }
+        }
+        best.map(|(_, id)| id)
+    }
+
+    // Outlines every occupied cell in world space
+    pub fn debug_draw<E: YapeEngineApi + ?Sized>(&self, engine: &mut E, camera: &Camera2D, pixel: &Pixel) {
+        for &(x, y) in self.cells.keys() {
+            let rect = Rectf::new(x as f32 * self.cell_size, y as f32 * self.cell_size, self.cell_size, self.cell_size);
+            engine.draw_rect_w(camera, &rect, pixel);
+        }
+    }
+}
+
+struct Node<T> {
+    bounds: Rectf,
+    depth: u32,
+    parent: Option<usize>,
+    // Index of the first of four consecutive children
+    children: Option<usize>,
+    // Entities that fit no child
+    items: Vec<(T, Rectf)>,
+}
+
+// Entities live in the deepest node whose area contains them whole, or in the root when they
+// are outside of it. Nodes split once they hold more than max_items and merge back when their
+// subtree drops to max_items
+pub struct QuadTree<T> {
+    max_items: usize,
+    max_depth: u32,
+    nodes: Vec<Node<T>>,
+    // First index of child blocks that were merged away
+    free: Vec<usize>,
+    items: HashMap<T, usize>,
+}
+
+impl<T: Copy + Eq + Hash> QuadTree<T> {
+    pub fn new(bounds: Rectf, max_items: usize, max_depth: u32) -> QuadTree<T> {
+        QuadTree {
+            max_items: max_items.max(1),
+            max_depth,
+            nodes: vec![Node { bounds, depth: 0, parent: None, children: None, items: Vec::new() }],
+            free: Vec::new(),
+            items: HashMap::new(),
+        }
+    }
+
+    pub fn bounds(&self) -> Rectf {
+        self.nodes[0].bounds
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        let bounds = self.bounds();
+        self.nodes.truncate(1);
+        self.nodes[0] = Node { bounds, depth: 0, parent: None, children: None, items: Vec::new() };
+        self.free.clear();
+        self.items.clear();
+    }
+
+    pub fn get(&self, id: T) -> Option<Rectf> {
+        let node = *self.items.get(&id)?;
+        self.nodes[node].items.iter().find(|(other, _)| *other == id).map(|(_, r)| *r)
+    }
+
+    // Inserting a key that is already there moves it
+    pub fn insert(&mut self, id: T, bounds: Rectf) {
+        self.remove(id);
+        self.insert_from(0, id, bounds);
+    }
+
+    fn insert_from(&mut self, start: usize, id: T, bounds: Rectf) {
+        let node = self.descend(start, &bounds);
+        self.nodes[node].items.push((id, bounds));
+        self.items.insert(id, node);
+        if self.nodes[node].children.is_none()
+            && self.nodes[node].items.len() > self.max_items
+            && self.nodes[node].depth < self.max_depth
+        {
+            self.split(node);
+        }
+    }
+
+    fn descend(&self, mut node: usize, bounds: &Rectf) -> usize {
+        while let Some(first) = self.nodes[node].children {
+            match (first..first + 4).find(|&c| self.nodes[c].bounds.contains_rect(bounds)) {
+                Some(child) => node = child,
+                None => break,
+            }
+        }
+        node
+    }
+
+    fn split(&mut self, node: usize) {
+        let Rectf { x, y, w, h } = self.nodes[node].bounds;
+        let (hw, hh) = (w / 2.0, h / 2.0);
+        let quadrants = [
+            Rectf::new(x, y, hw, hh),
+            Rectf::new(x + hw, y, w - hw, hh),
+            Rectf::new(x, y + hh, hw, h - hh),
+            Rectf::new(x + hw, y + hh, w - hw, h - hh),
+        ];
+        let depth = self.nodes[node].depth + 1;
+        let make = |bounds| Node { bounds, depth, parent: Some(node), children: None, items: Vec::new() };
+        let first = match self.free.pop() {
+            Some(first) => {
+                for (i, bounds) in quadrants.iter().enumerate() {
+                    self.nodes[first + i] = make(*bounds);
+                }
+                first
+            }
+            None => {
+                let first = self.nodes.len();
+                self.nodes.extend(quadrants.iter().map(|b| make(*b)));
+                first
+            }
+        };
+        self.nodes[node].children = Some(first);
+        for (id, bounds) in std::mem::take(&mut self.nodes[node].items) {
+            self.insert_from(node, id, bounds);
+        }
+    }
+
+    pub fn remove(&mut self, id: T) -> bool {
+        let node = match self.items.remove(&id) {
+            Some(node) => node,
+            None => return false,
+        };
+        self.nodes[node].items.retain(|(other, _)| *other != id);
+        // Merge upward while a subtree fits in one node again, starting at the node itself since
+        // entities straddling its children keep it from being a leaf
+        let mut current = if self.nodes[node].children.is_some() { Some(node) } else { self.nodes[node].parent };
+        while let Some(parent) = current {
+            if !self.try_merge(parent) {
+                break;
+            }
+            current = self.nodes[parent].parent;
+        }
+        true
+    }
+
+    // Folds the children of node back into it when they are leaves and hold few enough entities
+    fn try_merge(&mut self, node: usize) -> bool {
+        let first = match self.nodes[node].children {
+            Some(first) => first,
+            None => return false,
+        };
+        let children = first..first + 4;
+        if children.clone().any(|c| self.nodes[c].children.is_some()) {
+            return false;
+        }
+        let total = self.nodes[node].items.len() + children.clone().map(|c| self.nodes[c].items.len()).sum::<usize>();
+        if total > self.max_items {
+            return false;
+        }
+        for c in children {
+            for (id, bounds) in std::mem::take(&mut self.nodes[c].items) {
+                self.items.insert(id, node);
+                self.nodes[node].items.push((id, bounds));
+            }
+        }
+        self.nodes[node].children = None;
+        self.free.push(first);
+        true
+    }
+
+    // Stays in place while insert would pick the same node, returns false for unknown keys
+    pub fn update(&mut self, id: T, bounds: Rectf) -> bool {
+        let node = match self.items.get(&id) {
+            Some(&node) => node,
+            None => return false,
+        };
+        if self.descend(0, &bounds) == node {
+            if let Some(item) = self.nodes[node].items.iter_mut().find(|(other, _)| *other == id) {
+                item.1 = bounds;
+            }
+            return true;
+        }
+        self.remove(id);
+        self.insert_from(0, id, bounds);
+        true
+    }
+
+    // Visits the entities of every node whose area passes node_test, the root is always visited
+    // since it also holds entities outside of its area
+    fn visit<N: Fn(&Rectf) -> bool, F: FnMut(T, &Rectf)>(&self, node_test: N, mut f: F) {
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            for (id, bounds) in node.items.iter() {
+                f(*id, bounds);
+            }
+            if let Some(first) = node.children {
+                stack.extend((first..first + 4).filter(|&c| node_test(&self.nodes[c].bounds)));
+            }
+        }
+    }
+
+    pub fn query_rect(&self, area: &Rectf) -> Vec<T> {
+        let mut found = Vec::new();
+        self.visit(|node| touches(node, area), |id, bounds| {
+            if touches(bounds, area) {
+                found.push(id);
+            }
+        });
+        found
+    }
+
+    pub fn query_radius(&self, center: Vf2d, radius: f32) -> Vec<T> {
+        let mut found = Vec::new();
+        let r2 = radius * radius;
+        self.visit(|node| distance2(node, center) <= r2, |id, bounds| {
+            if distance2(bounds, center) <= r2 {
+                found.push(id);
+            }
+        });
+        found
+    }
+
+    // Closest entity by distance to its rect, nodes farther than the best so far are skipped
+    pub fn nearest(&self, point: Vf2d) -> Option<T> {
+        let mut best: Option<(f32, T)> = None;
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            for (id, bounds) in node.items.iter() {
+                best = closer(best, *id, distance2(bounds, point));
+            }
+            if let Some(first) = node.children {
+                for child in first..first + 4 {
+                    if best.is_none_or(|(b, _)| distance2(&self.nodes[child].bounds, point) < b) {
+                        stack.push(child);
+                    }
+                }
+            }
+        }
+        best.map(|(_, id)| id)
+    }
+
+    // Outlines every node in world space
+    pub fn debug_draw<E: YapeEngineApi + ?Sized>(&self, engine: &mut E, camera: &Camera2D, pixel: &Pixel) {
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            engine.draw_rect_w(camera, &self.nodes[node].bounds, pixel);
+            if let Some(first) = self.nodes[node].children {
+                stack.extend(first..first + 4);
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+// Patterned outlines, the pattern advances one step per plotted pixel and carries over
+// from one segment to the next so corners of rects and polylines don't restart it
+use std::f32::consts::PI;
+
+use crate::raster;
+use crate::{Pixel, Vi2d, YapeEngineApi};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    // 32 pixel mask read from the most significant bit and repeated, like olcPixelGameEngine's
+    // DrawLine pattern, so 0xF0F0F0F0 draws four pixels and skips four
+    Pattern(u32),
+    // Lengths in pixels
+    Dash { dash: u32, gap: u32 },
+}
+
+struct Stipple {
+    style: LineStyle,
+    step: u32,
+}
+
+impl Stipple {
+    fn new(style: LineStyle) -> Stipple {
+        Stipple { style, step: 0 }
+    }
+
+    fn next(&mut self) -> bool {
+        let step = self.step;
+        self.step = self.step.wrapping_add(1);
+        match self.style {
+            LineStyle::Solid => true,
+            LineStyle::Pattern(pattern) => (pattern >> (31 - step % 32)) & 1 == 1,
+            LineStyle::Dash { dash, gap } => dash + gap == 0 || step % (dash + gap) < dash,
+        }
+    }
+}
+
+// Bresenham pixels of a line in order from a to b, raster::line may walk it backwards
+fn line_pixels(a: Vi2d, b: Vi2d, out: &mut Vec<Vi2d>) {
+    let start = out.len();
+    raster::line(a.x, a.y, b.x, b.y, |x, y| out.push(Vi2d::new(x, y)));
+    if out[start] != a {
+        out[start..].reverse();
+    }
+}
+
+// Pixels along the points, shared vertices appear once
+fn path_pixels(points: &[Vi2d]) -> Vec<Vi2d> {
+    let mut pixels = Vec::new();
+    match points.len() {
+        0 => {}
+        1 => pixels.push(points[0]),
+        _ => {
+            for pair in points.windows(2) {
+                if !pixels.is_empty() {
+                    pixels.pop();
+                }
+                line_pixels(pair[0], pair[1], &mut pixels);
+            }
+        }
+    }
+    pixels
+}
+
+fn stroke<E: YapeEngineApi + ?Sized>(engine: &mut E, pixels: &[Vi2d], style: LineStyle, pixel: &Pixel) {
+    let mut stipple = Stipple::new(style);
+    for p in pixels {
+        if stipple.next() {
+            raster::plot(engine, p.x, p.y, pixel);
+        }
+    }
+}
+
+pub(crate) fn polyline<E: YapeEngineApi + ?Sized>(engine: &mut E, points: &[Vi2d], style: LineStyle, pixel: &Pixel) {
+    stroke(engine, &path_pixels(points), style, pixel);
+}
+
+// Same outline as draw_rect, walked clockwise from the top left corner
+pub(crate) fn rect<E: YapeEngineApi + ?Sized>(engine: &mut E, pos: Vi2d, size: Vi2d, style: LineStyle, pixel: &Pixel) {
+    let end = pos + size;
+    let mut pixels = path_pixels(&[pos, Vi2d::new(end.x, pos.y), end, Vi2d::new(pos.x, end.y), pos]);
+    if pixels.len() > 1 {
+        pixels.pop();
+    }
+    stroke(engine, &pixels, style, pixel);
+}
+
+// Same pixels as draw_circle, ordered by angle clockwise from the positive x axis
+pub(crate) fn circle<E: YapeEngineApi + ?Sized>(engine: &mut E, center: Vi2d, radius: i32, style: LineStyle, pixel: &Pixel) {
+    let mut pixels: Vec<(f32, Vi2d)> = Vec::new();
+    raster::circle(0, 0, radius, |x, y| {
+        pixels.push(((y as f32).atan2(x as f32).rem_euclid(2.0 * PI), Vi2d::new(x, y)));
+    });
+    pixels.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    pixels.dedup_by_key(|(_, p)| *p);
+    let pixels: Vec<Vi2d> = pixels.into_iter().map(|(_, p)| center + p).collect();
+    stroke(engine, &pixels, style, pixel);
+}
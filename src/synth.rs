@@ -0,0 +1,264 @@
+// Procedural sound synthesis, based on olcPixelGameEngine's sound extension
+// and javidx9's "Code-It-Yourself! Sound Synthesizer" series
+// https://github.com/OneLoneCoder/synth
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Waveform {
+    Sine,
+    Square,
+    Saw,
+    Triangle,
+    Noise,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oscillator {
+    pub waveform: Waveform,
+    pub frequency: f32,
+    pub amplitude: f32,
+}
+
+impl Oscillator {
+    pub fn new(waveform: Waveform, frequency: f32) -> Oscillator {
+        Oscillator { waveform, frequency, amplitude: 1.0 }
+    }
+
+    pub fn sample(&self, time: f64) -> f32 {
+        let phase = time * self.frequency as f64;
+        let value = match self.waveform {
+            Waveform::Sine => (2.0 * PI * phase).sin(),
+            Waveform::Square => if phase.fract() < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Saw => 2.0 * phase.fract() - 1.0,
+            Waveform::Triangle => 1.0 - 4.0 * (phase.fract() - 0.5).abs(),
+            // Sample and hold noise, a new random value every period (like the NES noise channel)
+            Waveform::Noise => noise(phase.floor() as i64),
+        };
+        value as f32 * self.amplitude
+    }
+}
+
+// Stateless hash noise in [-1.0, 1.0], so oscillators stay deterministic
+fn noise(step: i64) -> f64 {
+    let mut x = step as u64 ^ 0x9e37_79b9_7f4a_7c15;
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^= x >> 31;
+    (x >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Envelope {
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+    pub peak: f32,
+}
+
+impl Envelope {
+    pub fn new(attack: f32, decay: f32, sustain: f32, release: f32) -> Envelope {
+        Envelope { attack, decay, sustain, release, peak: 1.0 }
+    }
+
+    // Amplitude at `time` for a note pressed at `on_time` and, if already released, at `off_time`
+    pub fn amplitude(&self, time: f64, on_time: f64, off_time: Option<f64>) -> f32 {
+        match off_time {
+            Some(off_time) if time >= off_time => {
+                let released_from = self.held_amplitude((off_time - on_time) as f32);
+                let t = (time - off_time) as f32;
+                if t >= self.release {
+                    0.0
+                } else {
+                    released_from * (1.0 - t / self.release)
+                }
+            }
+            _ => self.held_amplitude((time - on_time) as f32),
+        }
+    }
+
+    fn held_amplitude(&self, t: f32) -> f32 {
+        if t < 0.0 {
+            0.0
+        } else if t < self.attack {
+            (t / self.attack) * self.peak
+        } else if t < self.attack + self.decay {
+            let d = (t - self.attack) / self.decay;
+            self.peak + d * (self.sustain - self.peak)
+        } else {
+            self.sustain
+        }
+    }
+}
+
+impl Default for Envelope {
+    fn default() -> Envelope {
+        Envelope::new(0.01, 0.05, 0.8, 0.1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voice {
+    pub oscillators: Vec<Oscillator>,
+    pub envelope: Envelope,
+    pub volume: f32,
+    // Released automatically after this many seconds, `None` plays until `Synth::release`
+    pub duration: Option<f32>,
+}
+
+impl Voice {
+    pub fn new(waveform: Waveform, frequency: f32) -> Voice {
+        Voice {
+            oscillators: vec![Oscillator::new(waveform, frequency)],
+            envelope: Envelope::default(),
+            volume: 1.0,
+            duration: None,
+        }
+    }
+
+    pub fn with_envelope(mut self, envelope: Envelope) -> Voice {
+        self.envelope = envelope;
+        self
+    }
+
+    pub fn with_volume(mut self, volume: f32) -> Voice {
+        self.volume = volume;
+        self
+    }
+
+    pub fn with_duration(mut self, duration: f32) -> Voice {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn with_oscillator(mut self, oscillator: Oscillator) -> Voice {
+        self.oscillators.push(oscillator);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VoiceId(u64);
+
+struct ActiveVoice {
+    id: VoiceId,
+    voice: Voice,
+    on_time: f64,
+    off_time: Option<f64>,
+}
+
+pub type SynthFunction = Box<dyn FnMut(f64, f64) -> f32 + Send>;
+
+pub struct Synth {
+    sample_rate: u32,
+    global_time: f64,
+    volume: f32,
+    next_id: u64,
+    voices: Vec<ActiveVoice>,
+    user_function: Option<SynthFunction>,
+}
+
+impl Synth {
+    pub fn new(sample_rate: u32) -> Synth {
+        Synth {
+            sample_rate,
+            global_time: 0.0,
+            volume: 1.0,
+            next_id: 0,
+            voices: Vec::new(),
+            user_function: None,
+        }
+    }
+
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn get_time(&self) -> f64 {
+        self.global_time
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+    }
+
+    pub fn get_volume(&self) -> f32 {
+        self.volume
+    }
+
+    // Called once per sample with (global time, time step), its output is mixed with the voices
+    pub fn set_user_function(&mut self, function: Option<SynthFunction>) {
+        self.user_function = function;
+    }
+
+    pub fn play(&mut self, voice: Voice) -> VoiceId {
+        let id = VoiceId(self.next_id);
+        self.next_id += 1;
+        let off_time = voice.duration.map(|d| self.global_time + d as f64);
+        self.voices.push(ActiveVoice { id, voice, on_time: self.global_time, off_time });
+        id
+    }
+
+    pub fn release(&mut self, id: VoiceId) {
+        let now = self.global_time;
+        for active in self.voices.iter_mut().filter(|v| v.id == id) {
+            if active.off_time.is_none_or(|off| off > now) {
+                active.off_time = Some(now);
+            }
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        self.voices.clear();
+    }
+
+    pub fn active_voices(&self) -> usize {
+        self.voices.len()
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        let time = self.global_time;
+        let time_step = 1.0 / self.sample_rate as f64;
+
+        let mut mixed: f32 = 0.0;
+        for active in self.voices.iter() {
+            let amplitude = active.voice.envelope.amplitude(time, active.on_time, active.off_time);
+            if amplitude > 0.0 {
+                let sound: f32 = active.voice.oscillators.iter().map(|o| o.sample(time - active.on_time)).sum();
+                mixed += sound * amplitude * active.voice.volume;
+            }
+        }
+        if let Some(function) = self.user_function.as_mut() {
+            mixed += function(time, time_step);
+        }
+
+        self.voices.retain(|active| match active.off_time {
+            Some(off_time) => time < off_time + active.voice.envelope.release as f64,
+            None => true,
+        });
+        self.global_time += time_step;
+
+        (mixed * self.volume).clamp(-1.0, 1.0)
+    }
+
+    pub fn fill(&mut self, out: &mut [f32]) {
+        for sample in out.iter_mut() {
+            *sample = self.next_sample();
+        }
+    }
+
+    // Same sample on every channel of an interleaved buffer
+    pub fn fill_interleaved(&mut self, out: &mut [f32], channels: usize) {
+        for frame in out.chunks_mut(channels) {
+            let sample = self.next_sample();
+            for s in frame.iter_mut() {
+                *s = sample;
+            }
+        }
+    }
+}
+
+// Frequency in Hz of a MIDI note number (69 = A4 = 440Hz)
+pub fn note_frequency(note: i32) -> f32 {
+    440.0 * 2f32.powf((note - 69) as f32 / 12.0)
+}
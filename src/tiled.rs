@@ -0,0 +1,766 @@
+// Tiled map editor import for orthogonal TMX (XML) and TMJ (JSON) maps, tilesets can be
+// embedded or external (TSX and TSJ) and all tilesets of a map are merged into one TileSet
+// https://doc.mapeditor.org/en/stable/reference/tmx-map-format/
+// https://doc.mapeditor.org/en/stable/reference/json-map-format/
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use base64::Engine;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use serde_json::Value;
+
+use crate::tilemap::{AnimationFrame, TileFlags, TileId, TileMap, TileSet};
+use crate::{Pixel, Sprite, Vf2d, YapeError, YapeErrorKind, YapeResult};
+
+// High gid bits Tiled uses for flipped and rotated tiles, they are cleared and tiles draw unflipped
+const FLIP_MASK: u32 = 0xF000_0000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Color(Pixel),
+    // Path as written in the map
+    File(String),
+    // Id of an object of the map, 0 when unset
+    Object(u32),
+    Class(Properties),
+}
+
+pub type Properties = HashMap<String, PropertyValue>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjectShape {
+    Rect,
+    Ellipse,
+    Point,
+    // Points relative to the object position
+    Polygon(Vec<Vf2d>),
+    Polyline(Vec<Vf2d>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    // Tiled's class, called type before Tiled 1.9
+    pub class: String,
+    // Map pixels, the bottom left corner for tile objects and the top left corner otherwise
+    pub position: Vf2d,
+    pub size: Vf2d,
+    // Degrees clockwise around position
+    pub rotation: f32,
+    pub visible: bool,
+    // Set for tile objects
+    pub gid: Option<TileId>,
+    pub shape: ObjectShape,
+    pub properties: Properties,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectLayer {
+    pub name: String,
+    pub visible: bool,
+    pub objects: Vec<TiledObject>,
+    pub properties: Properties,
+}
+
+pub struct TiledMap {
+    // Tile layers in drawing order, the tiles' "solid", "one_way", "slope_up_right" and
+    // "slope_up_left" bool properties become TileFlags and tile animations become animated tiles
+    pub map: TileMap,
+    pub properties: Properties,
+    // Properties of the tile layers, by layer name
+    pub layer_properties: HashMap<String, Properties>,
+    pub object_layers: Vec<ObjectLayer>,
+}
+
+impl TiledMap {
+    pub fn object_layer(&self, name: &str) -> Option<&ObjectLayer> {
+        self.object_layers.iter().find(|l| l.name == name)
+    }
+
+    // First object with the given name in any object layer
+    pub fn find_object(&self, name: &str) -> Option<&TiledObject> {
+        self.object_layers.iter().flat_map(|l| l.objects.iter()).find(|o| o.name == name)
+    }
+}
+
+struct RawTile {
+    id: u32,
+    properties: Properties,
+    // (tile id, milliseconds)
+    animation: Vec<(u32, u32)>,
+}
+
+struct RawTileset {
+    first_gid: u32,
+    tile_w: u32,
+    tile_h: u32,
+    columns: u32,
+    count: u32,
+    spacing: u32,
+    margin: u32,
+    image: PathBuf,
+    tiles: Vec<RawTile>,
+}
+
+struct RawTileLayer {
+    name: String,
+    visible: bool,
+    data: Vec<u32>,
+    properties: Properties,
+}
+
+struct RawMap {
+    width: u32,
+    height: u32,
+    tile_w: u32,
+    tile_h: u32,
+    properties: Properties,
+    tilesets: Vec<RawTileset>,
+    tile_layers: Vec<RawTileLayer>,
+    object_layers: Vec<ObjectLayer>,
+}
+
+fn decode_error(message: &str) -> YapeError {
+    YapeError::new(YapeErrorKind::Decode, message)
+}
+
+fn source_error<E: std::error::Error + Send + Sync + 'static>(error: E) -> YapeError {
+    YapeError::with_source(YapeErrorKind::Decode, error.to_string().as_str(), error)
+}
+
+// Picks the format from the extension: .tmx is XML, .tmj and .json are JSON
+pub fn load<P: AsRef<Path>>(path: P) -> YapeResult<TiledMap> {
+    let path = path.as_ref();
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("tmx") | Some("xml") => load_tmx(path),
+        Some("tmj") | Some("json") => load_tmj(path),
+        _ => Err(decode_error(format!("unknown Tiled map format {}", path.display()).as_str())),
+    }
+}
+
+pub fn load_tmx<P: AsRef<Path>>(path: P) -> YapeResult<TiledMap> {
+    let path = path.as_ref();
+    parse_tmx(&fs::read_to_string(path)?, &parent_dir(path))
+}
+
+pub fn load_tmj<P: AsRef<Path>>(path: P) -> YapeResult<TiledMap> {
+    let path = path.as_ref();
+    parse_tmj(&fs::read_to_string(path)?, &parent_dir(path))
+}
+
+// External tilesets and images are looked up relative to base_dir
+pub fn parse_tmx(text: &str, base_dir: &Path) -> YapeResult<TiledMap> {
+    build(tmx::map(text, base_dir)?)
+}
+
+pub fn parse_tmj(text: &str, base_dir: &Path) -> YapeResult<TiledMap> {
+    build(tmj::map(text, base_dir)?)
+}
+
+fn parent_dir(path: &Path) -> PathBuf {
+    path.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+fn external_tileset(first_gid: u32, path: &Path) -> YapeResult<RawTileset> {
+    let text = fs::read_to_string(path)?;
+    let dir = parent_dir(path);
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("tsj") | Some("json") => tmj::tileset(first_gid, &serde_json::from_str(&text).map_err(source_error)?, &dir),
+        _ => {
+            let document = roxmltree::Document::parse(&text).map_err(source_error)?;
+            tmx::tileset(first_gid, document.root_element(), &dir)
+        }
+    }
+}
+
+// "#AARRGGBB" or "#RRGGBB", an empty value is an unset color
+fn parse_color(value: &str) -> YapeResult<Pixel> {
+    let hex = value.trim_start_matches('#');
+    let channel = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| decode_error(format!("invalid color {}", value).as_str()))
+    };
+    if !hex.is_ascii() {
+        return Err(decode_error(format!("invalid color {}", value).as_str()));
+    }
+    match hex.len() {
+        0 => Ok(Pixel::default()),
+        6 => Ok(Pixel::rgb(channel(0)?, channel(2)?, channel(4)?)),
+        8 => Ok(Pixel::rgba(channel(2)?, channel(4)?, channel(6)?, channel(0)?)),
+        _ => Err(decode_error(format!("invalid color {}", value).as_str())),
+    }
+}
+
+// Little endian gids, optionally zlib or gzip compressed
+fn decode_base64(text: &str, compression: &str) -> YapeResult<Vec<u32>> {
+    let bytes = base64::engine::general_purpose::STANDARD.decode(text.trim()).map_err(source_error)?;
+    let bytes = match compression {
+        "" => bytes,
+        "zlib" => {
+            let mut out = Vec::new();
+            ZlibDecoder::new(bytes.as_slice()).read_to_end(&mut out).map_err(source_error)?;
+            out
+        }
+        "gzip" => {
+            let mut out = Vec::new();
+            GzDecoder::new(bytes.as_slice()).read_to_end(&mut out).map_err(source_error)?;
+            out
+        }
+        other => return Err(decode_error(format!("unsupported layer compression {}", other).as_str())),
+    };
+    if bytes.len() % 4 != 0 {
+        return Err(decode_error("layer data is not a whole number of tiles"));
+    }
+    Ok(bytes.chunks_exact(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect())
+}
+
+fn decode_csv(text: &str) -> YapeResult<Vec<u32>> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<u32>().map_err(|_| decode_error(format!("invalid tile {}", s).as_str())))
+        .collect()
+}
+
+fn tile_flags(properties: &Properties) -> TileFlags {
+    let mut flags = TileFlags::NONE;
+    if properties.get("solid") == Some(&PropertyValue::Bool(true)) {
+        flags |= TileFlags::SOLID;
+    }
+    if properties.get("one_way") == Some(&PropertyValue::Bool(true)) {
+        flags |= TileFlags::ONE_WAY;
+    }
+    if properties.get("slope_up_right") == Some(&PropertyValue::Bool(true)) {
+        flags |= TileFlags::SLOPE_UP_RIGHT;
+    }
+    if properties.get("slope_up_left") == Some(&PropertyValue::Bool(true)) {
+        flags |= TileFlags::SLOPE_UP_LEFT;
+    }
+    flags
+}
+
+// Sizes, ids and gids come straight from the file, a malformed one mustn't overflow
+fn checked(value: Option<u32>) -> YapeResult<u32> {
+    value.ok_or_else(|| decode_error("tile ids or sizes overflow"))
+}
+
+// A zero tile size would divide by zero when the map is drawn or queried
+fn tile_size(size: u32) -> YapeResult<u32> {
+    match size {
+        0 => Err(decode_error("tile sizes must not be zero")),
+        size => Ok(size),
+    }
+}
+
+// Every tileset is copied into one sheet with a slot per gid, so gids index the sheet directly
+fn build(raw: RawMap) -> YapeResult<TiledMap> {
+    let (tile_w, tile_h) = (raw.tile_w, raw.tile_h);
+    if raw.tilesets.iter().any(|t| t.tile_w != tile_w || t.tile_h != tile_h) {
+        return Err(decode_error("tilesets with a tile size different from the map are not supported"));
+    }
+    let area = checked(raw.width.checked_mul(raw.height))?;
+    let mut total = 0;
+    for tileset in raw.tilesets.iter() {
+        if tileset.first_gid < 1 {
+            return Err(decode_error(format!("invalid tileset firstgid {}", tileset.first_gid).as_str()));
+        }
+        total = total.max(checked(tileset.first_gid.checked_add(tileset.count))? - 1);
+    }
+    let columns = raw.tilesets.iter().map(|t| t.columns).max().unwrap_or(1).max(1);
+    let rows = total.div_ceil(columns);
+    let (sheet_w, sheet_h) = (checked(columns.checked_mul(tile_w))?, checked(rows.checked_mul(tile_h))?);
+    checked(sheet_w.checked_mul(sheet_h))?;
+    let mut sheet = Sprite::new(sheet_w, sheet_h);
+
+    for tileset in raw.tilesets.iter() {
+        let image = Sprite::load_png(&tileset.image)?;
+        let step_x = checked(tile_w.checked_add(tileset.spacing))?;
+        let step_y = checked(tile_h.checked_add(tileset.spacing))?;
+        for local in 0..tileset.count {
+            let slot = tileset.first_gid + local - 1;
+            let sx = (local % tileset.columns).checked_mul(step_x).and_then(|x| x.checked_add(tileset.margin));
+            let sy = (local / tileset.columns).checked_mul(step_y).and_then(|y| y.checked_add(tileset.margin));
+            let (sx, sy) = (checked(sx)?, checked(sy)?);
+            let (dx, dy) = ((slot % columns) * tile_w, (slot / columns) * tile_h);
+            for y in 0..tile_h {
+                for x in 0..tile_w {
+                    sheet.set_pixel(dx + x, dy + y, image.get_pixel(sx.saturating_add(x), sy.saturating_add(y)));
+                }
+            }
+        }
+    }
+
+    let mut set = TileSet::new(sheet, tile_w, tile_h);
+    for tileset in raw.tilesets.iter() {
+        for tile in tileset.tiles.iter() {
+            let gid = checked(tileset.first_gid.checked_add(tile.id))?;
+            set.set_flags(gid, tile_flags(&tile.properties));
+            let frames = tile
+                .animation
+                .iter()
+                .map(|&(id, ms)| {
+                    let tile = checked(tileset.first_gid.checked_add(id))?;
+                    Ok(AnimationFrame { tile, duration: ms as f32 / 1000.0 })
+                })
+                .collect::<YapeResult<_>>()?;
+            set.set_animation(gid, frames);
+        }
+    }
+
+    let mut map = TileMap::new(set, raw.width, raw.height);
+    let mut layer_properties = HashMap::new();
+    for layer in raw.tile_layers {
+        if layer.data.len() != area as usize {
+            return Err(decode_error(format!("layer {} doesn't match the map size", layer.name).as_str()));
+        }
+        let index = map.add_layer(&layer.name);
+        let gids: Vec<TileId> = layer.data.iter().map(|gid| gid & !FLIP_MASK).collect();
+        map.layers[index].fill_from(&gids);
+        map.layers[index].visible = layer.visible;
+        layer_properties.insert(layer.name, layer.properties);
+    }
+
+    Ok(TiledMap { map, properties: raw.properties, layer_properties, object_layers: raw.object_layers })
+}
+
+fn check_map(orientation: &str, infinite: bool) -> YapeResult<()> {
+    if orientation != "orthogonal" {
+        return Err(decode_error(format!("unsupported map orientation {}", orientation).as_str()));
+    }
+    if infinite {
+        return Err(decode_error("infinite maps are not supported"));
+    }
+    Ok(())
+}
+
+fn parse_points<'a, I: Iterator<Item = (&'a str, &'a str)>>(points: I) -> YapeResult<Vec<Vf2d>> {
+    points
+        .map(|(x, y)| match (x.trim().parse::<f32>(), y.trim().parse::<f32>()) {
+            (Ok(x), Ok(y)) => Ok(Vf2d::new(x, y)),
+            _ => Err(decode_error(format!("invalid point {},{}", x, y).as_str())),
+        })
+        .collect()
+}
+
+mod tmx {
+    use super::*;
+    use roxmltree::Node;
+
+    fn attr<T: FromStr>(node: Node, name: &str) -> YapeResult<Option<T>> {
+        match node.attribute(name) {
+            Some(value) => value.trim().parse::<T>().map(Some).map_err(|_| {
+                decode_error(format!("invalid {} attribute {} on <{}>", name, value, node.tag_name().name()).as_str())
+            }),
+            None => Ok(None),
+        }
+    }
+
+    fn required<T: FromStr>(node: Node, name: &str) -> YapeResult<T> {
+        attr(node, name)?.ok_or_else(|| {
+            decode_error(format!("missing {} attribute on <{}>", name, node.tag_name().name()).as_str())
+        })
+    }
+
+    fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+        node.children().find(|n| n.has_tag_name(name))
+    }
+
+    fn visible(node: Node) -> YapeResult<bool> {
+        Ok(attr::<u8>(node, "visible")?.unwrap_or(1) != 0)
+    }
+
+    fn properties(node: Node) -> YapeResult<Properties> {
+        let mut values = Properties::new();
+        let list = match child(node, "properties") {
+            Some(list) => list,
+            None => return Ok(values),
+        };
+        for property in list.children().filter(|n| n.has_tag_name("property")) {
+            let name: String = required(property, "name")?;
+            let kind = property.attribute("type").unwrap_or("string");
+            // Multi-line strings are stored as text instead of a value attribute
+            let text = property.attribute("value").or_else(|| property.text()).unwrap_or("");
+            let value = match kind {
+                "bool" => PropertyValue::Bool(text == "true"),
+                "int" => PropertyValue::Int(required(property, "value")?),
+                "float" => PropertyValue::Float(required(property, "value")?),
+                "color" => PropertyValue::Color(parse_color(text)?),
+                "file" => PropertyValue::File(text.to_string()),
+                "object" => PropertyValue::Object(required(property, "value")?),
+                "class" => PropertyValue::Class(properties(property)?),
+                _ => PropertyValue::String(text.to_string()),
+            };
+            values.insert(name, value);
+        }
+        Ok(values)
+    }
+
+    pub(super) fn map(text: &str, base_dir: &Path) -> YapeResult<RawMap> {
+        let document = roxmltree::Document::parse(text).map_err(source_error)?;
+        let root = document.root_element();
+        if !root.has_tag_name("map") {
+            return Err(decode_error("TMX root element is not <map>"));
+        }
+        check_map(root.attribute("orientation").unwrap_or("orthogonal"), attr::<u8>(root, "infinite")? == Some(1))?;
+
+        let mut tilesets = Vec::new();
+        for node in root.children().filter(|n| n.has_tag_name("tileset")) {
+            let first_gid: u32 = required(node, "firstgid")?;
+            tilesets.push(match node.attribute("source") {
+                Some(source) => external_tileset(first_gid, &base_dir.join(source))?,
+                None => tileset(first_gid, node, base_dir)?,
+            });
+        }
+
+        let mut raw = RawMap {
+            width: required(root, "width")?,
+            height: required(root, "height")?,
+            tile_w: tile_size(required(root, "tilewidth")?)?,
+            tile_h: tile_size(required(root, "tileheight")?)?,
+            properties: properties(root)?,
+            tilesets,
+            tile_layers: Vec::new(),
+            object_layers: Vec::new(),
+        };
+        layers(root, true, &mut raw)?;
+        Ok(raw)
+    }
+
+    // Groups are flattened, a hidden group hides everything inside it
+    fn layers(parent: Node, parent_visible: bool, raw: &mut RawMap) -> YapeResult<()> {
+        for node in parent.children().filter(|n| n.is_element()) {
+            let name = node.attribute("name").unwrap_or("").to_string();
+            let visible = parent_visible && visible(node)?;
+            match node.tag_name().name() {
+                "layer" => raw.tile_layers.push(RawTileLayer {
+                    name,
+                    visible,
+                    data: data(node)?,
+                    properties: properties(node)?,
+                }),
+                "objectgroup" => {
+                    let objects =
+                        node.children().filter(|n| n.has_tag_name("object")).map(object).collect::<YapeResult<_>>()?;
+                    raw.object_layers.push(ObjectLayer { name, visible, objects, properties: properties(node)? });
+                }
+                "group" => layers(node, visible, raw)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn data(layer: Node) -> YapeResult<Vec<u32>> {
+        let data = child(layer, "data").ok_or_else(|| decode_error("tile layer without <data>"))?;
+        let text = data.text().unwrap_or("");
+        match data.attribute("encoding") {
+            Some("csv") => decode_csv(text),
+            Some("base64") => decode_base64(text, data.attribute("compression").unwrap_or("")),
+            Some(other) => Err(decode_error(format!("unsupported layer encoding {}", other).as_str())),
+            None => data
+                .children()
+                .filter(|n| n.has_tag_name("tile"))
+                .map(|tile| Ok(attr(tile, "gid")?.unwrap_or(0)))
+                .collect(),
+        }
+    }
+
+    fn points(node: Node) -> YapeResult<Vec<Vf2d>> {
+        let list = node.attribute("points").unwrap_or("");
+        parse_points(list.split_whitespace().map(|p| {
+            let mut xy = p.splitn(2, ',');
+            (xy.next().unwrap_or(""), xy.next().unwrap_or(""))
+        }))
+    }
+
+    fn object(node: Node) -> YapeResult<TiledObject> {
+        let shape = if child(node, "ellipse").is_some() {
+            ObjectShape::Ellipse
+        } else if child(node, "point").is_some() {
+            ObjectShape::Point
+        } else if let Some(polygon) = child(node, "polygon") {
+            ObjectShape::Polygon(points(polygon)?)
+        } else if let Some(polyline) = child(node, "polyline") {
+            ObjectShape::Polyline(points(polyline)?)
+        } else {
+            ObjectShape::Rect
+        };
+        let class = node.attribute("class").or_else(|| node.attribute("type")).unwrap_or("");
+        Ok(TiledObject {
+            id: attr(node, "id")?.unwrap_or(0),
+            name: node.attribute("name").unwrap_or("").to_string(),
+            class: class.to_string(),
+            position: Vf2d::new(attr(node, "x")?.unwrap_or(0.0), attr(node, "y")?.unwrap_or(0.0)),
+            size: Vf2d::new(attr(node, "width")?.unwrap_or(0.0), attr(node, "height")?.unwrap_or(0.0)),
+            rotation: attr(node, "rotation")?.unwrap_or(0.0),
+            visible: visible(node)?,
+            gid: attr::<u32>(node, "gid")?.map(|gid| gid & !FLIP_MASK),
+            shape,
+            properties: properties(node)?,
+        })
+    }
+
+    // Tiles that fit along an image side
+    fn fit(image: u32, tile: u32, spacing: u32, margin: u32) -> u32 {
+        image.saturating_add(spacing).saturating_sub(margin.saturating_mul(2)) / tile.saturating_add(spacing).max(1)
+    }
+
+    pub(super) fn tileset(first_gid: u32, node: Node, dir: &Path) -> YapeResult<RawTileset> {
+        let image = child(node, "image").ok_or_else(|| decode_error("image collection tilesets are not supported"))?;
+        let tile_w = tile_size(required(node, "tilewidth")?)?;
+        let tile_h = tile_size(required(node, "tileheight")?)?;
+        let spacing = attr(node, "spacing")?.unwrap_or(0);
+        let margin = attr(node, "margin")?.unwrap_or(0);
+        let image_w: u32 = attr(image, "width")?.unwrap_or(0);
+        let image_h: u32 = attr(image, "height")?.unwrap_or(0);
+        let columns = match attr(node, "columns")? {
+            Some(columns) => columns,
+            None => fit(image_w, tile_w, spacing, margin),
+        };
+        let count = match attr(node, "tilecount")? {
+            Some(count) => count,
+            None => checked(columns.checked_mul(fit(image_h, tile_h, spacing, margin)))?,
+        };
+
+        let mut tiles = Vec::new();
+        for tile in node.children().filter(|n| n.has_tag_name("tile")) {
+            let animation = match child(tile, "animation") {
+                Some(animation) => animation
+                    .children()
+                    .filter(|n| n.has_tag_name("frame"))
+                    .map(|frame| Ok((required(frame, "tileid")?, required(frame, "duration")?)))
+                    .collect::<YapeResult<_>>()?,
+                None => Vec::new(),
+            };
+            tiles.push(RawTile { id: required(tile, "id")?, properties: properties(tile)?, animation });
+        }
+
+        Ok(RawTileset {
+            first_gid,
+            tile_w,
+            tile_h,
+            columns: columns.max(1),
+            count,
+            spacing,
+            margin,
+            image: dir.join(required::<String>(image, "source")?),
+            tiles,
+        })
+    }
+}
+
+mod tmj {
+    use super::*;
+
+    fn field<'a>(value: &'a Value, key: &str) -> YapeResult<&'a Value> {
+        value.get(key).ok_or_else(|| decode_error(format!("missing {} field", key).as_str()))
+    }
+
+    // Numbers past u32 are rejected rather than truncated
+    fn to_u32(value: &Value, key: &str) -> YapeResult<u32> {
+        value
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| decode_error(format!("{} is not a 32 bit unsigned integer", key).as_str()))
+    }
+
+    fn u32_field(value: &Value, key: &str) -> YapeResult<u32> {
+        to_u32(field(value, key)?, key)
+    }
+
+    fn u32_or(value: &Value, key: &str, default: u32) -> YapeResult<u32> {
+        match value.get(key) {
+            Some(v) if v.is_u64() => to_u32(v, key),
+            _ => Ok(default),
+        }
+    }
+
+    fn f32_or(value: &Value, key: &str) -> f32 {
+        value.get(key).and_then(Value::as_f64).unwrap_or(0.0) as f32
+    }
+
+    fn str_or<'a>(value: &'a Value, key: &str) -> &'a str {
+        value.get(key).and_then(Value::as_str).unwrap_or("")
+    }
+
+    fn bool_or(value: &Value, key: &str, default: bool) -> bool {
+        value.get(key).and_then(Value::as_bool).unwrap_or(default)
+    }
+
+    fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+        value.get(key).and_then(Value::as_array).map_or(&[], |a| a.as_slice())
+    }
+
+    fn untyped(value: &Value) -> PropertyValue {
+        match value {
+            Value::Bool(b) => PropertyValue::Bool(*b),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => PropertyValue::Int(i),
+                None => PropertyValue::Float(n.as_f64().unwrap_or(0.0)),
+            },
+            Value::Object(members) => {
+                PropertyValue::Class(members.iter().map(|(k, v)| (k.clone(), untyped(v))).collect())
+            }
+            other => PropertyValue::String(other.as_str().map_or_else(|| other.to_string(), str::to_string)),
+        }
+    }
+
+    fn properties(value: &Value) -> YapeResult<Properties> {
+        let mut values = Properties::new();
+        for property in array(value, "properties") {
+            let name = field(property, "name")?.as_str().unwrap_or("").to_string();
+            let v = field(property, "value")?;
+            let parsed = match str_or(property, "type") {
+                "int" => PropertyValue::Int(v.as_i64().unwrap_or(0)),
+                "float" => PropertyValue::Float(v.as_f64().unwrap_or(0.0)),
+                "bool" => PropertyValue::Bool(v.as_bool().unwrap_or(false)),
+                "color" => PropertyValue::Color(parse_color(v.as_str().unwrap_or(""))?),
+                "file" => PropertyValue::File(v.as_str().unwrap_or("").to_string()),
+                "object" => PropertyValue::Object(if v.is_u64() { to_u32(v, "object property")? } else { 0 }),
+                _ => untyped(v),
+            };
+            values.insert(name, parsed);
+        }
+        Ok(values)
+    }
+
+    pub(super) fn map(text: &str, base_dir: &Path) -> YapeResult<RawMap> {
+        let root: Value = serde_json::from_str(text).map_err(source_error)?;
+        let orientation = match str_or(&root, "orientation") {
+            "" => "orthogonal",
+            orientation => orientation,
+        };
+        check_map(orientation, bool_or(&root, "infinite", false))?;
+
+        let mut tilesets = Vec::new();
+        for node in array(&root, "tilesets") {
+            let first_gid = u32_field(node, "firstgid")?;
+            tilesets.push(match node.get("source").and_then(Value::as_str) {
+                Some(source) => external_tileset(first_gid, &base_dir.join(source))?,
+                None => tileset(first_gid, node, base_dir)?,
+            });
+        }
+
+        let mut raw = RawMap {
+            width: u32_field(&root, "width")?,
+            height: u32_field(&root, "height")?,
+            tile_w: tile_size(u32_field(&root, "tilewidth")?)?,
+            tile_h: tile_size(u32_field(&root, "tileheight")?)?,
+            properties: properties(&root)?,
+            tilesets,
+            tile_layers: Vec::new(),
+            object_layers: Vec::new(),
+        };
+        layers(array(&root, "layers"), true, &mut raw)?;
+        Ok(raw)
+    }
+
+    fn layers(list: &[Value], parent_visible: bool, raw: &mut RawMap) -> YapeResult<()> {
+        for node in list {
+            let name = str_or(node, "name").to_string();
+            let visible = parent_visible && bool_or(node, "visible", true);
+            match str_or(node, "type") {
+                "tilelayer" => raw.tile_layers.push(RawTileLayer {
+                    name,
+                    visible,
+                    data: data(node)?,
+                    properties: properties(node)?,
+                }),
+                "objectgroup" => {
+                    let objects = array(node, "objects").iter().map(object).collect::<YapeResult<_>>()?;
+                    raw.object_layers.push(ObjectLayer { name, visible, objects, properties: properties(node)? });
+                }
+                "group" => layers(array(node, "layers"), visible, raw)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn data(layer: &Value) -> YapeResult<Vec<u32>> {
+        match field(layer, "data")? {
+            Value::Array(gids) => gids
+                .iter()
+                .map(|gid| to_u32(gid, "tile in layer data"))
+                .collect(),
+            Value::String(text) => match str_or(layer, "encoding") {
+                "base64" => decode_base64(text, str_or(layer, "compression")),
+                other => Err(decode_error(format!("unsupported layer encoding {}", other).as_str())),
+            },
+            _ => Err(decode_error("invalid layer data")),
+        }
+    }
+
+    fn points(list: &[Value]) -> YapeResult<Vec<Vf2d>> {
+        Ok(list.iter().map(|p| Vf2d::new(f32_or(p, "x"), f32_or(p, "y"))).collect())
+    }
+
+    fn object(node: &Value) -> YapeResult<TiledObject> {
+        let shape = if bool_or(node, "ellipse", false) {
+            ObjectShape::Ellipse
+        } else if bool_or(node, "point", false) {
+            ObjectShape::Point
+        } else if node.get("polygon").is_some() {
+            ObjectShape::Polygon(points(array(node, "polygon"))?)
+        } else if node.get("polyline").is_some() {
+            ObjectShape::Polyline(points(array(node, "polyline"))?)
+        } else {
+            ObjectShape::Rect
+        };
+        let class = match str_or(node, "class") {
+            "" => str_or(node, "type"),
+            class => class,
+        };
+        Ok(TiledObject {
+            id: u32_or(node, "id", 0)?,
+            name: str_or(node, "name").to_string(),
+            class: class.to_string(),
+            position: Vf2d::new(f32_or(node, "x"), f32_or(node, "y")),
+            size: Vf2d::new(f32_or(node, "width"), f32_or(node, "height")),
+            rotation: f32_or(node, "rotation"),
+            visible: bool_or(node, "visible", true),
+            gid: match node.get("gid") {
+                Some(gid) if gid.is_u64() => Some(to_u32(gid, "gid")? & !FLIP_MASK),
+                _ => None,
+            },
+            shape,
+            properties: properties(node)?,
+        })
+    }
+
+    pub(super) fn tileset(first_gid: u32, node: &Value, dir: &Path) -> YapeResult<RawTileset> {
+        let image = match node.get("image").and_then(Value::as_str) {
+            Some(image) => image,
+            None => return Err(decode_error("image collection tilesets are not supported")),
+        };
+        let tiles = array(node, "tiles")
+            .iter()
+            .map(|tile| {
+                let animation = array(tile, "animation")
+                    .iter()
+                    .map(|frame| Ok((u32_field(frame, "tileid")?, u32_field(frame, "duration")?)))
+                    .collect::<YapeResult<_>>()?;
+                Ok(RawTile { id: u32_field(tile, "id")?, properties: properties(tile)?, animation })
+            })
+            .collect::<YapeResult<_>>()?;
+        Ok(RawTileset {
+            first_gid,
+            tile_w: tile_size(u32_field(node, "tilewidth")?)?,
+            tile_h: tile_size(u32_field(node, "tileheight")?)?,
+            columns: u32_field(node, "columns")?.max(1),
+            count: u32_field(node, "tilecount")?,
+            spacing: u32_or(node, "spacing", 0)?,
+            margin: u32_or(node, "margin", 0)?,
+            image: dir.join(image),
+            tiles,
+        })
+    }
+}
@@ -0,0 +1,368 @@
+// Tile maps over a sprite sheet. Tile ids follow Tiled's convention: 0 is an empty cell and
+// id n is the nth tile of the sheet counting from 1, left to right and top to bottom
+use std::collections::HashMap;
+use std::ops::{BitOr, BitOrAssign};
+
+use crate::collision::{self, Hit};
+use crate::{Recti, Sprite, Vf2d, Vi2d, YapeEngineApi};
+
+pub type TileId = u32;
+
+pub const EMPTY_TILE: TileId = 0;
+
+// Layers are stored in square chunks so drawing can skip empty parts of large maps
+const CHUNK_SIZE: i32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TileFlags(u8);
+
+impl TileFlags {
+    pub const NONE: TileFlags = TileFlags(0);
+    // Blocks movement from every side
+    pub const SOLID: TileFlags = TileFlags(1);
+    // Only blocks movement coming down onto its top edge
+    pub const ONE_WAY: TileFlags = TileFlags(2);
+    // 45 degree floors filling the lower triangle of the cell, rising toward the right or the
+    // left. Walkers follow the surface under their center and walk through them sideways
+    pub const SLOPE_UP_RIGHT: TileFlags = TileFlags(4);
+    pub const SLOPE_UP_LEFT: TileFlags = TileFlags(8);
+
+    pub fn contains(&self, other: TileFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for TileFlags {
+    type Output = TileFlags;
+    fn bitor(self, other: TileFlags) -> TileFlags {
+        TileFlags(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for TileFlags {
+    fn bitor_assign(&mut self, other: TileFlags) {
+        self.0 |= other.0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationFrame {
+    pub tile: TileId,
+    // Seconds
+    pub duration: f32,
+}
+
+pub struct TileSet {
+    pub sheet: Sprite,
+    pub tile_size: Vi2d,
+    columns: u32,
+    count: u32,
+    flags: Vec<TileFlags>,
+    animations: HashMap<TileId, Vec<AnimationFrame>>,
+}
+
+impl TileSet {
+    // Zero tile sizes are taken as 1 so cell lookups never divide by zero
+    pub fn new(sheet: Sprite, tile_w: u32, tile_h: u32) -> TileSet {
+        let (tile_w, tile_h) = (tile_w.max(1), tile_h.max(1));
+        let columns = sheet.width / tile_w;
+        let count = columns * (sheet.height / tile_h);
+        TileSet {
+            sheet,
+            tile_size: Vi2d::new(tile_w as i32, tile_h as i32),
+            columns,
+            count,
+            flags: vec![TileFlags::NONE; count as usize],
+            animations: HashMap::new(),
+        }
+    }
+
+    pub fn tile_count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn set_flags(&mut self, tile: TileId, flags: TileFlags) {
+        if tile != EMPTY_TILE && tile <= self.count {
+            self.flags[tile as usize - 1] = flags;
+        }
+    }
+
+    pub fn flags(&self, tile: TileId) -> TileFlags {
+        if tile == EMPTY_TILE || tile > self.count {
+            TileFlags::NONE
+        } else {
+            self.flags[tile as usize - 1]
+        }
+    }
+
+    // Cells holding tile show the frames in turn, looping
+    pub fn set_animation(&mut self, tile: TileId, frames: Vec<AnimationFrame>) {
+        if frames.is_empty() {
+            self.animations.remove(&tile);
+        } else {
+            self.animations.insert(tile, frames);
+        }
+    }
+
+    pub fn animation(&self, tile: TileId) -> Option<&[AnimationFrame]> {
+        self.animations.get(&tile).map(|frames| frames.as_slice())
+    }
+
+    // Tile shown for tile at the given time in seconds
+    pub fn resolve(&self, tile: TileId, time: f32) -> TileId {
+        let frames = match self.animations.get(&tile) {
+            Some(frames) => frames,
+            None => return tile,
+        };
+        let total: f32 = frames.iter().map(|f| f.duration.max(0.0)).sum();
+        if total <= 0.0 {
+            return frames[0].tile;
+        }
+        let mut t = time.rem_euclid(total);
+        for frame in frames {
+            if t < frame.duration {
+                return frame.tile;
+            }
+            t -= frame.duration.max(0.0);
+        }
+        frames[frames.len() - 1].tile
+    }
+
+    // Area of the sheet holding tile
+    pub fn source_rect(&self, tile: TileId) -> Option<Recti> {
+        if tile == EMPTY_TILE || tile > self.count {
+            return None;
+        }
+        let index = tile - 1;
+        let (w, h) = (self.tile_size.x, self.tile_size.y);
+        Some(Recti::new((index % self.columns) as i32 * w, (index / self.columns) as i32 * h, w, h))
+    }
+}
+
+struct Chunk {
+    tiles: Vec<TileId>,
+    used: u32,
+}
+
+pub struct TileLayer {
+    pub name: String,
+    pub visible: bool,
+    width: u32,
+    height: u32,
+    chunks_x: u32,
+    chunks: Vec<Option<Chunk>>,
+}
+
+impl TileLayer {
+    pub fn new(name: &str, width: u32, height: u32) -> TileLayer {
+        let chunks_x = width.div_ceil(CHUNK_SIZE as u32);
+        let chunks_y = height.div_ceil(CHUNK_SIZE as u32);
+        TileLayer {
+            name: name.to_string(),
+            visible: true,
+            width,
+            height,
+            chunks_x,
+            chunks: (0..chunks_x * chunks_y).map(|_| None).collect(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn locate(&self, x: i32, y: i32) -> Option<(usize, usize)> {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return None;
+        }
+        let chunk = (y / CHUNK_SIZE) as u32 * self.chunks_x + (x / CHUNK_SIZE) as u32;
+        let cell = (y % CHUNK_SIZE) * CHUNK_SIZE + x % CHUNK_SIZE;
+        Some((chunk as usize, cell as usize))
+    }
+
+    // Cells outside the layer read as empty
+    pub fn get(&self, x: i32, y: i32) -> TileId {
+        match self.locate(x, y) {
+            Some((chunk, cell)) => self.chunks[chunk].as_ref().map_or(EMPTY_TILE, |c| c.tiles[cell]),
+            None => EMPTY_TILE,
+        }
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, tile: TileId) {
+        let (chunk, cell) = match self.locate(x, y) {
+            Some(location) => location,
+            None => return,
+        };
+        let slot = &mut self.chunks[chunk];
+        if slot.is_none() {
+            if tile == EMPTY_TILE {
+                return;
+            }
+            *slot = Some(Chunk { tiles: vec![EMPTY_TILE; (CHUNK_SIZE * CHUNK_SIZE) as usize], used: 0 });
+        }
+        if let Some(c) = slot {
+            let old = std::mem::replace(&mut c.tiles[cell], tile);
+            match (old == EMPTY_TILE, tile == EMPTY_TILE) {
+                (true, false) => c.used += 1,
+                (false, true) => c.used -= 1,
+                _ => {}
+            }
+            if c.used == 0 {
+                *slot = None;
+            }
+        }
+    }
+
+    // Row major tiles, width * height of them
+    pub fn fill_from(&mut self, tiles: &[TileId]) {
+        for (i, &tile) in tiles.iter().enumerate().take((self.width * self.height) as usize) {
+            self.set(i as i32 % self.width as i32, i as i32 / self.width as i32, tile);
+        }
+    }
+}
+
+pub struct TileMap {
+    pub tileset: TileSet,
+    pub layers: Vec<TileLayer>,
+    width: u32,
+    height: u32,
+    // Seconds, drives animated tiles
+    time: f32,
+}
+
+impl TileMap {
+    pub fn new(tileset: TileSet, width: u32, height: u32) -> TileMap {
+        TileMap { tileset, layers: Vec::new(), width, height, time: 0.0 }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // Size in pixels
+    pub fn pixel_size(&self) -> Vi2d {
+        Vi2d::new(self.width as i32 * self.tileset.tile_size.x, self.height as i32 * self.tileset.tile_size.y)
+    }
+
+    // Appends an empty layer drawn above the existing ones, returns its index
+    pub fn add_layer(&mut self, name: &str) -> usize {
+        self.layers.push(TileLayer::new(name, self.width, self.height));
+        self.layers.len() - 1
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&TileLayer> {
+        self.layers.iter().find(|l| l.name == name)
+    }
+
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut TileLayer> {
+        self.layers.iter_mut().find(|l| l.name == name)
+    }
+
+    pub fn update(&mut self, time_elapsed: f32) {
+        self.time += time_elapsed;
+    }
+
+    // Flags of every layer's tile in the cell combined
+    pub fn flags_at(&self, x: i32, y: i32) -> TileFlags {
+        self.layers.iter().fold(TileFlags::NONE, |flags, layer| flags | self.tileset.flags(layer.get(x, y)))
+    }
+
+    pub fn is_solid(&self, x: i32, y: i32) -> bool {
+        self.flags_at(x, y).contains(TileFlags::SOLID)
+    }
+
+    // Cell containing a point in map pixels
+    pub fn cell_at(&self, pos: Vf2d) -> Vi2d {
+        let size = self.tileset.tile_size.to_f32();
+        Vi2d::new((pos.x / size.x).floor() as i32, (pos.y / size.y).floor() as i32)
+    }
+
+    // Cells overlapping a rect in map pixels, clamped to the map
+    pub fn cells_in(&self, area: &Recti) -> Recti {
+        let size = self.tileset.tile_size;
+        let x1 = area.x.div_euclid(size.x).max(0);
+        let y1 = area.y.div_euclid(size.y).max(0);
+        let x2 = (area.right() + size.x - 1).div_euclid(size.x).min(self.width as i32);
+        let y2 = (area.bottom() + size.y - 1).div_euclid(size.y).min(self.height as i32);
+        Recti::new(x1, y1, (x2 - x1).max(0), (y2 - y1).max(0))
+    }
+
+    // First solid cell along the ray from origin to origin + direction in map pixels, with the
+    // cell the ray starts in skipped
+    pub fn raycast(&self, origin: Vf2d, direction: Vf2d) -> Option<(Vi2d, Hit)> {
+        collision::raycast_grid(origin, direction, self.tileset.tile_size.to_f32(), |x, y| self.is_solid(x, y))
+    }
+
+    // Draws every visible layer into viewport (screen cells), with scroll as the map pixel
+    // shown at the viewport's top left corner. Only the chunks and tiles inside the viewport are
+    // visited and tiles on its border are cut to it
+    pub fn draw<E: YapeEngineApi + ?Sized>(&self, engine: &mut E, scroll: Vf2d, viewport: &Recti) {
+        for index in 0..self.layers.len() {
+            self.draw_layer(engine, index, scroll, viewport);
+        }
+    }
+
+    pub fn draw_layer<E: YapeEngineApi + ?Sized>(&self, engine: &mut E, index: usize, scroll: Vf2d, viewport: &Recti) {
+        let layer = match self.layers.get(index) {
+            Some(layer) if layer.visible => layer,
+            _ => return,
+        };
+        let screen = Recti::new(0, 0, engine.get_screen_width() as i32, engine.get_screen_height() as i32);
+        let viewport = match viewport.clip(&screen) {
+            Some(viewport) => viewport,
+            None => return,
+        };
+        // Whole pixel scrolling, fractions are dropped so tiles stay on the pixel grid
+        let scroll = scroll.floor().to_i32();
+        let origin = viewport.pos() - scroll;
+        let cells = self.cells_in(&Recti::from_pos_size(scroll, viewport.size()));
+        if cells.is_empty() {
+            return;
+        }
+        let size = self.tileset.tile_size;
+
+        let first_chunk = Vi2d::new(cells.x / CHUNK_SIZE, cells.y / CHUNK_SIZE);
+        let last_chunk = Vi2d::new((cells.right() - 1) / CHUNK_SIZE, (cells.bottom() - 1) / CHUNK_SIZE);
+        for cy in first_chunk.y..=last_chunk.y {
+            for cx in first_chunk.x..=last_chunk.x {
+                let chunk = match &layer.chunks[(cy as u32 * layer.chunks_x + cx as u32) as usize] {
+                    Some(chunk) => chunk,
+                    None => continue,
+                };
+                let x1 = (cx * CHUNK_SIZE).max(cells.x);
+                let y1 = (cy * CHUNK_SIZE).max(cells.y);
+                let x2 = ((cx + 1) * CHUNK_SIZE).min(cells.right());
+                let y2 = ((cy + 1) * CHUNK_SIZE).min(cells.bottom());
+                for y in y1..y2 {
+                    for x in x1..x2 {
+                        let tile = chunk.tiles[((y % CHUNK_SIZE) * CHUNK_SIZE + x % CHUNK_SIZE) as usize];
+                        if tile == EMPTY_TILE {
+                            continue;
+                        }
+                        let source = match self.tileset.source_rect(self.tileset.resolve(tile, self.time)) {
+                            Some(source) => source,
+                            None => continue,
+                        };
+                        let dest = Recti::new(origin.x + x * size.x, origin.y + y * size.y, size.x, size.y);
+                        if let Some(visible) = dest.clip(&viewport) {
+                            let cut = Recti::from_pos_size(source.pos() + visible.pos() - dest.pos(), visible.size());
+                            engine.draw_partial_sprite(visible.pos(), &self.tileset.sheet, &cut, 1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
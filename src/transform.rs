@@ -0,0 +1,139 @@
+// Affine transforms for sprite drawing, after olcPixelGameEngine's olcPGEX_Graphics2D
+use crate::raster;
+use crate::{Pixel, Sprite, Vf2d, YapeEngineApi};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sampling {
+    Nearest,
+    Bilinear,
+}
+
+// Row major 3x3 matrix, each builder call applies its operation after the previous ones,
+// so `Transform2D::new().rotate(a).translate(x, y)` rotates around the origin and then moves
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform2D {
+    m: [[f32; 3]; 3],
+}
+
+impl Default for Transform2D {
+    fn default() -> Transform2D {
+        Transform2D::new()
+    }
+}
+
+impl Transform2D {
+    pub fn new() -> Transform2D {
+        Transform2D { m: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]] }
+    }
+
+    fn from_rows(a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> Transform2D {
+        Transform2D { m: [[a, b, c], [d, e, f], [0.0, 0.0, 1.0]] }
+    }
+
+    pub fn translate(self, x: f32, y: f32) -> Transform2D {
+        self.then(&Transform2D::from_rows(1.0, 0.0, x, 0.0, 1.0, y))
+    }
+
+    // Radians, clockwise on screen since y points down
+    pub fn rotate(self, theta: f32) -> Transform2D {
+        let (sin, cos) = theta.sin_cos();
+        self.then(&Transform2D::from_rows(cos, -sin, 0.0, sin, cos, 0.0))
+    }
+
+    pub fn scale(self, sx: f32, sy: f32) -> Transform2D {
+        self.then(&Transform2D::from_rows(sx, 0.0, 0.0, 0.0, sy, 0.0))
+    }
+
+    pub fn shear(self, sx: f32, sy: f32) -> Transform2D {
+        self.then(&Transform2D::from_rows(1.0, sx, 0.0, sy, 1.0, 0.0))
+    }
+
+    // self followed by other
+    pub fn then(&self, other: &Transform2D) -> Transform2D {
+        let mut m = [[0.0f32; 3]; 3];
+        for (r, row) in m.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = (0..3).map(|k| other.m[r][k] * self.m[k][c]).sum();
+            }
+        }
+        Transform2D { m }
+    }
+
+    pub fn invert(&self) -> Option<Transform2D> {
+        let m = &self.m;
+        let det = m[0][0] * m[1][1] - m[0][1] * m[1][0];
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let inv = 1.0 / det;
+        let a = m[1][1] * inv;
+        let b = -m[0][1] * inv;
+        let d = -m[1][0] * inv;
+        let e = m[0][0] * inv;
+        let c = -(a * m[0][2] + b * m[1][2]);
+        let f = -(d * m[0][2] + e * m[1][2]);
+        Some(Transform2D::from_rows(a, b, c, d, e, f))
+    }
+
+    pub fn forward(&self, p: Vf2d) -> Vf2d {
+        Vf2d::new(
+            self.m[0][0] * p.x + self.m[0][1] * p.y + self.m[0][2],
+            self.m[1][0] * p.x + self.m[1][1] * p.y + self.m[1][2],
+        )
+    }
+}
+
+// Inverse maps every destination pixel inside the transformed bounds back into the sprite
+pub(crate) fn draw_sprite_transformed<E: YapeEngineApi + ?Sized>(
+    engine: &mut E,
+    sprite: &Sprite,
+    transform: &Transform2D,
+    sampling: Sampling,
+) {
+    let inverse = match transform.invert() {
+        Some(inverse) => inverse,
+        None => return,
+    };
+    let (w, h) = (sprite.width as f32, sprite.height as f32);
+    let corners = [
+        transform.forward(Vf2d::new(0.0, 0.0)),
+        transform.forward(Vf2d::new(w, 0.0)),
+        transform.forward(Vf2d::new(0.0, h)),
+        transform.forward(Vf2d::new(w, h)),
+    ];
+    let min = corners.iter().fold(corners[0], |a, c| a.min(*c));
+    let max = corners.iter().fold(corners[0], |a, c| a.max(*c));
+
+    let x1 = (min.x.floor() as i32).max(0);
+    let y1 = (min.y.floor() as i32).max(0);
+    let x2 = (max.x.ceil() as i32).min(engine.get_screen_width() as i32);
+    let y2 = (max.y.ceil() as i32).min(engine.get_screen_height() as i32);
+
+    for y in y1..y2 {
+        for x in x1..x2 {
+            let source = inverse.forward(Vf2d::new(x as f32 + 0.5, y as f32 + 0.5));
+            if source.x < 0.0 || source.y < 0.0 || source.x >= w || source.y >= h {
+                continue;
+            }
+            let pixel = match sampling {
+                Sampling::Nearest => *sprite.get_pixel(source.x as u32, source.y as u32),
+                Sampling::Bilinear => sprite.sample_bilinear(source.x / w, source.y / h),
+            };
+            raster::plot(engine, x, y, &pixel);
+        }
+    }
+}
+
+pub(crate) fn bilinear(sprite: &Sprite, u: f32, v: f32) -> Pixel {
+    // Texel centers sit at half coordinates, edges clamp
+    let x = (u * sprite.width as f32 - 0.5).max(0.0);
+    let y = (v * sprite.height as f32 - 0.5).max(0.0);
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let x1 = (x0 + 1).min(sprite.width.saturating_sub(1));
+    let y1 = (y0 + 1).min(sprite.height.saturating_sub(1));
+    let (fx, fy) = (x.fract(), y.fract());
+
+    let top = sprite.get_pixel(x0, y0).lerp(sprite.get_pixel(x1, y0), fx);
+    let bottom = sprite.get_pixel(x0, y1).lerp(sprite.get_pixel(x1, y1), fx);
+    top.lerp(&bottom, fy)
+}
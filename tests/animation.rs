@@ -0,0 +1,290 @@
+use std::fs;
+use std::path::Path;
+
+use yape::animation::{Animation, Frame, FrameEvent, PlayMode};
+use yape::aseprite;
+use yape::{Recti, Sprite, Vi2d, YapeEngine, YapeEngineApi, YapeErrorKind};
+
+fn strip(mode: PlayMode, frames: usize) -> Animation {
+    Animation::from_grid("strip", mode, Vi2d::new(2, 2), frames as u32, 0, frames as u32, 0.25)
+}
+
+// Frame indices after each step of 0.25 seconds
+fn play(animation: &mut Animation, steps: usize) -> Vec<usize> {
+    (0..steps)
+        .map(|_| {
+            animation.update(0.25);
+            animation.current_index()
+        })
+        .collect()
+}
+
+fn event(frame: usize, name: &str) -> FrameEvent {
+    FrameEvent { frame, name: name.to_string() }
+}
+
+#[test]
+fn grid_frames_read_left_to_right_then_down() {
+    let animation = Animation::from_grid("run", PlayMode::Loop, Vi2d::new(16, 8), 4, 2, 4, 0.125);
+    let sources: Vec<Recti> = animation.frames().iter().map(|f| f.source).collect();
+    assert_eq!(
+        sources,
+        vec![Recti::new(32, 0, 16, 8), Recti::new(48, 0, 16, 8), Recti::new(0, 8, 16, 8), Recti::new(16, 8, 16, 8)]
+    );
+    assert_eq!(animation.duration(), 0.5);
+    assert_eq!(animation.name, "run");
+    assert_eq!(animation.current_index(), 0);
+    assert_eq!(animation.current_frame(), Some(&Frame::new(Recti::new(32, 0, 16, 8), 0.125)));
+}
+
+#[test]
+fn play_modes_step_through_frames() {
+    assert_eq!(play(&mut strip(PlayMode::Loop, 3), 7), vec![1, 2, 0, 1, 2, 0, 1]);
+    assert_eq!(play(&mut strip(PlayMode::PingPong, 3), 9), vec![1, 2, 1, 0, 1, 2, 1, 0, 1]);
+    assert_eq!(play(&mut strip(PlayMode::PingPong, 2), 4), vec![1, 0, 1, 0]);
+    assert_eq!(play(&mut strip(PlayMode::PingPong, 1), 3), vec![0, 0, 0]);
+
+    let mut once = strip(PlayMode::Once, 3);
+    assert_eq!(play(&mut once, 2), vec![1, 2]);
+    assert!(!once.is_finished());
+    assert_eq!(play(&mut once, 3), vec![2, 2, 2]);
+    assert!(once.is_finished());
+
+    once.restart();
+    assert!(!once.is_finished());
+    assert_eq!(once.current_index(), 0);
+    assert_eq!(play(&mut once, 1), vec![1]);
+}
+
+#[test]
+fn time_carries_over_and_scales_with_speed() {
+    let mut animation = strip(PlayMode::Loop, 3);
+    animation.update(0.125);
+    assert_eq!(animation.current_index(), 0);
+    animation.update(0.125);
+    assert_eq!(animation.current_index(), 1);
+    // Several frames in one update
+    animation.update(0.5);
+    assert_eq!(animation.current_index(), 0);
+
+    animation.speed = 2.0;
+    animation.update(0.125);
+    assert_eq!(animation.current_index(), 1);
+    animation.speed = 0.0;
+    animation.update(10.0);
+    assert_eq!(animation.current_index(), 1);
+    animation.speed = -1.0;
+    animation.update(10.0);
+    assert_eq!(animation.current_index(), 1);
+
+    animation.set_frame(2);
+    assert_eq!(animation.current_index(), 2);
+    animation.set_frame(3);
+    assert_eq!(animation.current_index(), 2);
+
+    // Nothing to play
+    let mut empty = Animation::new("empty", PlayMode::Loop);
+    assert!(empty.update(1.0).is_empty());
+    assert_eq!(empty.current_frame(), None);
+    assert_eq!(empty.duration(), 0.0);
+}
+
+#[test]
+fn events_fire_for_every_frame_entered() {
+    let mut animation = strip(PlayMode::Loop, 3).with_event(0, "loop").with_event(2, "step");
+    // The first frame is entered when playback starts
+    assert_eq!(animation.update(0.25), vec![event(0, "loop")]);
+    // Skipping over frames still reports them, in order
+    assert_eq!(animation.update(0.5), vec![event(2, "step"), event(0, "loop")]);
+    animation.set_event(2, None);
+    assert_eq!(animation.update(0.75), vec![event(0, "loop")]);
+    // Events on missing frames are ignored
+    animation.set_event(7, Some("nowhere"));
+    assert_eq!(animation.frames().len(), 3);
+
+    let mut once = strip(PlayMode::Once, 2).with_event(1, "done");
+    assert_eq!(once.update(0.25), vec![event(1, "done")]);
+    // Finishing doesn't enter the last frame again
+    assert!(once.update(0.25).is_empty());
+    assert!(once.is_finished());
+    assert!(once.update(1.0).is_empty());
+
+    // Restarting or jumping enters the frame again
+    animation.restart();
+    assert_eq!(animation.update(0.0), vec![event(0, "loop")]);
+    assert!(animation.update(0.0).is_empty());
+    animation.set_event(2, Some("step"));
+    animation.set_frame(2);
+    assert_eq!(animation.update(0.125), vec![event(2, "step")]);
+
+    // Zero length frames are passed through without looping forever
+    let mut instant = Animation::new("instant", PlayMode::Loop)
+        .with_frame(Recti::new(0, 0, 1, 1), 0.0)
+        .with_frame(Recti::new(1, 0, 1, 1), 0.0)
+        .with_event(1, "tick");
+    assert!(!instant.update(0.25).is_empty());
+}
+
+#[test]
+fn long_hitches_skip_whole_cycles() {
+    let mut animation = strip(PlayMode::Loop, 3).with_event(1, "step");
+    animation.update(0.0);
+    // 400 frames, only the last cycle and the frames after it report their events
+    assert_eq!(animation.update(100.0 + 0.125), vec![event(1, "step"); 2]);
+    assert_eq!(animation.current_index(), 1);
+    // Playback carries on one frame at a time afterwards
+    assert_eq!(play(&mut animation, 4), vec![2, 0, 1, 2]);
+
+    let mut pingpong = strip(PlayMode::PingPong, 3);
+    play(&mut pingpong, 1);
+    pingpong.update(100.0);
+    assert_eq!(pingpong.current_index(), 1);
+    assert_eq!(play(&mut pingpong, 4), vec![2, 1, 0, 1]);
+
+    let mut once = strip(PlayMode::Once, 3);
+    once.update(1000.0);
+    assert!(once.is_finished());
+    assert_eq!(once.current_index(), 2);
+}
+
+#[test]
+fn draw_places_the_current_frame_with_its_offset() {
+    let mut sheet = Sprite::new(4, 2);
+    for y in 0..2 {
+        for x in 0..4 {
+            sheet.set_pixel(x, y, if x < 2 { &yape::RED } else { &yape::BLUE });
+        }
+    }
+    let mut animation = Animation::new("blink", PlayMode::Loop).with_frame(Recti::new(0, 0, 2, 2), 0.25);
+    animation.push_frame(Frame { source: Recti::new(2, 0, 2, 2), offset: Vi2d::new(1, 0), duration: 0.25, event: None });
+
+    let mut engine = YapeEngine::construct_headless(8, 8);
+    engine.clear(&yape::BLACK);
+    animation.draw(&mut engine, &sheet, Vi2d::new(1, 1), 2);
+    let target = engine.get_draw_target();
+    for y in 0..8 {
+        for x in 0..8 {
+            let inside = (1..5).contains(&x) && (1..5).contains(&y);
+            assert_eq!(*target.get_pixel(x, y), if inside { yape::RED } else { yape::BLACK }, "{} {}", x, y);
+        }
+    }
+
+    // The offset is scaled with the frame
+    animation.update(0.25);
+    engine.clear(&yape::BLACK);
+    animation.draw(&mut engine, &sheet, Vi2d::new(1, 1), 2);
+    let target = engine.get_draw_target();
+    for y in 0..8 {
+        for x in 0..8 {
+            let inside = (3..7).contains(&x) && (1..5).contains(&y);
+            assert_eq!(*target.get_pixel(x, y), if inside { yape::BLUE } else { yape::BLACK }, "{} {}", x, y);
+        }
+    }
+}
+
+const HASH_SHEET: &str = r##"{
+ "frames": {
+  "hero 0.aseprite": {"frame": {"x": 0, "y": 0, "w": 16, "h": 16}, "rotated": false, "trimmed": false,
+   "spriteSourceSize": {"x": 0, "y": 0, "w": 16, "h": 16}, "sourceSize": {"w": 16, "h": 16}, "duration": 100},
+  "hero 1.aseprite": {"frame": {"x": 16, "y": 0, "w": 12, "h": 14}, "rotated": false, "trimmed": true,
+   "spriteSourceSize": {"x": 2, "y": 1, "w": 12, "h": 14}, "sourceSize": {"w": 16, "h": 16}, "duration": 250},
+  "hero 2.aseprite": {"frame": {"x": 32, "y": 0, "w": 16, "h": 16}, "rotated": false, "trimmed": false,
+   "spriteSourceSize": {"x": 0, "y": 0, "w": 16, "h": 16}, "sourceSize": {"w": 16, "h": 16}, "duration": 100},
+  "hero 3.aseprite": {"frame": {"x": 48, "y": 0, "w": 16, "h": 16}, "rotated": false, "trimmed": false,
+   "sourceSize": {"w": 16, "h": 16}}
+ },
+ "meta": {
+  "app": "https://www.aseprite.org/", "image": "hero.png", "format": "RGBA8888",
+  "size": {"w": 64, "h": 16}, "scale": "1",
+  "frameTags": [
+   {"name": "walk", "from": 0, "to": 2, "direction": "forward"},
+   {"name": "back", "from": 0, "to": 2, "direction": "reverse"},
+   {"name": "bounce", "from": 1, "to": 3, "direction": "pingpong"}
+  ],
+  "slices": [
+   {"name": "hitbox", "color": "#0000ffff", "data": "solid", "keys": [
+    {"frame": 0, "bounds": {"x": 2, "y": 2, "w": 12, "h": 14}, "pivot": {"x": 8, "y": 16}},
+    {"frame": 2, "bounds": {"x": 3, "y": 2, "w": 10, "h": 14}, "center": {"x": 1, "y": 1, "w": 8, "h": 12}}
+   ]}
+  ]
+ }
+}"##;
+
+fn array_sheet() -> String {
+    let root: serde_json::Value = serde_json::from_str(HASH_SHEET).unwrap();
+    let frames: Vec<serde_json::Value> = root["frames"].as_object().unwrap().values().cloned().collect();
+    serde_json::json!({ "frames": frames, "meta": root["meta"] }).to_string()
+}
+
+#[test]
+fn aseprite_hash_and_array_exports_match() {
+    let sheet = aseprite::parse(HASH_SHEET, Path::new("assets")).unwrap();
+    assert_eq!(aseprite::parse(&array_sheet(), Path::new("assets")).unwrap(), sheet);
+
+    assert_eq!(sheet.image, Path::new("assets").join("hero.png"));
+    assert_eq!(sheet.size, Vi2d::new(64, 16));
+    assert_eq!(sheet.frames.len(), 4);
+    assert_eq!(
+        sheet.frames[1],
+        Frame { source: Recti::new(16, 0, 12, 14), offset: Vi2d::new(2, 1), duration: 0.25, event: None }
+    );
+    // Frames without a duration show for 100ms
+    assert_eq!(sheet.frames[3].duration, 0.1);
+    assert_eq!(sheet.frames[3].offset, Vi2d::new(0, 0));
+
+    let walk = sheet.animation("walk").unwrap();
+    assert_eq!(walk.mode, PlayMode::Loop);
+    assert_eq!(walk.frames(), &sheet.frames[0..3]);
+    let back = sheet.animation("back").unwrap();
+    let sources: Vec<i32> = back.frames().iter().map(|f| f.source.x).collect();
+    assert_eq!(sources, vec![32, 16, 0]);
+    let bounce = sheet.animation("bounce").unwrap();
+    assert_eq!(bounce.mode, PlayMode::PingPong);
+    assert_eq!(bounce.frames(), &sheet.frames[1..4]);
+    assert!(sheet.animation("jump").is_none());
+
+    let all = sheet.all_frames(PlayMode::Once);
+    assert_eq!((all.mode, all.frames()), (PlayMode::Once, &sheet.frames[..]));
+
+    let hitbox = sheet.slice("hitbox").unwrap();
+    assert_eq!(hitbox.data, "solid");
+    assert_eq!(hitbox.key_at(0).unwrap().pivot, Some(Vi2d::new(8, 16)));
+    assert_eq!(hitbox.key_at(1).unwrap().bounds, Recti::new(2, 2, 12, 14));
+    let later = hitbox.key_at(5).unwrap();
+    assert_eq!((later.frame, later.bounds), (2, Recti::new(3, 2, 10, 14)));
+    assert_eq!(later.center, Some(Recti::new(1, 1, 8, 12)));
+    assert_eq!(later.pivot, None);
+    assert!(sheet.slice("hurtbox").is_none());
+}
+
+#[test]
+fn aseprite_files_load_relative_to_their_directory() {
+    let dir = std::env::temp_dir().join(format!("yape_aseprite_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let mut image = Sprite::new(64, 16);
+    image.set_pixel(20, 3, &yape::RED);
+    image.save_png(dir.join("hero.png")).unwrap();
+    fs::write(dir.join("hero.json"), HASH_SHEET).unwrap();
+
+    let sheet = aseprite::load(dir.join("hero.json")).unwrap();
+    assert_eq!(sheet.image, dir.join("hero.png"));
+    let sprite = sheet.load_sprite().unwrap();
+    assert_eq!(*sprite.get_pixel(20, 3), yape::RED);
+
+    assert_eq!(aseprite::load(dir.join("missing.json")).unwrap_err().kind, YapeErrorKind::Io);
+}
+
+#[test]
+fn broken_aseprite_exports_are_decode_errors() {
+    let decode = |text: &str| aseprite::parse(text, Path::new("")).unwrap_err();
+
+    let error = decode("{\"frames\": ");
+    assert_eq!(error.kind, YapeErrorKind::Decode);
+    assert!(std::error::Error::source(&error).is_some());
+    assert_eq!(decode("{\"meta\": {}}").kind, YapeErrorKind::Decode);
+    assert_eq!(decode(&HASH_SHEET.replacen("\"rotated\": false", "\"rotated\": true", 1)).kind, YapeErrorKind::Decode);
+    assert_eq!(decode(&HASH_SHEET.replace("\"to\": 3", "\"to\": 4")).kind, YapeErrorKind::Decode);
+    assert_eq!(decode(&HASH_SHEET.replace("\"from\": 0, \"to\": 2", "\"from\": 2, \"to\": 1")).kind, YapeErrorKind::Decode);
+    assert_eq!(decode(&HASH_SHEET.replacen("\"w\": 16", "\"width\": 16", 1)).kind, YapeErrorKind::Decode);
+}
@@ -0,0 +1,126 @@
+use yape::antialias::LineCap;
+use yape::{Pixel, PixelMode, Vf2d, Vi2d, YapeEngine, YapeEngineApi};
+
+fn engine(w: u32, h: u32) -> YapeEngine {
+    let mut engine = YapeEngine::construct_headless(w, h);
+    engine.clear(&yape::BLACK);
+    engine
+}
+
+fn red(engine: &YapeEngine, x: u32, y: u32) -> u8 {
+    engine.get_draw_target().get_pixel(x, y).red
+}
+
+#[test]
+fn horizontal_line_is_solid_with_half_covered_ends() {
+    let mut e = engine(10, 5);
+    e.draw_line_aa(Vf2d::new(1.0, 2.0), Vf2d::new(6.0, 2.0), &yape::RED);
+    for x in 2..6 {
+        assert_eq!(red(&e, x, 2), 255);
+        assert_eq!(red(&e, x, 1), 0);
+        assert_eq!(red(&e, x, 3), 0);
+    }
+    for x in [1, 6].iter() {
+        assert!((120..=135).contains(&red(&e, *x, 2)), "end {} is {}", x, red(&e, *x, 2));
+    }
+    assert_eq!(red(&e, 0, 2), 0);
+    assert_eq!(red(&e, 7, 2), 0);
+}
+
+#[test]
+fn line_between_rows_splits_coverage() {
+    let mut e = engine(10, 5);
+    e.draw_line_aa(Vf2d::new(0.0, 1.5), Vf2d::new(9.0, 1.5), &yape::RED);
+    for x in 1..9 {
+        let (top, bottom) = (red(&e, x, 1), red(&e, x, 2));
+        assert!((120..=135).contains(&top) && (120..=135).contains(&bottom), "{} {}", top, bottom);
+    }
+}
+
+#[test]
+fn steep_lines_are_drawn_by_rows() {
+    let mut e = engine(5, 10);
+    e.draw_line_aa(Vf2d::new(2.0, 8.0), Vf2d::new(2.0, 1.0), &yape::RED);
+    for y in 2..8 {
+        assert_eq!(red(&e, 2, y), 255);
+        assert_eq!(red(&e, 1, y), 0);
+        assert_eq!(red(&e, 3, y), 0);
+    }
+}
+
+#[test]
+fn pixel_mode_is_restored() {
+    let mut e = engine(8, 8);
+    e.set_pixel_mode(PixelMode::Mask);
+    e.draw_line_aa(Vf2d::new(0.0, 0.0), Vf2d::new(7.0, 3.0), &yape::RED);
+    e.draw_circle_aa(Vi2d::new(4, 4), 2.0, &yape::RED);
+    e.draw_thick_line(Vf2d::new(0.0, 0.0), Vf2d::new(7.0, 7.0), 2.0, LineCap::Round, &yape::RED);
+    assert_eq!(*e.get_pixel_mode(), PixelMode::Mask);
+}
+
+#[test]
+fn additive_mode_is_kept() {
+    let mut e = engine(10, 5);
+    e.clear(&Pixel::rgb(100, 0, 0));
+    e.set_pixel_mode(PixelMode::Additive);
+    e.draw_line_aa(Vf2d::new(1.0, 2.0), Vf2d::new(6.0, 2.0), &Pixel::rgb(50, 0, 0));
+    assert_eq!(red(&e, 3, 2), 150);
+    // Half covered ends add half the color
+    assert!((120..=130).contains(&red(&e, 1, 2)), "{}", red(&e, 1, 2));
+    assert_eq!(red(&e, 3, 1), 100);
+    assert_eq!(*e.get_pixel_mode(), PixelMode::Additive);
+}
+
+#[test]
+fn circle_is_symmetric_and_exact_on_the_axes() {
+    let mut e = engine(17, 17);
+    e.draw_circle_aa(Vi2d::new(8, 8), 4.0, &yape::RED);
+    for (x, y) in [(8, 4), (8, 12), (4, 8), (12, 8)].iter() {
+        assert_eq!(red(&e, *x, *y), 255);
+    }
+    assert_eq!(red(&e, 8, 8), 0);
+    assert_eq!(red(&e, 8, 3), 0);
+    for dy in -5..=5i32 {
+        for dx in -5..=5i32 {
+            let at = |x: i32, y: i32| red(&e, (8 + x) as u32, (8 + y) as u32);
+            let v = at(dx, dy);
+            assert_eq!(v, at(-dx, dy));
+            assert_eq!(v, at(dx, -dy));
+            assert_eq!(v, at(dy, dx));
+        }
+    }
+}
+
+#[test]
+fn thick_line_width_and_caps() {
+    let mut butt = engine(12, 9);
+    butt.draw_thick_line(Vf2d::new(2.0, 4.0), Vf2d::new(8.0, 4.0), 3.0, LineCap::Butt, &yape::RED);
+    for y in 3..=5 {
+        assert_eq!(red(&butt, 5, y), 255);
+    }
+    assert_eq!(red(&butt, 5, 2), 0);
+    assert_eq!(red(&butt, 5, 6), 0);
+    assert!((120..=135).contains(&red(&butt, 2, 4)));
+    assert_eq!(red(&butt, 1, 4), 0);
+
+    let mut square = engine(12, 9);
+    square.draw_thick_line(Vf2d::new(2.0, 4.0), Vf2d::new(8.0, 4.0), 3.0, LineCap::Square, &yape::RED);
+    assert_eq!(red(&square, 1, 4), 255);
+    assert_eq!(red(&square, 9, 4), 255);
+    assert_eq!(red(&square, 0, 4), 0);
+
+    let mut round = engine(12, 9);
+    round.draw_thick_line(Vf2d::new(2.0, 4.0), Vf2d::new(8.0, 4.0), 3.0, LineCap::Round, &yape::RED);
+    assert_eq!(red(&round, 1, 4), 255);
+    // The round cap doesn't reach the corners a square cap fills
+    assert!(red(&round, 1, 3) < red(&square, 1, 3));
+}
+
+#[test]
+fn coverage_blends_with_the_background() {
+    let mut e = engine(10, 5);
+    e.clear(&Pixel::rgb(0, 0, 200));
+    e.draw_line_aa(Vf2d::new(0.0, 1.5), Vf2d::new(9.0, 1.5), &yape::RED);
+    let p = *e.get_draw_target().get_pixel(4, 1);
+    assert!(p.red > 100 && p.blue > 80 && p.blue < 120, "{:?}", p);
+}
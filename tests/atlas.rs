@@ -0,0 +1,151 @@
+use std::fs;
+
+use yape::atlas::{Atlas, AtlasBuilder, SkylinePacker};
+use yape::{Pixel, Recti, Sprite, Vi2d, YapeEngine, YapeEngineApi, YapeErrorKind};
+
+// Sprite with a different color in every texel, so copies can be checked texel by texel
+fn sprite(w: u32, h: u32, tag: u8) -> Sprite {
+    let mut sprite = Sprite::new(w, h);
+    for y in 0..h {
+        for x in 0..w {
+            sprite.set_pixel(x, y, &Pixel::rgb(tag, x as u8, y as u8));
+        }
+    }
+    sprite
+}
+
+fn overlaps(a: &Recti, b: &Recti) -> bool {
+    a.x < b.right() && b.x < a.right() && a.y < b.bottom() && b.y < a.bottom()
+}
+
+#[test]
+fn skyline_places_rects_lowest_then_leftmost() {
+    let mut packer = SkylinePacker::new(10, None);
+    assert_eq!(packer.height(), 0);
+    assert_eq!(packer.pack(4, 3), Some(Vi2d::new(0, 0)));
+    assert_eq!(packer.pack(4, 2), Some(Vi2d::new(4, 0)));
+    // Doesn't fit the 2 pixels left on the floor, goes on the lower of the two rects
+    assert_eq!(packer.pack(4, 5), Some(Vi2d::new(4, 2)));
+    assert_eq!(packer.height(), 7);
+    assert_eq!(packer.pack(2, 1), Some(Vi2d::new(8, 0)));
+    // Spans two segments and rests on the higher one
+    assert_eq!(packer.pack(6, 1), Some(Vi2d::new(0, 7)));
+    assert_eq!(packer.pack(11, 1), None);
+
+    let mut bounded = SkylinePacker::new(4, Some(4));
+    assert_eq!(bounded.pack(4, 3), Some(Vi2d::new(0, 0)));
+    assert_eq!(bounded.pack(4, 2), None);
+    assert_eq!(bounded.pack(4, 1), Some(Vi2d::new(0, 3)));
+    assert_eq!(bounded.height(), 4);
+}
+
+#[test]
+fn skyline_never_overlaps() {
+    let mut packer = SkylinePacker::new(64, None);
+    let mut placed: Vec<Recti> = Vec::new();
+    for i in 0..200u32 {
+        let (w, h) = (1 + i * 7 % 13, 1 + i * 5 % 11);
+        let pos = packer.pack(w, h).unwrap();
+        let rect = Recti::new(pos.x, pos.y, w as i32, h as i32);
+        assert!(rect.x >= 0 && rect.y >= 0 && rect.right() <= 64, "{:?}", rect);
+        assert!(rect.bottom() as u32 <= packer.height());
+        assert!(placed.iter().all(|other| !overlaps(&rect, other)), "{:?}", rect);
+        placed.push(rect);
+    }
+}
+
+#[test]
+fn atlas_copies_sprites_with_padding_and_extrusion() {
+    let sprites = [sprite(5, 3, 1), sprite(2, 6, 2), sprite(4, 4, 3), sprite(1, 1, 4), sprite(7, 2, 5)];
+    let names = ["wide", "tall", "square", "dot", "bar"];
+    let builder = names.iter().zip(sprites.iter()).fold(AtlasBuilder::new(16), |b, (n, s)| b.add(n, s));
+    let atlas = builder.with_padding(1).with_extrusion(2).build().unwrap();
+    assert_eq!(atlas.sprite.width, 16);
+
+    let added: Vec<&str> = atlas.regions().iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(added, names);
+    let mut areas = Vec::new();
+    for (name, source) in names.iter().zip(sprites.iter()) {
+        let region = atlas.get(name).unwrap();
+        assert_eq!((region.w, region.h), (source.width as i32, source.height as i32));
+        // Extruded edges repeat the nearest texel of the sprite
+        for y in -2..region.h + 2 {
+            for x in -2..region.w + 2 {
+                let texel = source.get_pixel(x.clamp(0, region.w - 1) as u32, y.clamp(0, region.h - 1) as u32);
+                let at = atlas.sprite.get_pixel((region.x + x) as u32, (region.y + y) as u32);
+                assert_eq!(at, texel, "{} {} {}", name, x, y);
+            }
+        }
+        // The extruded area and its padding belong to one sprite only
+        areas.push(Recti::new(region.x - 2, region.y - 2, region.w + 5, region.h + 5));
+    }
+    for (i, a) in areas.iter().enumerate() {
+        assert!(a.x >= 0 && a.y >= 0 && a.right() <= 16);
+        assert!(a.bottom() <= atlas.sprite.height as i32);
+        assert!(areas[i + 1..].iter().all(|b| !overlaps(a, b)));
+    }
+    assert!(atlas.get("missing").is_none());
+}
+
+#[test]
+fn atlas_build_fails_when_sprites_dont_fit() {
+    let big = sprite(8, 8, 1);
+    let small = sprite(4, 4, 2);
+    assert!(AtlasBuilder::new(7).add("big", &big).build().is_none());
+    assert!(AtlasBuilder::new(8).with_max_height(10).add("big", &big).add("small", &small).build().is_none());
+    let atlas = AtlasBuilder::new(12).with_max_height(8).add("big", &big).add("small", &small).build().unwrap();
+    assert_eq!(atlas.get("small"), Some(Recti::new(8, 0, 4, 4)));
+
+    // Empty sprites get an empty region and copy nothing
+    let empty = Sprite::new(0, 0);
+    let atlas = AtlasBuilder::new(4).add("empty", &empty).build().unwrap();
+    assert_eq!(atlas.get("empty"), Some(Recti::new(0, 0, 0, 0)));
+}
+
+#[test]
+fn atlas_regions_draw_like_the_source_sprite() {
+    let first = sprite(3, 2, 1);
+    let second = sprite(2, 3, 2);
+    let atlas = AtlasBuilder::new(8).with_padding(1).add("first", &first).add("second", &second).build().unwrap();
+
+    let mut engine = YapeEngine::construct_headless(8, 8);
+    engine.clear(&yape::BLACK);
+    atlas.draw(&mut engine, "second", Vi2d::new(1, 2), 1);
+    atlas.draw(&mut engine, "missing", Vi2d::new(0, 0), 1);
+    let target = engine.get_draw_target();
+    for y in 0..8 {
+        for x in 0..8 {
+            let expected = if (1..3).contains(&x) && (2..5).contains(&y) {
+                *second.get_pixel(x - 1, y - 2)
+            } else {
+                yape::BLACK
+            };
+            assert_eq!(*target.get_pixel(x, y), expected, "{} {}", x, y);
+        }
+    }
+}
+
+#[test]
+fn atlas_save_and_load_round_trip() {
+    let dir = std::env::temp_dir().join(format!("yape_atlas_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let (a, b) = (sprite(3, 5, 1), sprite(6, 2, 2));
+    let atlas = AtlasBuilder::new(16).with_extrusion(1).add("a", &a).add("b", &b).build().unwrap();
+    atlas.save(dir.join("atlas.png"), dir.join("atlas.json")).unwrap();
+
+    let loaded = Atlas::load(dir.join("atlas.json")).unwrap();
+    assert_eq!(loaded.regions(), atlas.regions());
+    assert_eq!(loaded.get("b"), atlas.get("b"));
+    assert_eq!((loaded.sprite.width, loaded.sprite.height), (atlas.sprite.width, atlas.sprite.height));
+    assert_eq!(loaded.sprite.data, atlas.sprite.data);
+
+    fs::write(dir.join("broken.json"), r#"{"regions": {}}"#).unwrap();
+    assert_eq!(Atlas::load(dir.join("broken.json")).err().unwrap().kind, YapeErrorKind::Decode);
+    fs::write(dir.join("bad.json"), r#"{"image": "atlas.png", "regions": {"a": {"x": 1}}}"#).unwrap();
+    assert_eq!(Atlas::load(dir.join("bad.json")).err().unwrap().kind, YapeErrorKind::Decode);
+    fs::write(dir.join("truncated.json"), "{").unwrap();
+    assert_eq!(Atlas::load(dir.join("truncated.json")).err().unwrap().kind, YapeErrorKind::Decode);
+    assert_eq!(Atlas::load(dir.join("missing.json")).err().unwrap().kind, YapeErrorKind::Io);
+}
@@ -0,0 +1,117 @@
+use yape::camera::Camera2D;
+use yape::{Rectf, Vf2d, Vi2d, YapeEngine, YapeEngineApi};
+
+fn close(a: Vf2d, b: Vf2d) -> bool {
+    (a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3
+}
+
+fn cameras() -> Vec<Camera2D> {
+    vec![
+        Camera2D::default(),
+        Camera2D::centered(Vf2d::new(10.0, 5.0), Vi2d::new(160, 120)),
+        Camera2D { position: Vf2d::new(-3.5, 8.0), origin: Vf2d::new(40.0, 30.0), zoom: 2.5, rotation: 0.0 },
+        Camera2D { position: Vf2d::new(100.0, -20.0), origin: Vf2d::new(64.0, 64.0), zoom: 0.25, rotation: 0.7 },
+        Camera2D { position: Vf2d::new(1.0, 1.0), origin: Vf2d::new(0.0, 0.0), zoom: 4.0, rotation: -2.0 },
+    ]
+}
+
+fn points() -> Vec<Vf2d> {
+    vec![Vf2d::new(0.0, 0.0), Vf2d::new(10.0, 5.0), Vf2d::new(-37.25, 12.5), Vf2d::new(250.0, -80.0)]
+}
+
+#[test]
+fn world_to_screen_round_trips() {
+    for camera in cameras() {
+        for p in points() {
+            let screen = camera.world_to_screen(p);
+            assert!(close(camera.screen_to_world(screen), p), "{:?} {:?}", camera, p);
+            let world = camera.screen_to_world(p);
+            assert!(close(camera.world_to_screen(world), p), "{:?} {:?}", camera, p);
+            // The transform used for sprites agrees with the point mapping
+            assert!(close(camera.transform().forward(p), screen), "{:?} {:?}", camera, p);
+        }
+    }
+}
+
+#[test]
+fn position_is_shown_at_origin_and_zoom_scales_distances() {
+    let camera = Camera2D { position: Vf2d::new(10.0, 5.0), origin: Vf2d::new(80.0, 60.0), zoom: 2.0, rotation: 0.0 };
+    assert_eq!(camera.world_to_screen(Vf2d::new(10.0, 5.0)), Vf2d::new(80.0, 60.0));
+    assert_eq!(camera.world_to_screen(Vf2d::new(12.0, 4.0)), Vf2d::new(84.0, 58.0));
+
+    let centered = Camera2D::centered(Vf2d::new(3.0, 4.0), Vi2d::new(100, 50));
+    assert_eq!(centered.world_to_screen(Vf2d::new(3.0, 4.0)), Vf2d::new(50.0, 25.0));
+
+    // Rotation turns the world around the origin without moving it
+    let rotated = Camera2D { rotation: 1.0, ..camera };
+    assert!(close(rotated.world_to_screen(camera.position), camera.origin));
+    let d = rotated.world_to_screen(Vf2d::new(13.0, 9.0)) - rotated.origin;
+    assert!((d.length() - 10.0).abs() < 1e-3);
+}
+
+#[test]
+fn zoom_at_keeps_the_anchor_fixed() {
+    for mut camera in cameras() {
+        for anchor in [Vf2d::new(0.0, 0.0), Vf2d::new(17.0, 93.0), Vf2d::new(160.0, 120.0)].iter() {
+            let before = camera.screen_to_world(*anchor);
+            let zoom = camera.zoom;
+            camera.zoom_at(*anchor, 1.5);
+            assert!((camera.zoom - zoom * 1.5).abs() < 1e-4);
+            assert!(close(camera.screen_to_world(*anchor), before), "{:?} {:?}", camera, anchor);
+            assert!(close(camera.world_to_screen(before), *anchor));
+            camera.zoom_at(*anchor, 0.5);
+            assert!(close(camera.screen_to_world(*anchor), before), "{:?} {:?}", camera, anchor);
+        }
+    }
+
+    // Zooming far out stops at a small positive zoom
+    let mut camera = Camera2D::default();
+    camera.zoom_at(Vf2d::new(5.0, 5.0), 0.0);
+    assert!(camera.zoom > 0.0);
+    assert!(camera.screen_to_world(Vf2d::new(6.0, 6.0)).x.is_finite());
+}
+
+#[test]
+fn pan_follows_the_drag() {
+    for mut camera in cameras() {
+        let grabbed = camera.screen_to_world(Vf2d::new(20.0, 30.0));
+        camera.pan(Vf2d::new(7.0, -4.0));
+        assert!(close(camera.world_to_screen(grabbed), Vf2d::new(27.0, 26.0)), "{:?}", camera);
+    }
+}
+
+#[test]
+fn visible_area_covers_the_screen() {
+    let camera = Camera2D { position: Vf2d::new(10.0, 20.0), origin: Vf2d::new(0.0, 0.0), zoom: 2.0, rotation: 0.0 };
+    let area = camera.visible_area(Vi2d::new(64, 32));
+    assert_eq!(area, Rectf::new(10.0, 20.0, 32.0, 16.0));
+
+    let rotated = Camera2D { rotation: 0.5, ..camera };
+    let area = rotated.visible_area(Vi2d::new(64, 32));
+    for corner in [Vf2d::new(0.0, 0.0), Vf2d::new(64.0, 0.0), Vf2d::new(0.0, 32.0), Vf2d::new(64.0, 32.0)].iter() {
+        let world = rotated.screen_to_world(*corner);
+        assert!(world.x >= area.x - 1e-3 && world.x <= area.right() + 1e-3);
+        assert!(world.y >= area.y - 1e-3 && world.y <= area.bottom() + 1e-3);
+    }
+}
+
+#[test]
+fn world_drawing_goes_through_the_camera() {
+    let mut engine = YapeEngine::construct_headless(16, 16);
+    let mut camera = Camera2D { position: Vf2d::new(1.0, 1.0), origin: Vf2d::new(0.0, 0.0), zoom: 2.0, rotation: 0.0 };
+    engine.fill_rect_w(&camera, &Rectf::new(2.0, 2.0, 2.0, 2.0), &yape::WHITE);
+    let target = engine.get_draw_target();
+    for y in 0..16 {
+        for x in 0..16 {
+            let inside = (2..6).contains(&x) && (2..6).contains(&y);
+            assert_eq!(*target.get_pixel(x, y) == yape::WHITE, inside, "{} {}", x, y);
+        }
+    }
+    // The mouse rests at the top left cell of a headless engine
+    assert!(close(camera.mouse_to_world(&engine), Vf2d::new(1.0, 1.0)));
+
+    // Wheel zoom does nothing while the wheel is still
+    let before = camera;
+    assert!(!camera.zoom_to_cursor(&engine, 2.0));
+    assert_eq!(camera, before);
+}
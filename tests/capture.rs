@@ -0,0 +1,163 @@
+use std::fs::{self, File};
+use std::path::PathBuf;
+
+use yape::capture::{self, RecordFormat};
+use yape::{Pixel, Sprite, YapeEngine, YapeEngineApi, YapeErrorKind};
+
+// Fresh scratch directory per test, tests run in parallel
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("yape_capture_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn scaled_rgb_repeats_columns_and_rows_and_drops_alpha() {
+    let mut sprite = Sprite::new(2, 1);
+    sprite.set_pixel(0, 0, &Pixel::rgba(1, 2, 3, 0));
+    sprite.set_pixel(1, 0, &Pixel::rgb(4, 5, 6));
+    let rgb = capture::scaled_rgb(&sprite, 2, 2);
+    let row = [1, 2, 3, 1, 2, 3, 4, 5, 6, 4, 5, 6];
+    assert_eq!(rgb, [row, row].concat());
+
+    // Zero scales are treated as one
+    assert_eq!(capture::scaled_rgb(&sprite, 0, 0), vec![1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn sprite_png_round_trip() {
+    let dir = scratch("png");
+    let mut sprite = Sprite::new(3, 2);
+    sprite.set_pixel(0, 0, &Pixel::rgba(10, 20, 30, 40));
+    sprite.set_pixel(2, 1, &yape::RED);
+    let path = dir.join("sprite.png");
+    sprite.save_png(&path).unwrap();
+
+    let loaded = Sprite::load_png(&path).unwrap();
+    assert_eq!((loaded.width, loaded.height), (3, 2));
+    assert_eq!(loaded.data, sprite.data);
+}
+
+#[test]
+fn load_png_reports_missing_files_as_io() {
+    let error = capture::load_png("does/not/exist.png").err().unwrap();
+    assert_eq!(error.kind, YapeErrorKind::Io);
+}
+
+#[test]
+fn record_frames_writes_a_numbered_png_sequence() {
+    let dir = scratch("sequence");
+    let mut engine = YapeEngine::construct_headless(4, 3);
+    engine.record_frames(&dir, 3, RecordFormat::PngSequence).unwrap();
+    assert!(engine.is_recording());
+
+    for i in 0..5 {
+        engine.clear(&Pixel::rgb(i * 10, 0, 0));
+        engine.capture_frame(1.0 / 30.0).unwrap();
+    }
+    assert!(!engine.is_recording());
+
+    for i in 0..3 {
+        let frame = capture::load_png(dir.join(format!("frame_{:05}.png", i))).unwrap();
+        assert_eq!((frame.width, frame.height), (4, 3));
+        assert_eq!(*frame.get_pixel(2, 1), Pixel::rgb(i as u8 * 10, 0, 0));
+    }
+    assert!(!dir.join("frame_00003.png").exists());
+}
+
+#[test]
+fn failed_captures_stop_the_recording() {
+    let dir = scratch("failed");
+    let mut engine = YapeEngine::construct_headless(2, 2);
+    engine.record_frames(&dir, 3, RecordFormat::PngSequence).unwrap();
+    engine.capture_frame(0.1).unwrap();
+
+    // The directory turns into a file, the next frame can't be written
+    fs::remove_dir_all(&dir).unwrap();
+    File::create(&dir).unwrap();
+    assert_eq!(engine.capture_frame(0.1).err().unwrap().kind, YapeErrorKind::Io);
+    assert!(!engine.is_recording());
+    assert!(engine.capture_frame(0.1).is_ok());
+    assert!(engine.take_capture_error().is_none());
+    fs::remove_file(&dir).unwrap();
+}
+
+#[test]
+fn record_frames_writes_an_animated_gif() {
+    let dir = scratch("gif");
+    let path = dir.join("clip.gif");
+    let mut engine = YapeEngine::construct_headless(4, 3);
+    engine.record_frames(&path, 2, RecordFormat::Gif).unwrap();
+    for _ in 0..4 {
+        engine.capture_frame(0.05).unwrap();
+    }
+    assert!(!engine.is_recording());
+
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(File::open(&path).unwrap()).unwrap();
+    assert_eq!((decoder.width(), decoder.height()), (4, 3));
+    let mut frames = 0;
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        assert_eq!(frame.delay, 5);
+        frames += 1;
+    }
+    assert_eq!(frames, 2);
+}
+
+fn decode_gif(path: &PathBuf) -> (u16, u16, Vec<gif::Frame<'static>>) {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(File::open(path).unwrap()).unwrap();
+    let (width, height) = (decoder.width(), decoder.height());
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        frames.push(frame.clone());
+    }
+    (width, height, frames)
+}
+
+#[test]
+fn gif_recording_can_be_saved_after_stopping() {
+    let dir = scratch("ring");
+    let path = dir.join("ring.gif");
+    let mut engine = YapeEngine::construct_headless(5, 4);
+    assert!(engine.save_gif(&path).is_err());
+
+    engine.start_gif_recording(1.0).unwrap();
+    for i in 0..3 {
+        engine.clear(&Pixel::rgb(0, 0, i * 100));
+        engine.capture_frame(0.1).unwrap();
+    }
+    engine.stop_gif_recording();
+    assert!(!engine.is_gif_recording());
+    // Frames after stopping are not recorded
+    engine.capture_frame(0.1).unwrap();
+    engine.save_gif(&path).unwrap();
+
+    let (width, height, frames) = decode_gif(&path);
+    assert_eq!((width, height), (5, 4));
+    assert_eq!(frames.len(), 3);
+    for (i, frame) in frames.iter().enumerate() {
+        assert_eq!(frame.delay, 10);
+        assert_eq!(&frame.buffer[..4], &[0, 0, i as u8 * 100, 255]);
+    }
+
+    // Starting again drops the old frames
+    engine.start_gif_recording(1.0).unwrap();
+    assert!(engine.save_gif(&path).is_err());
+}
+
+#[test]
+fn gif_recording_keeps_only_the_last_max_duration_seconds() {
+    let dir = scratch("window");
+    let path = dir.join("window.gif");
+    let mut engine = YapeEngine::construct_headless(2, 2);
+    engine.start_gif_recording(0.5).unwrap();
+    for _ in 0..20 {
+        engine.capture_frame(0.1).unwrap();
+    }
+    engine.save_gif(&path).unwrap();
+    assert_eq!(decode_gif(&path).2.len(), 5);
+}
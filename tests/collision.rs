@@ -0,0 +1,259 @@
+use yape::collision::{self, Circle, Hit, Shape};
+use yape::{Rectf, Vf2d, Vi2d};
+
+fn close(a: Vf2d, b: Vf2d) -> bool {
+    (a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4
+}
+
+fn same_points(mut a: Vec<Vf2d>, mut b: Vec<Vf2d>) -> bool {
+    let key = |p: &Vf2d| (p.x * 1000.0) as i64 * 1_000_000 + (p.y * 1000.0) as i64;
+    a.sort_by_key(key);
+    b.sort_by_key(key);
+    a.len() == b.len() && a.iter().zip(b.iter()).all(|(p, q)| close(*p, *q))
+}
+
+fn square(x: f32, y: f32, size: f32) -> Vec<Vf2d> {
+    vec![Vf2d::new(x, y), Vf2d::new(x + size, y), Vf2d::new(x + size, y + size), Vf2d::new(x, y + size)]
+}
+
+fn reversed(mut points: Vec<Vf2d>) -> Vec<Vf2d> {
+    points.reverse();
+    points
+}
+
+#[test]
+fn boxes_push_out_along_the_shallow_axis() {
+    let a = Rectf::new(0.0, 0.0, 4.0, 4.0);
+    let contact = collision::aabb_vs_aabb(&a, &Rectf::new(3.0, 1.0, 4.0, 2.0)).unwrap();
+    assert_eq!((contact.normal, contact.depth), (Vf2d::new(1.0, 0.0), 1.0));
+    assert!(same_points(contact.points, vec![Vf2d::new(3.5, 1.0), Vf2d::new(3.5, 3.0)]));
+
+    let contact = collision::aabb_vs_aabb(&a, &Rectf::new(1.0, -3.0, 2.0, 4.0)).unwrap();
+    assert_eq!((contact.normal, contact.depth), (Vf2d::new(0.0, -1.0), 1.0));
+    assert_eq!(contact.penetration(), Vf2d::new(0.0, -1.0));
+
+    // Touching edges don't collide
+    assert!(collision::aabb_vs_aabb(&a, &Rectf::new(4.0, 0.0, 2.0, 2.0)).is_none());
+    assert!(collision::aabb_vs_aabb(&a, &Rectf::new(5.0, 5.0, 2.0, 2.0)).is_none());
+}
+
+#[test]
+fn circles_push_apart_along_their_centers() {
+    let a = Circle::new(Vf2d::new(0.0, 0.0), 2.0);
+    let contact = collision::circle_vs_circle(&a, &Circle::new(Vf2d::new(3.0, 0.0), 2.0)).unwrap();
+    assert_eq!((contact.normal, contact.depth), (Vf2d::new(1.0, 0.0), 1.0));
+    assert_eq!(contact.points, vec![Vf2d::new(2.0, 0.0)]);
+
+    // Same center still gets a usable normal
+    let contact = collision::circle_vs_circle(&a, &Circle::new(Vf2d::new(0.0, 0.0), 1.0)).unwrap();
+    assert_eq!((contact.normal, contact.depth), (Vf2d::new(1.0, 0.0), 3.0));
+
+    assert!(collision::circle_vs_circle(&a, &Circle::new(Vf2d::new(4.0, 0.0), 2.0)).is_none());
+}
+
+#[test]
+fn box_and_circle_contacts() {
+    let rect = Rectf::new(0.0, 0.0, 4.0, 4.0);
+    let contact = collision::aabb_vs_circle(&rect, &Circle::new(Vf2d::new(5.0, 2.0), 2.0)).unwrap();
+    assert_eq!((contact.normal, contact.depth), (Vf2d::new(1.0, 0.0), 1.0));
+    assert_eq!(contact.points, vec![Vf2d::new(4.0, 2.0)]);
+
+    // Center inside leaves through the nearest side
+    let contact = collision::aabb_vs_circle(&rect, &Circle::new(Vf2d::new(1.0, 2.5), 1.0)).unwrap();
+    assert_eq!((contact.normal, contact.depth), (Vf2d::new(-1.0, 0.0), 2.0));
+    assert_eq!(contact.points, vec![Vf2d::new(0.0, 2.5)]);
+
+    // Near a corner the normal points from the corner
+    let contact = collision::aabb_vs_circle(&rect, &Circle::new(Vf2d::new(5.0, 5.0), 2.0)).unwrap();
+    assert!(close(contact.normal, Vf2d::new(1.0, 1.0).normalize()));
+    assert!((contact.depth - (2.0 - 2.0f32.sqrt())).abs() < 1e-5);
+    assert!(collision::aabb_vs_circle(&rect, &Circle::new(Vf2d::new(5.5, 5.5), 2.0)).is_none());
+
+    // Swapping the shapes flips the normal
+    let circle = Shape::Circle(Circle::new(Vf2d::new(5.0, 2.0), 2.0));
+    let flipped = collision::collide(&circle, &Shape::Aabb(rect)).unwrap();
+    assert_eq!((flipped.normal, flipped.depth), (Vf2d::new(-1.0, 0.0), 1.0));
+}
+
+#[test]
+fn polygon_and_circle_contacts() {
+    for polygon in [square(0.0, 0.0, 4.0), reversed(square(0.0, 0.0, 4.0))].iter() {
+        let contact = collision::polygon_vs_circle(polygon, &Circle::new(Vf2d::new(5.0, 2.0), 2.0)).unwrap();
+        assert!(close(contact.normal, Vf2d::new(1.0, 0.0)) && (contact.depth - 1.0).abs() < 1e-5);
+
+        let contact = collision::polygon_vs_circle(polygon, &Circle::new(Vf2d::new(5.0, 5.0), 2.0)).unwrap();
+        assert!(close(contact.normal, Vf2d::new(1.0, 1.0).normalize()));
+        assert!(close(contact.points[0], Vf2d::new(4.0, 4.0)));
+
+        let contact = collision::polygon_vs_circle(polygon, &Circle::new(Vf2d::new(2.0, 0.5), 1.0)).unwrap();
+        assert!(close(contact.normal, Vf2d::new(0.0, -1.0)) && (contact.depth - 1.5).abs() < 1e-5);
+
+        assert!(collision::polygon_vs_circle(polygon, &Circle::new(Vf2d::new(5.5, 5.5), 2.0)).is_none());
+        assert!(collision::polygon_vs_circle(polygon, &Circle::new(Vf2d::new(7.0, 2.0), 2.0)).is_none());
+    }
+    // Degenerate polygons never collide
+    assert!(collision::polygon_vs_circle(&[Vf2d::new(0.0, 0.0)], &Circle::new(Vf2d::new(0.0, 0.0), 1.0)).is_none());
+}
+
+#[test]
+fn polygons_clip_contact_points_in_either_winding() {
+    let a = square(0.0, 0.0, 4.0);
+    let b = vec![Vf2d::new(3.0, 1.0), Vf2d::new(7.0, 1.0), Vf2d::new(7.0, 3.0), Vf2d::new(3.0, 3.0)];
+    for (a, b) in [(a.clone(), b.clone()), (reversed(a.clone()), reversed(b.clone())), (a.clone(), reversed(b))].iter() {
+        let contact = collision::polygon_vs_polygon(a, b).unwrap();
+        assert!(close(contact.normal, Vf2d::new(1.0, 0.0)), "{:?}", contact);
+        assert!((contact.depth - 1.0).abs() < 1e-5);
+        assert!(same_points(contact.points, vec![Vf2d::new(3.0, 1.0), Vf2d::new(3.0, 3.0)]));
+    }
+
+    // A triangle poking into the top of the square touches at its tip
+    let triangle = vec![Vf2d::new(1.0, -1.0), Vf2d::new(3.0, -1.0), Vf2d::new(2.0, 1.0)];
+    let contact = collision::polygon_vs_polygon(&a, &triangle).unwrap();
+    assert!(close(contact.normal, Vf2d::new(0.0, -1.0)));
+    assert!((contact.depth - 1.0).abs() < 1e-5);
+    assert!(same_points(contact.points, vec![Vf2d::new(2.0, 1.0)]));
+    let flipped = collision::polygon_vs_polygon(&triangle, &a).unwrap();
+    assert!(close(flipped.normal, Vf2d::new(0.0, 1.0)));
+
+    assert!(collision::polygon_vs_polygon(&a, &square(4.0, 0.0, 2.0)).is_none());
+    assert!(collision::polygon_vs_polygon(&a, &square(10.0, 10.0, 2.0)).is_none());
+}
+
+#[test]
+fn moving_by_the_penetration_separates_every_shape_pair() {
+    let shapes = [
+        Shape::Aabb(Rectf::new(0.0, 0.0, 4.0, 4.0)),
+        Shape::Circle(Circle::new(Vf2d::new(2.0, 2.0), 2.0)),
+        Shape::Polygon(vec![Vf2d::new(0.0, 0.0), Vf2d::new(4.0, 1.0), Vf2d::new(3.0, 4.0), Vf2d::new(-1.0, 3.0)]),
+    ];
+    let offsets = [Vf2d::new(3.0, 0.5), Vf2d::new(-1.0, 2.5), Vf2d::new(0.5, -3.0), Vf2d::new(2.5, 2.5)];
+    for a in shapes.iter() {
+        for b in shapes.iter() {
+            for offset in offsets.iter() {
+                let b = b.translate(*offset);
+                let contact = match collision::collide(a, &b) {
+                    Some(contact) => contact,
+                    None => panic!("{:?} {:?} should overlap", a, b),
+                };
+                assert!((contact.normal.length() - 1.0).abs() < 1e-4, "{:?}", contact);
+                assert!(contact.depth > 0.0 && !contact.points.is_empty(), "{:?}", contact);
+                assert!(a.overlaps(&b));
+
+                // The normal points from a toward b
+                let toward = b.bounds().center() - a.bounds().center();
+                assert!(contact.normal.dot(toward) >= 0.0, "{:?} {:?} {:?}", a, b, contact);
+
+                let pushed = b.translate(contact.normal * (contact.depth + 1e-3));
+                assert!(collision::collide(a, &pushed).is_none(), "{:?} {:?} {:?}", a, b, contact);
+            }
+        }
+    }
+}
+
+#[test]
+fn shapes_know_their_bounds_and_points() {
+    let circle = Shape::Circle(Circle::new(Vf2d::new(2.0, 3.0), 1.5));
+    assert_eq!(circle.bounds(), Rectf::new(0.5, 1.5, 3.0, 3.0));
+    let triangle = Shape::Polygon(vec![Vf2d::new(1.0, 0.0), Vf2d::new(4.0, 2.0), Vf2d::new(0.0, 3.0)]);
+    assert_eq!(triangle.bounds(), Rectf::new(0.0, 0.0, 4.0, 3.0));
+    assert_eq!(
+        triangle.translate(Vf2d::new(1.0, 1.0)),
+        Shape::Polygon(vec![Vf2d::new(2.0, 1.0), Vf2d::new(5.0, 3.0), Vf2d::new(1.0, 4.0)])
+    );
+    let rect = Shape::Aabb(Rectf::new(0.0, 0.0, 2.0, 2.0));
+    assert_eq!(rect.translate(Vf2d::new(-1.0, 3.0)).bounds(), Rectf::new(-1.0, 3.0, 2.0, 2.0));
+
+    assert!(circle.contains(Vf2d::new(2.0, 4.5)));
+    assert!(!circle.contains(Vf2d::new(3.1, 4.1)));
+    assert!(rect.contains(Vf2d::new(1.0, 1.0)));
+    assert!(!rect.contains(Vf2d::new(2.0, 1.0)));
+    for polygon in [square(0.0, 0.0, 4.0), reversed(square(0.0, 0.0, 4.0))].iter() {
+        let shape = Shape::Polygon(polygon.clone());
+        assert!(shape.contains(Vf2d::new(1.0, 3.0)));
+        assert!(!shape.contains(Vf2d::new(-0.5, 3.0)));
+    }
+    assert!(!Shape::Polygon(Vec::new()).contains(Vf2d::new(0.0, 0.0)));
+}
+
+#[test]
+fn rays_hit_the_facing_side() {
+    let rect = Rectf::new(4.0, 0.0, 2.0, 4.0);
+    let hit = collision::ray_vs_aabb(Vf2d::new(0.0, 1.0), Vf2d::new(8.0, 0.0), &rect).unwrap();
+    assert_eq!(hit, Hit { time: 0.5, point: Vf2d::new(4.0, 1.0), normal: Vf2d::new(-1.0, 0.0) });
+    let hit = collision::ray_vs_aabb(Vf2d::new(5.0, 8.0), Vf2d::new(0.0, -8.0), &rect).unwrap();
+    assert_eq!(hit, Hit { time: 0.5, point: Vf2d::new(5.0, 4.0), normal: Vf2d::new(0.0, 1.0) });
+    // Too short, starting inside, grazing an edge and pointing away all miss
+    assert!(collision::ray_vs_aabb(Vf2d::new(0.0, 1.0), Vf2d::new(3.0, 0.0), &rect).is_none());
+    assert!(collision::ray_vs_aabb(Vf2d::new(5.0, 1.0), Vf2d::new(8.0, 0.0), &rect).is_none());
+    assert!(collision::ray_vs_aabb(Vf2d::new(0.0, 0.0), Vf2d::new(8.0, 0.0), &rect).is_none());
+    assert!(collision::ray_vs_aabb(Vf2d::new(0.0, 1.0), Vf2d::new(-8.0, 0.0), &rect).is_none());
+
+    let circle = Circle::new(Vf2d::new(5.0, 0.0), 1.0);
+    let hit = collision::ray_vs_circle(Vf2d::new(0.0, 0.0), Vf2d::new(8.0, 0.0), &circle).unwrap();
+    assert_eq!(hit, Hit { time: 0.5, point: Vf2d::new(4.0, 0.0), normal: Vf2d::new(-1.0, 0.0) });
+    assert!(collision::ray_vs_circle(Vf2d::new(0.0, 2.0), Vf2d::new(8.0, 0.0), &circle).is_none());
+    assert!(collision::ray_vs_circle(Vf2d::new(5.0, 0.5), Vf2d::new(8.0, 0.0), &circle).is_none());
+    assert!(collision::ray_vs_circle(Vf2d::new(0.0, 0.0), Vf2d::new(0.0, 0.0), &circle).is_none());
+
+    for polygon in [square(4.0, -2.0, 4.0), reversed(square(4.0, -2.0, 4.0))].iter() {
+        let hit = collision::ray_vs_polygon(Vf2d::new(0.0, 1.0), Vf2d::new(8.0, 0.0), polygon).unwrap();
+        assert!((hit.time - 0.5).abs() < 1e-5 && close(hit.point, Vf2d::new(4.0, 1.0)));
+        assert!(close(hit.normal, Vf2d::new(-1.0, 0.0)));
+        let hit = collision::ray_vs_polygon(Vf2d::new(6.0, 6.0), Vf2d::new(0.0, -8.0), polygon).unwrap();
+        assert!(close(hit.normal, Vf2d::new(0.0, 1.0)) && close(hit.point, Vf2d::new(6.0, 2.0)));
+        assert!(collision::ray_vs_polygon(Vf2d::new(6.0, 0.0), Vf2d::new(8.0, 0.0), polygon).is_none());
+        assert!(collision::ray_vs_polygon(Vf2d::new(0.0, 3.0), Vf2d::new(8.0, 0.0), polygon).is_none());
+    }
+
+    let shape = Shape::Circle(circle);
+    assert_eq!(
+        collision::raycast(Vf2d::new(0.0, 0.0), Vf2d::new(8.0, 0.0), &shape),
+        collision::ray_vs_circle(Vf2d::new(0.0, 0.0), Vf2d::new(8.0, 0.0), &circle)
+    );
+}
+
+#[test]
+fn swept_boxes_stop_at_first_contact() {
+    let moving = Rectf::new(0.0, 0.0, 2.0, 2.0);
+    let target = Rectf::new(5.0, 0.0, 2.0, 2.0);
+    let hit = collision::sweep_aabb(&moving, Vf2d::new(10.0, 0.0), &target).unwrap();
+    assert!((hit.time - 0.3).abs() < 1e-5);
+    assert_eq!(hit.normal, Vf2d::new(-1.0, 0.0));
+    // The point is the moving box's center when it touches
+    assert!(close(hit.point, Vf2d::new(4.0, 1.0)));
+
+    assert!(collision::sweep_aabb(&moving, Vf2d::new(2.0, 0.0), &target).is_none());
+    assert!(collision::sweep_aabb(&moving, Vf2d::new(10.0, 0.0), &Rectf::new(5.0, 2.0, 2.0, 2.0)).is_none());
+    assert!(collision::sweep_aabb(&moving, Vf2d::new(-10.0, 0.0), &target).is_none());
+
+    let overlapping = collision::sweep_aabb(&moving, Vf2d::new(10.0, 0.0), &Rectf::new(1.0, 0.5, 2.0, 2.0)).unwrap();
+    assert_eq!((overlapping.time, overlapping.normal), (0.0, Vf2d::new(-1.0, 0.0)));
+}
+
+#[test]
+fn grid_rays_walk_cells_in_order() {
+    let walls = [Vi2d::new(2, 5), Vi2d::new(7, 7), Vi2d::new(9, 5)];
+    let solid = |x: i32, y: i32| walls.contains(&Vi2d::new(x, y));
+    let size = Vf2d::new(1.0, 1.0);
+
+    let (cell, hit) = collision::raycast_grid(Vf2d::new(5.5, 5.5), Vf2d::new(-4.0, 0.0), size, solid).unwrap();
+    assert_eq!(cell, Vi2d::new(2, 5));
+    assert!((hit.time - 0.625).abs() < 1e-5 && close(hit.point, Vf2d::new(3.0, 5.5)));
+    assert_eq!(hit.normal, Vf2d::new(1.0, 0.0));
+
+    let (cell, hit) = collision::raycast_grid(Vf2d::new(5.5, 5.5), Vf2d::new(4.0, 0.0), size, solid).unwrap();
+    assert_eq!(cell, Vi2d::new(9, 5));
+    assert!(close(hit.point, Vf2d::new(9.0, 5.5)));
+
+    let (cell, hit) = collision::raycast_grid(Vf2d::new(5.5, 5.5), Vf2d::new(3.0, 3.0), size, solid).unwrap();
+    assert_eq!(cell, Vi2d::new(7, 7));
+    assert!(close(hit.point, Vf2d::new(7.0, 7.0)));
+
+    // Wider cells, the start cell is skipped and short rays stop before the wall
+    let wide = Vf2d::new(4.0, 2.0);
+    let (cell, _) = collision::raycast_grid(Vf2d::new(1.0, 11.0), Vf2d::new(0.0, -2.0), wide, |x, y| (x, y) == (0, 4)).unwrap();
+    assert_eq!(cell, Vi2d::new(0, 4));
+    assert!(collision::raycast_grid(Vf2d::new(9.5, 5.5), Vf2d::new(4.0, 0.0), size, solid).is_none());
+    assert!(collision::raycast_grid(Vf2d::new(5.5, 5.5), Vf2d::new(-2.0, 0.0), size, solid).is_none());
+    assert!(collision::raycast_grid(Vf2d::new(5.5, 5.5), Vf2d::new(0.0, 0.0), size, solid).is_none());
+}
@@ -0,0 +1,144 @@
+use std::f32::consts::PI;
+
+use yape::{Pixel, PixelMode, Recti, Vf2d, Vi2d, YapeEngine, YapeEngineApi};
+
+const DIM: Pixel = Pixel { red: 10, green: 10, blue: 10, alpha: 255 };
+
+// Draws additively on a black target, so pixels drawn twice are brighter
+fn draw(f: impl Fn(&mut YapeEngine)) -> YapeEngine {
+    let mut engine = YapeEngine::construct_headless(32, 32);
+    engine.clear(&yape::BLACK);
+    engine.set_pixel_mode(PixelMode::Additive);
+    f(&mut engine);
+    engine
+}
+
+fn assert_no_overdraw(engine: &YapeEngine) {
+    assert!(engine.get_draw_target().data.iter().all(|p| p.red == 0 || p.red == 10), "overdraw");
+}
+
+fn lit(engine: &YapeEngine, x: i32, y: i32) -> bool {
+    engine.get_draw_target().get_pixel(x as u32, y as u32).red != 0
+}
+
+fn lit_pixels(engine: &YapeEngine) -> Vec<Vi2d> {
+    let target = engine.get_draw_target();
+    (0..target.height as i32)
+        .flat_map(|y| (0..target.width as i32).map(move |x| Vi2d::new(x, y)))
+        .filter(|p| lit(engine, p.x, p.y))
+        .collect()
+}
+
+fn bounds(engine: &YapeEngine) -> Recti {
+    lit_pixels(engine).iter().fold(Recti::default(), |b, p| b.union(&Recti::new(p.x, p.y, 1, 1)))
+}
+
+#[test]
+fn cubic_bezier_starts_and_ends_on_its_end_points() {
+    let (p0, p1, p2, p3) = (Vf2d::new(2.0, 3.0), Vf2d::new(8.0, 28.0), Vf2d::new(20.0, -6.0), Vf2d::new(27.0, 18.0));
+    let e = draw(|e| e.draw_cubic_bezier(p0, p1, p2, p3, &DIM));
+    assert_no_overdraw(&e);
+    assert!(lit(&e, 2, 3));
+    assert!(lit(&e, 27, 18));
+
+    // The point at t = 0.5 is on the curve, within the flattening tolerance
+    let mid = (p0 + p1 * 3.0 + p2 * 3.0 + p3) / 8.0;
+    let near = lit_pixels(&e).iter().any(|p| p.to_f32().distance(mid) <= 1.0);
+    assert!(near, "nothing drawn near {:?}", mid);
+
+    // Every pixel but the two ends touches another one, so there are no gaps
+    for p in lit_pixels(&e) {
+        let neighbours = (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+            .filter(|&(dx, dy)| (dx, dy) != (0, 0) && lit(&e, p.x + dx, p.y + dy))
+            .count();
+        assert!(neighbours >= 1, "{:?} is isolated", p);
+    }
+}
+
+#[test]
+fn straight_bezier_matches_the_line() {
+    let curve = draw(|e| {
+        e.draw_cubic_bezier(Vf2d::new(1.0, 1.0), Vf2d::new(5.0, 3.0), Vf2d::new(13.0, 7.0), Vf2d::new(21.0, 11.0), &DIM)
+    });
+    let line = draw(|e| e.draw_line_v(Vi2d::new(1, 1), Vi2d::new(21, 11), &DIM));
+    assert_eq!(lit_pixels(&curve), lit_pixels(&line));
+}
+
+#[test]
+fn quadratic_bezier_ends_and_bulges_toward_its_control_point() {
+    let e = draw(|e| e.draw_quadratic_bezier(Vf2d::new(2.0, 20.0), Vf2d::new(16.0, 0.0), Vf2d::new(30.0, 20.0), &DIM));
+    assert_no_overdraw(&e);
+    assert!(lit(&e, 2, 20));
+    assert!(lit(&e, 30, 20));
+    // Apex at t = 0.5 is halfway between the chord and the control point
+    assert!(lit(&e, 16, 10));
+    assert_eq!(bounds(&e).top(), 10);
+}
+
+#[test]
+fn degenerate_bezier_is_a_single_pixel() {
+    let p = Vf2d::new(5.0, 5.0);
+    let e = draw(|e| e.draw_cubic_bezier(p, p, p, p, &DIM));
+    assert_eq!(lit_pixels(&e), vec![Vi2d::new(5, 5)]);
+}
+
+#[test]
+fn arcs_run_clockwise_from_start_to_end() {
+    let c = Vi2d::new(16, 16);
+    let e = draw(|e| e.draw_arc(c, 8, 0.0, PI / 2.0, &DIM));
+    assert!(lit(&e, 24, 16));
+    assert!(lit(&e, 16, 24));
+    assert!(!lit(&e, 8, 16));
+    assert!(!lit(&e, 16, 8));
+    assert!(lit_pixels(&e).iter().all(|p| p.x >= 16 && p.y >= 16));
+
+    // Sweeps that wrap past a full turn
+    let e = draw(|e| e.draw_arc(c, 8, 1.5 * PI, 2.5 * PI, &DIM));
+    assert!(lit(&e, 16, 8) && lit(&e, 24, 16) && lit(&e, 16, 24));
+    assert!(!lit(&e, 8, 16));
+
+    let full = draw(|e| e.draw_arc(c, 8, 0.0, 2.0 * PI, &DIM));
+    let circle = draw(|e| e.draw_circle_v(c, 8, &DIM));
+    assert_eq!(lit_pixels(&full), lit_pixels(&circle));
+}
+
+#[test]
+fn pies_are_closed_and_filled_pies_stay_in_their_sector() {
+    let c = Vi2d::new(16, 16);
+    let outline = draw(|e| e.draw_pie(c, 8, 0.0, PI / 2.0, &DIM));
+    assert!(lit(&outline, 16, 16));
+    assert!(lit(&outline, 20, 16));
+    assert!(lit(&outline, 16, 20));
+    assert!(!lit(&outline, 19, 19));
+
+    // Top half, from the left through up to the right
+    let filled = draw(|e| e.fill_pie(c, 8, PI, 2.0 * PI, &DIM));
+    assert_no_overdraw(&filled);
+    assert!(lit(&filled, 16, 10));
+    assert!(lit(&filled, 10, 16) && lit(&filled, 22, 16));
+    assert!(!lit(&filled, 16, 18));
+    let disc = draw(|e| e.fill_circle_v(c, 8, &DIM));
+    let in_disc = lit_pixels(&disc);
+    assert!(lit_pixels(&filled).iter().all(|p| p.y <= 16 && in_disc.contains(p)));
+}
+
+#[test]
+fn rounded_rects_have_the_extents_of_plain_rects() {
+    let (pos, size) = (Vi2d::new(2, 3), Vi2d::new(12, 7));
+    let outline = draw(|e| e.draw_rounded_rect(pos, size, 3, &DIM));
+    let plain = draw(|e| e.fill_rect_v(pos, size + Vi2d::new(1, 1), &DIM));
+    assert_eq!(bounds(&outline), bounds(&plain));
+    assert!(!lit(&outline, 2, 3));
+    assert!(lit(&outline, 8, 3));
+
+    let filled = draw(|e| e.fill_rounded_rect(pos, size, 3, &DIM));
+    assert_no_overdraw(&filled);
+    let rect = draw(|e| e.fill_rect_v(pos, size, &DIM));
+    assert_eq!(bounds(&filled), bounds(&rect));
+    assert!(!lit(&filled, 2, 3));
+    assert!(lit(&filled, 8, 6));
+
+    let square = draw(|e| e.fill_rounded_rect(pos, size, 0, &DIM));
+    assert_eq!(lit_pixels(&square), lit_pixels(&rect));
+}
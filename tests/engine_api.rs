@@ -0,0 +1,117 @@
+use std::path::Path;
+use yape::camera::Camera2D;
+use yape::{ButtonState, KeyboardKey, MouseButton, Pixel, PixelMode, Sprite, Vf2d, Vi2d, YapeEngineApi};
+
+const RELEASED: ButtonState = ButtonState { pressed: false, released: false, held: false };
+
+// An engine implementing only the required methods, everything else must come from default
+// bodies
+struct Canvas {
+    mode: PixelMode,
+    target: Sprite,
+}
+
+impl YapeEngineApi for Canvas {
+    fn is_window_focused(&self) -> bool {
+        false
+    }
+    fn get_key_state(&self, _key: &KeyboardKey) -> &ButtonState {
+        &RELEASED
+    }
+    fn get_mouse_button_state(&self, _button: &MouseButton) -> &ButtonState {
+        &RELEASED
+    }
+    fn get_mouse_x(&self) -> u32 {
+        0
+    }
+    fn get_mouse_y(&self) -> u32 {
+        0
+    }
+    fn get_mouse_wheel(&self) -> i32 {
+        0
+    }
+    fn get_screen_width(&self) -> u32 {
+        self.target.width
+    }
+    fn get_screen_height(&self) -> u32 {
+        self.target.height
+    }
+    fn get_fps(&self) -> u32 {
+        0
+    }
+    fn set_pixel_mode(&mut self, mode: PixelMode) {
+        self.mode = mode;
+    }
+    fn get_pixel_mode(&mut self) -> &PixelMode {
+        &self.mode
+    }
+    fn set_pixel_blend(&mut self, _blend: f32) {}
+    fn get_pixel_blend(&self) -> f32 {
+        1.0
+    }
+    fn draw_pixel(&mut self, x: u32, y: u32, pixel: &Pixel) {
+        if x < self.target.width && y < self.target.height {
+            self.target.set_pixel(x, y, pixel);
+        }
+    }
+    fn draw_line(&mut self, _x1: u32, _y1: u32, _x2: u32, _y2: u32, _pixel: &Pixel) {}
+    fn draw_circle(&mut self, _x: u32, _y: u32, _radius: u32, _pixel: &Pixel) {}
+    fn draw_rect(&mut self, _x: u32, _y: u32, _w: u32, _h: u32, _pixel: &Pixel) {}
+    fn draw_triangle(&mut self, _x1: u32, _y1: u32, _x2: u32, _y2: u32, _x3: u32, _y3: u32, _pixel: &Pixel) {}
+    fn fill_circle(&mut self, _x: u32, _y: u32, _radius: u32, _pixel: &Pixel) {}
+    fn fill_rect(&mut self, _x: u32, _y: u32, _w: u32, _h: u32, _pixel: &Pixel) {}
+    fn fill_triangle(&mut self, _x1: u32, _y1: u32, _x2: u32, _y2: u32, _x3: u32, _y3: u32, _pixel: &Pixel) {}
+    fn clear(&mut self, pixel: &Pixel) {
+        self.target.clear(pixel);
+    }
+}
+
+fn canvas(width: u32, height: u32) -> Canvas {
+    Canvas { mode: PixelMode::Normal, target: Sprite::new(width, height) }
+}
+
+#[test]
+fn default_fill_span_is_clipped_to_the_screen() {
+    let mut c = canvas(4, 2);
+    c.fill_span(3, -5, 1, &yape::RED);
+    c.fill_span(0, 9, 5, &yape::RED);
+    let row: Vec<Pixel> = (0..4).map(|x| *c.target.get_pixel(x, 1)).collect();
+    assert_eq!(row, vec![yape::RED; 4]);
+    assert!((0..4).all(|x| *c.target.get_pixel(x, 0) != yape::RED));
+}
+
+#[test]
+fn default_sprite_drawing_goes_through_draw_pixel() {
+    let mut sprite = Sprite::new(2, 1);
+    sprite.set_pixel(1, 0, &yape::GREEN);
+    let mut c = canvas(6, 3);
+    c.draw_sprite(Vi2d::new(1, 0), &sprite, 2);
+    assert_eq!(*c.target.get_pixel(3, 0), yape::GREEN);
+    assert_eq!(*c.target.get_pixel(4, 1), yape::GREEN);
+    assert_ne!(*c.target.get_pixel(5, 0), yape::GREEN);
+}
+
+#[test]
+fn defaults_without_engine_state() {
+    let mut c = canvas(2, 2);
+    assert!(c.save_screenshot(Path::new("unused.png")).is_err());
+    assert!(c.record_frames(Path::new("unused"), 1, yape::capture::RecordFormat::PngSequence).is_err());
+    assert!(c.start_gif_recording(1.0).is_err());
+    assert!(!c.is_gif_recording());
+    assert!(c.save_gif(Path::new("unused.gif")).is_err());
+}
+
+#[test]
+fn default_mask_and_world_drawing_go_through_draw_pixel() {
+    let mut c = canvas(3, 2);
+    c.draw_pixel(1, 0, &yape::RED);
+    let mask = c.target.select_region(0, 1, 0);
+    assert_eq!(mask.count(), 5);
+    c.fill_mask(&mask, &yape::BLUE);
+    assert_eq!(*c.target.get_pixel(1, 0), yape::RED);
+    assert_eq!(*c.target.get_pixel(2, 1), yape::BLUE);
+
+    let camera = Camera2D { position: Vf2d::new(1.0, 0.0), ..Camera2D::default() };
+    c.fill_rect_w(&camera, &yape::Rectf::new(1.0, 0.0, 1.0, 1.0), &yape::GREEN);
+    assert_eq!(*c.target.get_pixel(0, 0), yape::GREEN);
+}
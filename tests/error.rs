@@ -0,0 +1,51 @@
+use std::error::Error;
+use std::io;
+
+use yape::{Pixel, YapeError, YapeErrorKind, YapeResult};
+
+#[test]
+fn display_shows_kind_and_message() {
+    let error = YapeError::new(YapeErrorKind::Decode, "bad header");
+    assert_eq!(error.to_string(), "YapeError[kind=Decode, message=bad header]");
+    assert_eq!(YapeError::user("game over").to_string(), "YapeError[kind=User, message=game over]");
+}
+
+#[test]
+fn source_chains_to_the_wrapped_error() {
+    let plain = YapeError::new(YapeErrorKind::Encode, "no frames");
+    assert!(plain.source().is_none());
+
+    let inner = YapeError::new(YapeErrorKind::Io, "disk full");
+    let outer = YapeError::with_source(YapeErrorKind::Encode, "saving failed", inner);
+    let source = outer.source().unwrap();
+    assert_eq!(source.to_string(), "YapeError[kind=Io, message=disk full]");
+    let inner = source.downcast_ref::<YapeError>().unwrap();
+    assert_eq!(inner.kind, YapeErrorKind::Io);
+    assert!(inner.source().is_none());
+}
+
+#[test]
+fn io_errors_map_to_io_kind_and_keep_the_original() {
+    let error: YapeError = io::Error::new(io::ErrorKind::NotFound, "missing.png").into();
+    assert_eq!(error.kind, YapeErrorKind::Io);
+    assert_eq!(error.message, "missing.png");
+    let source = error.source().unwrap().downcast_ref::<io::Error>().unwrap();
+    assert_eq!(source.kind(), io::ErrorKind::NotFound);
+}
+
+#[test]
+fn question_mark_converts_io_errors() {
+    fn open() -> YapeResult<()> {
+        std::fs::File::open("does/not/exist")?;
+        Ok(())
+    }
+    assert_eq!(open().unwrap_err().kind, YapeErrorKind::Io);
+}
+
+#[test]
+fn errors_are_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync + 'static>(_: &T) {}
+    assert_send_sync(&YapeError::new(YapeErrorKind::Audio, "no device"));
+    let boxed: Box<dyn Error + Send + Sync> = Box::new(Pixel::from_hex("#zz").unwrap_err());
+    assert_eq!(boxed.downcast_ref::<YapeError>().unwrap().kind, YapeErrorKind::Decode);
+}
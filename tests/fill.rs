@@ -0,0 +1,158 @@
+use yape::{Pixel, PixelMode, Vi2d, YapeEngine, YapeEngineApi};
+
+// Draws with additive blending, so any pixel covered twice ends up brighter than 10
+fn covered(draw: impl Fn(&mut YapeEngine)) -> Vec<Vec<bool>> {
+    let mut engine = YapeEngine::construct_headless(16, 16);
+    engine.clear(&yape::BLACK);
+    engine.set_pixel_mode(PixelMode::Additive);
+    draw(&mut engine);
+    let target = engine.get_draw_target();
+    (0..16)
+        .map(|y| {
+            (0..16)
+                .map(|x| {
+                    let p = target.get_pixel(x, y);
+                    assert!(p.red == 0 || p.red == 10, "({}, {}) drawn more than once", x, y);
+                    p.red == 10
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn row_extent(row: &[bool]) -> Option<(usize, usize)> {
+    let first = row.iter().position(|c| *c)?;
+    let last = row.iter().rposition(|c| *c)?;
+    assert!(row[first..=last].iter().all(|c| *c), "row has a gap");
+    Some((first, last))
+}
+
+const DIM: Pixel = Pixel { red: 10, green: 10, blue: 10, alpha: 255 };
+
+#[test]
+fn spans_match_per_pixel_drawing_in_every_mode() {
+    let color = Pixel::rgba(200, 40, 90, 128);
+    for mode in [PixelMode::Normal, PixelMode::Mask, PixelMode::Alpha, PixelMode::Additive].iter() {
+        let mut spans = YapeEngine::construct_headless(8, 2);
+        let mut pixels = YapeEngine::construct_headless(8, 2);
+        for e in [&mut spans, &mut pixels].iter_mut() {
+            e.clear(&Pixel::rgb(20, 100, 60));
+            e.set_pixel_mode(*mode);
+            e.set_pixel_blend(0.5);
+        }
+        spans.fill_span(6, 1, 1, &color);
+        for x in 1..=6 {
+            pixels.draw_pixel(x, 1, &color);
+        }
+        assert_eq!(spans.get_draw_target().data, pixels.get_draw_target().data, "{:?}", mode);
+    }
+}
+
+#[test]
+fn spans_are_clipped_to_the_target() {
+    let rows = covered(|e| {
+        e.fill_span(-20, 40, 3, &DIM);
+        e.fill_span(10, 30, -1, &DIM);
+        e.fill_span(-5, -1, 4, &DIM);
+        e.fill_span(16, 20, 5, &DIM);
+    });
+    assert_eq!(row_extent(&rows[3]), Some((0, 15)));
+    assert_eq!(rows.iter().flatten().filter(|c| **c).count(), 16);
+}
+
+#[test]
+fn rect_spans_cover_the_rect_once() {
+    let rows = covered(|e| e.fill_rect(2, 3, 4, 2, &DIM));
+    assert_eq!(row_extent(&rows[2]), None);
+    assert_eq!(row_extent(&rows[3]), Some((2, 5)));
+    assert_eq!(row_extent(&rows[4]), Some((2, 5)));
+    assert_eq!(row_extent(&rows[5]), None);
+
+    // Partly off screen and empty rects
+    let rows = covered(|e| {
+        e.fill_rect_v(Vi2d::new(-2, 14), Vi2d::new(4, 4), &DIM);
+        e.fill_rect(5, 5, 0, 3, &DIM);
+        e.fill_rect(5, 5, 3, 0, &DIM);
+    });
+    assert_eq!(row_extent(&rows[14]), Some((0, 1)));
+    assert_eq!(row_extent(&rows[15]), Some((0, 1)));
+    assert_eq!(rows.iter().flatten().filter(|c| **c).count(), 4);
+}
+
+#[test]
+fn clear_fills_the_whole_target_in_any_mode() {
+    let mut engine = YapeEngine::construct_headless(5, 3);
+    engine.set_pixel_mode(PixelMode::Additive);
+    engine.clear(&yape::RED);
+    engine.clear(&yape::BLUE);
+    assert!(engine.get_draw_target().data.iter().all(|p| *p == yape::BLUE));
+}
+
+#[test]
+fn circle_spans_cover_each_row_once() {
+    let rows = covered(|e| e.fill_circle_v(Vi2d::new(5, 5), 2, &DIM));
+    let extents: Vec<Option<(usize, usize)>> = rows.iter().map(|r| row_extent(r)).collect();
+    assert_eq!(extents[2], None);
+    assert_eq!(extents[3], Some((4, 6)));
+    assert_eq!(extents[4], Some((3, 7)));
+    assert_eq!(extents[5], Some((3, 7)));
+    assert_eq!(extents[6], Some((3, 7)));
+    assert_eq!(extents[7], Some((4, 6)));
+    assert_eq!(extents[8], None);
+
+    // Radius zero is a single pixel
+    let rows = covered(|e| e.fill_circle_v(Vi2d::new(1, 1), 0, &DIM));
+    assert_eq!(rows.iter().flatten().filter(|c| **c).count(), 1);
+}
+
+#[test]
+fn triangle_spans_cover_each_row_once() {
+    let rows = covered(|e| e.fill_triangle_v(Vi2d::new(0, 0), Vi2d::new(4, 0), Vi2d::new(0, 4), &DIM));
+    for (y, row) in rows.iter().enumerate().take(5) {
+        assert_eq!(row_extent(row), Some((0, 4 - y)));
+    }
+    assert_eq!(row_extent(&rows[5]), None);
+
+    // Vertex order doesn't matter
+    let rows = covered(|e| e.fill_triangle_v(Vi2d::new(8, 9), Vi2d::new(2, 3), Vi2d::new(2, 9), &DIM));
+    assert_eq!(row_extent(&rows[3]), Some((2, 2)));
+    assert_eq!(row_extent(&rows[9]), Some((2, 8)));
+
+    // Flat triangles are a single span
+    let rows = covered(|e| e.fill_triangle_v(Vi2d::new(7, 2), Vi2d::new(1, 2), Vi2d::new(4, 2), &DIM));
+    assert_eq!(row_extent(&rows[2]), Some((1, 7)));
+    assert_eq!(rows.iter().flatten().filter(|c| **c).count(), 7);
+}
+
+#[test]
+fn huge_circles_only_walk_visible_rows() {
+    // Radii whose square overflows an i32
+    let rows = covered(|e| e.fill_circle(8, 8, 100_000, &DIM));
+    assert!(rows.iter().flatten().all(|c| *c));
+    let rows = covered(|e| e.fill_circle_v(Vi2d::new(8, -1_000_000), i32::MAX, &DIM));
+    assert!(rows.iter().flatten().all(|c| *c));
+
+    // Far above the screen, the bottom row just reaches it
+    let rows = covered(|e| e.fill_circle_v(Vi2d::new(5, -60_000), 60_000, &DIM));
+    assert_eq!(row_extent(&rows[0]), Some((0, 15)));
+    assert_eq!(row_extent(&rows[1]), None);
+}
+
+#[test]
+fn huge_rects_and_triangles_only_walk_visible_rows() {
+    // Sizes past i32::MAX saturate instead of turning negative
+    let rows = covered(|e| e.fill_rect(2, 3, u32::MAX, u32::MAX, &DIM));
+    assert_eq!(row_extent(&rows[2]), None);
+    assert!(rows[3..].iter().all(|row| row_extent(row) == Some((2, 15))));
+    let rows = covered(|e| e.fill_rect_v(Vi2d::new(0, i32::MIN), Vi2d::new(4, i32::MAX), &DIM));
+    assert!(rows.iter().flatten().all(|c| !*c));
+    let rows = covered(|e| e.fill_rect_v(Vi2d::new(i32::MAX, 0), Vi2d::new(i32::MAX, 2), &DIM));
+    assert!(rows.iter().flatten().all(|c| !*c));
+
+    let rows = covered(|e| {
+        e.fill_triangle_v(Vi2d::new(0, -2_000_000_000), Vi2d::new(0, 2_000_000_000), Vi2d::new(i32::MAX, 0), &DIM)
+    });
+    assert!(rows.iter().flatten().all(|c| *c));
+    let rows = covered(|e| e.fill_triangle(0, 0, u32::MAX, 0, 0, u32::MAX, &DIM));
+    assert!(rows.iter().flatten().all(|c| *c));
+}
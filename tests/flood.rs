@@ -0,0 +1,81 @@
+use yape::flood::Mask;
+use yape::{Pixel, Recti, YapeEngine, YapeEngineApi};
+
+// Black target with a diagonal white wall from (0, 7) to (7, 0)
+fn walled() -> YapeEngine {
+    let mut engine = YapeEngine::construct_headless(8, 8);
+    engine.clear(&yape::BLACK);
+    for i in 0..8 {
+        engine.draw_pixel(i, 7 - i, &yape::WHITE);
+    }
+    engine
+}
+
+#[test]
+fn fill_counts_every_connected_pixel() {
+    let mut engine = YapeEngine::construct_headless(10, 6);
+    engine.clear(&yape::BLACK);
+    engine.fill_rect(4, 0, 1, 6, &yape::WHITE);
+
+    let left = engine.flood_fill(0, 0, &yape::RED, 0);
+    assert_eq!(left.count(), 4 * 6);
+    assert_eq!(left.bounds(), Some(Recti::new(0, 0, 4, 6)));
+    let target = engine.get_draw_target();
+    assert_eq!(target.data.iter().filter(|p| **p == yape::RED).count(), 24);
+    assert_eq!(*target.get_pixel(5, 0), yape::BLACK);
+    assert_eq!(*target.get_pixel(4, 3), yape::WHITE);
+
+    // Refilling with the same color selects the same region again
+    assert_eq!(engine.flood_fill(3, 5, &yape::RED, 0), left);
+    assert_eq!(engine.get_draw_target().select_region(9, 5, 0).count(), 5 * 6);
+    assert_eq!(engine.get_draw_target().select_region(4, 0, 0).count(), 6);
+}
+
+#[test]
+fn diagonal_walls_stop_fills() {
+    let engine = walled();
+    let above = engine.get_draw_target().select_region(0, 0, 0);
+    // The triangle above the wall
+    assert_eq!(above.count(), 7 * 8 / 2);
+    assert!(above.get(6, 0));
+    assert!(!above.get(7, 7));
+
+    // Wall pixels only touch each other at the corners
+    assert_eq!(engine.get_draw_target().select_region(3, 4, 0).count(), 1);
+    assert_eq!(engine.get_draw_target().select_region(7, 7, 0).count(), 7 * 8 / 2);
+}
+
+#[test]
+fn tolerance_is_the_largest_channel_difference() {
+    let mut engine = YapeEngine::construct_headless(4, 1);
+    engine.draw_pixel(0, 0, &Pixel::rgb(100, 100, 100));
+    engine.draw_pixel(1, 0, &Pixel::rgb(110, 95, 100));
+    engine.draw_pixel(2, 0, &Pixel::rgb(100, 100, 121));
+    engine.draw_pixel(3, 0, &Pixel::rgb(100, 100, 100));
+    assert_eq!(engine.get_draw_target().select_region(0, 0, 9).count(), 1);
+    assert_eq!(engine.get_draw_target().select_region(0, 0, 10).count(), 2);
+    assert_eq!(engine.get_draw_target().select_region(0, 0, 21).count(), 4);
+}
+
+#[test]
+fn out_of_range_seeds_select_nothing() {
+    let mut engine = walled();
+    assert!(engine.get_draw_target().select_region(8, 0, 0).is_empty());
+    assert!(engine.flood_fill(0, 100, &yape::RED, 255).is_empty());
+    assert!(engine.get_draw_target().data.iter().all(|p| *p != yape::RED));
+}
+
+#[test]
+fn mask_helpers() {
+    let mut mask = Mask::new(4, 3);
+    assert!(mask.is_empty());
+    assert_eq!(mask.bounds(), None);
+    mask.set(1, 1, true);
+    mask.set(2, 1, true);
+    mask.set(9, 9, true);
+    assert_eq!(mask.spans(), vec![(1, 2, 1)]);
+    assert_eq!(mask.bounds(), Some(Recti::new(1, 1, 2, 1)));
+    mask.invert();
+    assert_eq!(mask.count(), 10);
+    assert!(!mask.get(1, 1) && mask.get(0, 0) && !mask.get(4, 0));
+}
@@ -0,0 +1,84 @@
+use yape::{Rect, Recti, Rectf, Vf2d, Vi2d};
+
+#[test]
+fn vector_operators_and_helpers() {
+    let a = Vi2d::new(3, 4);
+    let b = Vi2d::new(-1, 2);
+    assert_eq!(a + b, Vi2d::new(2, 6));
+    assert_eq!(a - b, Vi2d::new(4, 2));
+    assert_eq!(a * 2, Vi2d::new(6, 8));
+    assert_eq!(a.dot(b), 5);
+    assert_eq!(a.cross(b), 10);
+    assert_eq!(a.perp(), Vi2d::new(-4, 3));
+    assert_eq!(a.to_f32().length(), 5.0);
+    assert_eq!(Vf2d::new(0.0, 0.0).normalize(), Vf2d::new(0.0, 0.0));
+    assert_eq!(Vf2d::new(2.5, -2.5).floor().to_i32(), Vi2d::new(2, -3));
+}
+
+#[test]
+fn contains_is_half_open() {
+    let r = Recti::new(0, 0, 4, 3);
+    assert!(r.contains(Vi2d::new(0, 0)));
+    assert!(r.contains(Vi2d::new(3, 2)));
+    assert!(!r.contains(Vi2d::new(4, 0)));
+    assert!(!r.contains(Vi2d::new(0, 3)));
+    assert!(!Recti::new(1, 1, 0, 0).contains(Vi2d::new(1, 1)));
+
+    assert!(r.contains_rect(&r));
+    assert!(r.contains_rect(&Recti::new(1, 1, 3, 2)));
+    assert!(!r.contains_rect(&Recti::new(1, 1, 4, 2)));
+}
+
+#[test]
+fn touching_rects_neither_intersect_nor_clip() {
+    let a = Recti::new(0, 0, 4, 4);
+    let right = Recti::new(4, 0, 4, 4);
+    let below = Recti::new(0, 4, 4, 4);
+    let corner = Recti::new(4, 4, 1, 1);
+    for other in [right, below, corner].iter() {
+        assert!(!a.intersects(other));
+        assert!(!other.intersects(&a));
+        assert_eq!(a.clip(other), None);
+    }
+}
+
+#[test]
+fn empty_rects_never_intersect() {
+    let a = Recti::new(0, 0, 10, 10);
+    let point = Recti::new(5, 5, 0, 0);
+    let line = Recti::new(2, 0, 0, 10);
+    let negative = Recti::new(5, 5, -2, 3);
+    for empty in [point, line, negative].iter() {
+        assert!(empty.is_empty());
+        assert!(!a.intersects(empty));
+        assert!(!empty.intersects(&a));
+        assert_eq!(a.clip(empty), None);
+    }
+}
+
+#[test]
+fn clip_keeps_the_overlap() {
+    let a = Recti::new(0, 0, 10, 10);
+    assert_eq!(Recti::new(-5, 8, 10, 10).clip(&a), Some(Recti::new(0, 8, 5, 2)));
+    assert_eq!(Recti::new(2, 3, 4, 4).clip(&a), Some(Recti::new(2, 3, 4, 4)));
+    assert_eq!(a.clip(&Recti::new(2, 3, 4, 4)), Some(Recti::new(2, 3, 4, 4)));
+    let f = Rectf::new(0.5, 0.5, 1.0, 1.0).clip(&Rectf::new(1.0, 0.0, 2.0, 2.0));
+    assert_eq!(f, Some(Rectf::new(1.0, 0.5, 0.5, 1.0)));
+}
+
+#[test]
+fn union_bounds_both_and_skips_empty_rects() {
+    let a = Recti::new(0, 0, 2, 2);
+    let b = Recti::new(5, -1, 1, 1);
+    assert_eq!(a.union(&b), Recti::new(0, -1, 6, 3));
+    assert_eq!(a.union(&Recti::new(2, 0, 2, 2)), Recti::new(0, 0, 4, 2));
+    let far_empty = Recti::new(100, 100, 0, 0);
+    assert_eq!(a.union(&far_empty), a);
+    assert_eq!(far_empty.union(&a), a);
+    assert!(Rect::<i32>::default().union(&Rect::default()).is_empty());
+}
+
+#[test]
+fn from_corners_orders_the_corners() {
+    assert_eq!(Recti::from_corners(Vi2d::new(4, 1), Vi2d::new(1, 5)), Recti::new(1, 1, 3, 4));
+}
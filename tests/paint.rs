@@ -0,0 +1,113 @@
+use yape::paint::{Gradient, Paint};
+use yape::{Pixel, Sprite, Vf2d, Vi2d, YapeEngine, YapeEngineApi};
+
+#[test]
+fn gradient_clamps_at_both_ends() {
+    let gradient = Gradient::new(yape::BLACK, yape::WHITE);
+    assert_eq!(gradient.sample(-1.0), yape::BLACK);
+    assert_eq!(gradient.sample(0.0), yape::BLACK);
+    assert_eq!(gradient.sample(1.0), yape::WHITE);
+    assert_eq!(gradient.sample(7.0), yape::WHITE);
+    let mid = gradient.sample(0.5);
+    assert!((126..=129).contains(&mid.red), "{:?}", mid);
+}
+
+#[test]
+fn gradient_stops_are_sorted_and_can_make_hard_edges() {
+    let gradient = Gradient::new(yape::BLACK, yape::WHITE)
+        .with_stop(0.5, yape::BLUE)
+        .with_stop(0.25, yape::RED)
+        .with_stop(0.5, yape::GREEN)
+        .with_stop(2.0, yape::YELLOW);
+    let offsets: Vec<f32> = gradient.stops().iter().map(|s| s.offset).collect();
+    assert_eq!(offsets, vec![0.0, 0.25, 0.5, 0.5, 1.0, 1.0]);
+    assert_eq!(gradient.sample(0.25), yape::RED);
+    assert_eq!(gradient.sample(0.5), yape::BLUE);
+    assert_eq!(gradient.sample(0.5001).green, 255);
+    assert_eq!(gradient.sample(1.0), yape::WHITE);
+}
+
+#[test]
+fn linear_gradient_is_evaluated_at_pixel_centers() {
+    let paint = Paint::LinearGradient {
+        start: Vf2d::new(2.0, 0.0),
+        end: Vf2d::new(10.0, 0.0),
+        gradient: Gradient::new(yape::BLACK, Pixel::rgb(160, 0, 0)),
+    };
+    assert_eq!(paint.color_at(0, 0), yape::BLACK);
+    assert_eq!(paint.color_at(1, 5), yape::BLACK);
+    assert_eq!(paint.color_at(2, 5).red, 10);
+    assert_eq!(paint.color_at(9, 5).red, 150);
+    assert_eq!(paint.color_at(10, 0).red, 160);
+    assert_eq!(paint.color_at(50, -3).red, 160);
+
+    let degenerate = Paint::LinearGradient {
+        start: Vf2d::new(4.0, 4.0),
+        end: Vf2d::new(4.0, 4.0),
+        gradient: Gradient::new(yape::RED, yape::BLUE),
+    };
+    assert_eq!(degenerate.color_at(9, 9), yape::RED);
+}
+
+#[test]
+fn radial_gradient_runs_from_center_to_radius() {
+    let paint = Paint::RadialGradient {
+        center: Vf2d::new(8.5, 8.5),
+        radius: 4.0,
+        gradient: Gradient::new(yape::WHITE, yape::BLACK),
+    };
+    assert_eq!(paint.color_at(8, 8), yape::WHITE);
+    assert_eq!(paint.color_at(12, 8), yape::BLACK);
+    assert_eq!(paint.color_at(0, 0), yape::BLACK);
+    let halfway = paint.color_at(10, 8);
+    assert!((126..=129).contains(&halfway.red), "{:?}", halfway);
+}
+
+#[test]
+fn pattern_tiles_in_every_direction() {
+    let mut sprite = Sprite::new(2, 2);
+    sprite.set_pixel(0, 0, &yape::RED);
+    sprite.set_pixel(1, 1, &yape::BLUE);
+    let paint = Paint::Pattern { sprite: &sprite, offset: Vi2d::new(1, 0) };
+    assert_eq!(paint.color_at(1, 0), yape::RED);
+    assert_eq!(paint.color_at(5, 4), yape::RED);
+    assert_eq!(paint.color_at(-1, -2), yape::RED);
+    assert_eq!(paint.color_at(0, 1), yape::BLUE);
+    assert_eq!(paint.color_at(-2, -1), yape::BLUE);
+
+    let empty = Sprite::new(0, 0);
+    assert_eq!(Paint::Pattern { sprite: &empty, offset: Vi2d::new(0, 0) }.color_at(3, 3), Pixel::default());
+}
+
+#[test]
+fn dither_levels_pick_a_share_of_each_color() {
+    let count = |level: f32| {
+        let paint = Paint::Dither { first: yape::BLACK, second: yape::WHITE, level };
+        (0..4).flat_map(|y| (0..4).map(move |x| (x, y))).filter(|&(x, y)| paint.color_at(x, y) == yape::WHITE).count()
+    };
+    assert_eq!(count(0.0), 0);
+    assert_eq!(count(0.25), 4);
+    assert_eq!(count(0.5), 8);
+    assert_eq!(count(1.0), 16);
+    // The matrix repeats every four pixels
+    let paint = Paint::Dither { first: yape::BLACK, second: yape::WHITE, level: 0.3 };
+    assert_eq!(paint.color_at(1, 2), paint.color_at(5, -2));
+}
+
+#[test]
+fn painted_fills_cover_the_same_pixels_as_solid_ones() {
+    let paint = Paint::LinearGradient {
+        start: Vf2d::new(0.0, 0.0),
+        end: Vf2d::new(16.0, 0.0),
+        gradient: Gradient::new(yape::RED, yape::BLUE),
+    };
+    let mut painted = YapeEngine::construct_headless(16, 16);
+    painted.fill_circle_with(Vi2d::new(7, 7), 5, &paint);
+    let mut solid = YapeEngine::construct_headless(16, 16);
+    solid.fill_circle_v(Vi2d::new(7, 7), 5, &yape::WHITE);
+
+    let covered = |e: &YapeEngine| -> Vec<bool> { e.get_draw_target().data.iter().map(|p| p.alpha != 0).collect() };
+    assert_eq!(covered(&painted), covered(&solid));
+    assert_eq!(*painted.get_draw_target().get_pixel(2, 7), paint.color_at(2, 7));
+    assert_eq!(*painted.get_draw_target().get_pixel(12, 7), paint.color_at(12, 7));
+}
@@ -0,0 +1,242 @@
+use yape::math::Vf2d;
+use yape::paint::Gradient;
+use yape::particles::{EmitterConfig, ParticleBlend, ParticleEmitter, ParticleShape};
+use yape::{Pixel, PixelMode, YapeEngine, YapeEngineApi};
+
+fn config() -> EmitterConfig {
+    EmitterConfig {
+        position: Vf2d::new(32.0, 32.0),
+        spawn_rate: 120.0,
+        lifetime: (0.5, 1.5),
+        speed: (10.0, 30.0),
+        spread: std::f32::consts::PI * 2.0,
+        gravity: Vf2d::new(0.0, 20.0),
+        size: (1.0, 4.0),
+        end_size: (0.0, 1.0),
+        color: Gradient::new(Pixel::rgb(255, 200, 50), Pixel::rgba(255, 0, 0, 0)),
+        shape: ParticleShape::Circle,
+        ..EmitterConfig::default()
+    }
+}
+
+fn run(seed: u64) -> (ParticleEmitter, YapeEngine) {
+    let mut emitter = ParticleEmitter::new(config(), seed);
+    let mut engine = YapeEngine::construct_headless(64, 64);
+    emitter.burst(20);
+    for _ in 0..60 {
+        emitter.update(1.0 / 60.0);
+    }
+    emitter.draw(&mut engine);
+    (emitter, engine)
+}
+
+#[test]
+fn same_seed_replays_exactly() {
+    let (a, engine_a) = run(7);
+    let (b, engine_b) = run(7);
+    assert!(!a.is_empty());
+    assert_eq!(a.particles(), b.particles());
+    assert_eq!(engine_a.get_draw_target().data, engine_b.get_draw_target().data);
+}
+
+#[test]
+fn different_seeds_differ() {
+    let (a, _) = run(1);
+    let (b, _) = run(2);
+    assert_ne!(a.particles(), b.particles());
+}
+
+#[test]
+fn bursts_respect_max_particles() {
+    let mut emitter = ParticleEmitter::new(EmitterConfig { spawn_rate: 0.0, max_particles: 10, ..config() }, 0);
+    emitter.burst(4);
+    assert_eq!(emitter.len(), 4);
+    emitter.burst(100);
+    assert_eq!(emitter.len(), 10);
+}
+
+#[test]
+fn spawn_rate_carries_fractions_between_updates() {
+    let mut emitter = ParticleEmitter::new(EmitterConfig { spawn_rate: 10.0, lifetime: (10.0, 10.0), ..config() }, 0);
+    for _ in 0..30 {
+        emitter.update(1.0 / 30.0);
+    }
+    assert_eq!(emitter.len(), 10);
+    emitter.active = false;
+    emitter.update(1.0);
+    assert_eq!(emitter.len(), 10);
+}
+
+#[test]
+fn particles_expire_after_their_lifetime() {
+    let mut emitter = ParticleEmitter::new(EmitterConfig { spawn_rate: 0.0, lifetime: (0.5, 1.0), ..config() }, 3);
+    emitter.burst(50);
+    emitter.update(0.25);
+    assert_eq!(emitter.len(), 50);
+    emitter.update(0.8);
+    assert!(emitter.is_empty());
+}
+
+#[test]
+fn additive_particles_brighten_and_restore_pixel_mode() {
+    let mut emitter = ParticleEmitter::new(
+        EmitterConfig {
+            spawn_rate: 0.0,
+            speed: (0.0, 0.0),
+            color: Gradient::new(Pixel::rgb(100, 50, 10), Pixel::rgb(100, 50, 10)),
+            blend: ParticleBlend::Additive,
+            shape: ParticleShape::Pixel,
+            ..config()
+        },
+        0,
+    );
+    emitter.burst(2);
+    let mut engine = YapeEngine::construct_headless(64, 64);
+    engine.clear(&Pixel::rgb(10, 10, 10));
+    emitter.draw(&mut engine);
+    assert_eq!(*engine.get_draw_target().get_pixel(32, 32), Pixel::rgb(210, 110, 30));
+    assert_eq!(*engine.get_pixel_mode(), PixelMode::Normal);
+}
+
+// Particles flying right from the config position, living for ten seconds
+fn flying() -> EmitterConfig {
+    EmitterConfig {
+        spawn_rate: 0.0,
+        lifetime: (10.0, 10.0),
+        speed: (10.0, 10.0),
+        direction: 0.0,
+        spread: 0.0,
+        gravity: Vf2d::new(0.0, 0.0),
+        ..config()
+    }
+}
+
+fn single(config: EmitterConfig) -> ParticleEmitter {
+    let mut emitter = ParticleEmitter::new(config, 0);
+    emitter.burst(1);
+    emitter
+}
+
+fn close(a: Vf2d, b: Vf2d) -> bool {
+    (a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4
+}
+
+#[test]
+fn gravity_acceleration_and_damping_change_the_velocity() {
+    let mut falling = single(EmitterConfig { gravity: Vf2d::new(0.0, 20.0), ..flying() });
+    falling.update(0.5);
+    let p = falling.particles()[0];
+    assert!(close(p.velocity, Vf2d::new(10.0, 10.0)), "{:?}", p);
+    assert!(close(p.position, Vf2d::new(37.0, 37.0)), "{:?}", p);
+    assert_eq!(p.age, 0.5);
+
+    let mut braking = single(EmitterConfig { acceleration: (-4.0, -4.0), ..flying() });
+    braking.update(0.5);
+    assert!(close(braking.particles()[0].velocity, Vf2d::new(8.0, 0.0)));
+    assert!(close(braking.particles()[0].position, Vf2d::new(36.0, 32.0)));
+
+    let mut damped = single(EmitterConfig { damping: 1.0, ..flying() });
+    damped.update(0.5);
+    assert!(close(damped.particles()[0].velocity, Vf2d::new(5.0, 0.0)));
+    // Damping never turns the velocity around
+    damped.update(4.0);
+    assert!(close(damped.particles()[0].velocity, Vf2d::new(0.0, 0.0)));
+}
+
+#[test]
+fn spawned_values_stay_inside_their_ranges() {
+    let config = EmitterConfig {
+        spawn_rate: 0.0,
+        direction: std::f32::consts::PI / 2.0,
+        spread: std::f32::consts::PI / 2.0,
+        ..config()
+    };
+    let mut emitter = ParticleEmitter::new(config.clone(), 11);
+    emitter.burst(200);
+    for p in emitter.particles() {
+        let angle = p.velocity.angle();
+        assert!(angle >= config.direction - config.spread / 2.0 - 1e-4, "{:?}", p);
+        assert!(angle <= config.direction + config.spread / 2.0 + 1e-4, "{:?}", p);
+        let speed = p.velocity.length();
+        assert!(speed >= config.speed.0 - 1e-3 && speed <= config.speed.1 + 1e-3, "{:?}", p);
+        assert!(p.lifetime >= config.lifetime.0 && p.lifetime <= config.lifetime.1);
+        assert!(p.size >= config.size.0 && p.size <= config.size.1);
+        assert!(p.end_size >= config.end_size.0 && p.end_size <= config.end_size.1);
+        assert_eq!(p.position, config.position);
+    }
+
+    emitter.clear();
+    assert!(emitter.is_empty());
+}
+
+#[test]
+fn size_and_color_follow_the_particle_life() {
+    let mut emitter = single(EmitterConfig {
+        size: (4.0, 4.0),
+        end_size: (2.0, 2.0),
+        color: Gradient::new(yape::RED, yape::BLUE),
+        blend: ParticleBlend::Alpha,
+        shape: ParticleShape::Pixel,
+        speed: (0.0, 0.0),
+        ..flying()
+    });
+    let p = emitter.particles()[0];
+    assert_eq!((p.life(), p.current_size()), (0.0, 4.0));
+
+    let mut engine = YapeEngine::construct_headless(64, 64);
+    emitter.draw(&mut engine);
+    assert_eq!(*engine.get_draw_target().get_pixel(32, 32), yape::RED);
+
+    emitter.update(5.0);
+    let p = emitter.particles()[0];
+    assert_eq!((p.life(), p.current_size()), (0.5, 3.0));
+    emitter.draw(&mut engine);
+    assert_eq!(*engine.get_draw_target().get_pixel(32, 32), emitter.config.color.sample(0.5));
+}
+
+#[test]
+fn shapes_draw_like_the_engine_primitives() {
+    let still = |shape: ParticleShape, size: f32| {
+        single(EmitterConfig {
+            position: Vf2d::new(20.5, 30.25),
+            size: (size, size),
+            end_size: (size, size),
+            speed: (0.0, 0.0),
+            color: Gradient::new(yape::GREEN, yape::GREEN),
+            blend: ParticleBlend::Alpha,
+            shape,
+            ..flying()
+        })
+    };
+    let draw = |emitter: &ParticleEmitter| {
+        let mut engine = YapeEngine::construct_headless(64, 64);
+        engine.clear(&yape::BLACK);
+        emitter.draw(&mut engine);
+        engine.get_draw_target().data.clone()
+    };
+    let reference = |paint: &dyn Fn(&mut YapeEngine)| {
+        let mut engine = YapeEngine::construct_headless(64, 64);
+        engine.clear(&yape::BLACK);
+        paint(&mut engine);
+        engine.get_draw_target().data.clone()
+    };
+    let center = yape::Vi2d::new(20, 30);
+
+    assert_eq!(
+        draw(&still(ParticleShape::Square, 3.0)),
+        reference(&|e| e.fill_rect_v(center - yape::Vi2d::new(1, 1), yape::Vi2d::new(3, 3), &yape::GREEN))
+    );
+    // Squares are at least one pixel
+    assert_eq!(
+        draw(&still(ParticleShape::Square, 0.2)),
+        reference(&|e| e.fill_rect_v(center, yape::Vi2d::new(1, 1), &yape::GREEN))
+    );
+    assert_eq!(
+        draw(&still(ParticleShape::Circle, 6.0)),
+        reference(&|e| e.fill_circle_v(center, 3, &yape::GREEN))
+    );
+    assert_eq!(
+        draw(&still(ParticleShape::Pixel, 6.0)),
+        reference(&|e| e.draw_pixel(20, 30, &yape::GREEN))
+    );
+}
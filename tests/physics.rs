@@ -0,0 +1,254 @@
+use yape::camera::Camera2D;
+use yape::collision::{Circle, Shape};
+use yape::physics::{Body, BodyType, PhysicsWorld};
+use yape::{Rectf, Vf2d, YapeEngine, YapeEngineApi};
+
+const DT: f32 = 1.0 / 60.0;
+
+fn close(a: Vf2d, b: Vf2d, tolerance: f32) -> bool {
+    (a.x - b.x).abs() < tolerance && (a.y - b.y).abs() < tolerance
+}
+
+// Box of w by h centered on pos
+fn block(body_type: BodyType, pos: Vf2d, w: f32, h: f32) -> Body {
+    Body::new(body_type, Shape::Aabb(Rectf::new(-w / 2.0, -h / 2.0, w, h))).with_position(pos)
+}
+
+fn ball(pos: Vf2d, radius: f32) -> Body {
+    Body::new(BodyType::Dynamic, Shape::Circle(Circle::new(Vf2d::new(0.0, 0.0), radius))).with_position(pos)
+}
+
+// Static floor with its top at y = 100
+fn floor() -> Body {
+    block(BodyType::Static, Vf2d::new(200.0, 110.0), 400.0, 20.0)
+}
+
+#[test]
+fn mass_follows_shape_and_density() {
+    assert_eq!(block(BodyType::Dynamic, Vf2d::new(0.0, 0.0), 4.0, 2.0).with_density(2.0).mass(), 16.0);
+    assert!((ball(Vf2d::new(0.0, 0.0), 2.0).mass() - 4.0 * std::f32::consts::PI).abs() < 1e-4);
+    assert_eq!(block(BodyType::Static, Vf2d::new(0.0, 0.0), 4.0, 2.0).mass(), 0.0);
+    assert_eq!(block(BodyType::Kinematic, Vf2d::new(0.0, 0.0), 4.0, 2.0).mass(), 0.0);
+
+    // Off center pushes spin polygons by their inertia, 16 * (16 + 16) / 12 for a 4 by 4 square
+    let square = vec![Vf2d::new(-2.0, -2.0), Vf2d::new(2.0, -2.0), Vf2d::new(2.0, 2.0), Vf2d::new(-2.0, 2.0)];
+    let mut body = Body::new(BodyType::Dynamic, Shape::Polygon(square.clone()));
+    assert!((body.mass() - 16.0).abs() < 1e-4);
+    body.apply_impulse(Vf2d::new(0.0, 10.0), Vf2d::new(2.0, 0.0));
+    assert!(close(body.velocity, Vf2d::new(0.0, 0.625), 1e-5));
+    assert!((body.angular_velocity - 20.0 * 12.0 / 512.0).abs() < 1e-5);
+    assert!(close(body.velocity_at(Vf2d::new(0.0, 2.0)), Vf2d::new(-0.9375, 0.625), 1e-5));
+
+    let mut fixed = Body::new(BodyType::Dynamic, Shape::Polygon(square)).with_fixed_rotation();
+    fixed.apply_impulse(Vf2d::new(0.0, 10.0), Vf2d::new(2.0, 0.0));
+    assert_eq!(fixed.angular_velocity, 0.0);
+    let mut boxed = block(BodyType::Dynamic, Vf2d::new(0.0, 0.0), 4.0, 4.0);
+    boxed.apply_impulse(Vf2d::new(0.0, 10.0), Vf2d::new(2.0, 0.0));
+    assert_eq!(boxed.angular_velocity, 0.0);
+}
+
+#[test]
+fn update_runs_whole_fixed_steps() {
+    let mut world = PhysicsWorld::new(Vf2d::new(0.0, 100.0));
+    let id = world.add(ball(Vf2d::new(0.0, 0.0), 1.0));
+    let mut steps = 0;
+    for _ in 0..60 {
+        steps += world.update(DT);
+    }
+    assert_eq!(steps, 60);
+    // Velocity first, then position: 100 * (1 + 2 + ... + 60) / 60^2
+    let body = world.body(id).unwrap();
+    assert!(close(body.velocity, Vf2d::new(0.0, 100.0), 1e-2));
+    assert!(close(body.position, Vf2d::new(0.0, 100.0 * 1830.0 / 3600.0), 1e-2));
+
+    assert_eq!(world.update(DT / 2.0), 0);
+    assert_eq!(world.update(DT / 2.0), 1);
+    // A long frame runs a few steps and drops the rest
+    assert_eq!(world.update(1.0), 8);
+    assert_eq!(world.update(0.0), 1);
+    assert_eq!(world.update(0.0), 0);
+
+    let mut slow = PhysicsWorld::new(Vf2d::new(0.0, 0.0)).with_fixed_dt(0.5);
+    let id = slow.add(ball(Vf2d::new(0.0, 0.0), 1.0).with_velocity(Vf2d::new(8.0, 0.0)));
+    slow.body_mut(id).unwrap().linear_damping = 1.0;
+    assert_eq!(slow.update(0.5), 1);
+    assert!(close(slow.body(id).unwrap().velocity, Vf2d::new(4.0, 0.0), 1e-5));
+    assert!(close(slow.body(id).unwrap().position, Vf2d::new(2.0, 0.0), 1e-5));
+}
+
+#[test]
+fn forces_last_one_step() {
+    let mut world = PhysicsWorld::new(Vf2d::new(0.0, 0.0)).with_fixed_dt(0.5);
+    let id = world.add(block(BodyType::Dynamic, Vf2d::new(0.0, 0.0), 2.0, 2.0));
+    world.body_mut(id).unwrap().apply_force(Vf2d::new(8.0, 0.0));
+    world.step();
+    assert!(close(world.body(id).unwrap().velocity, Vf2d::new(1.0, 0.0), 1e-5));
+    world.step();
+    assert!(close(world.body(id).unwrap().velocity, Vf2d::new(1.0, 0.0), 1e-5));
+}
+
+#[test]
+fn boxes_come_to_rest_on_the_floor() {
+    let mut world = PhysicsWorld::new(Vf2d::new(0.0, 200.0));
+    let ground = world.add(floor().with_restitution(0.0));
+    let id = world.add(block(BodyType::Dynamic, Vf2d::new(100.0, 80.0), 10.0, 10.0).with_restitution(0.0));
+    for _ in 0..180 {
+        world.update(DT);
+    }
+    let body = world.body(id).unwrap();
+    assert!((body.position.y - 95.0).abs() < 0.2, "{:?}", body.position);
+    assert_eq!(body.position.x, 100.0);
+    assert!(body.velocity.length() < 1e-3, "{:?}", body.velocity);
+    assert_eq!(world.body(ground).unwrap().position, Vf2d::new(200.0, 110.0));
+
+    let contacts: Vec<_> = world.contacts().collect();
+    assert_eq!(contacts.len(), 1);
+    assert_eq!((contacts[0].0, contacts[0].1), (id, ground));
+    assert!(close(contacts[0].2.normal, Vf2d::new(0.0, 1.0), 1e-5));
+}
+
+#[test]
+fn the_bouncier_body_sets_the_rebound() {
+    let mut world = PhysicsWorld::new(Vf2d::new(0.0, 0.0));
+    world.add(floor().with_restitution(0.0));
+    let id = world.add(ball(Vf2d::new(50.0, 90.0), 5.0).with_velocity(Vf2d::new(0.0, 100.0)).with_restitution(0.5));
+    for _ in 0..20 {
+        world.step();
+        if world.body(id).unwrap().velocity.y < 0.0 {
+            break;
+        }
+    }
+    assert!(close(world.body(id).unwrap().velocity, Vf2d::new(0.0, -50.0), 1e-3));
+    assert_eq!(world.body(id).unwrap().angular_velocity, 0.0);
+}
+
+#[test]
+fn collisions_keep_momentum() {
+    // Equal masses swap velocities in an elastic collision
+    let mut world = PhysicsWorld::new(Vf2d::new(0.0, 0.0));
+    let a = world.add(ball(Vf2d::new(0.0, 0.0), 5.0).with_velocity(Vf2d::new(100.0, 0.0)).with_restitution(1.0));
+    let b = world.add(ball(Vf2d::new(15.0, 0.0), 5.0).with_restitution(1.0));
+    for _ in 0..10 {
+        world.step();
+        if world.contacts().next().is_some() {
+            break;
+        }
+    }
+    let (from, to, contact) = world.contacts().next().unwrap();
+    assert_eq!((from, to), (a, b));
+    assert!(close(contact.normal, Vf2d::new(1.0, 0.0), 1e-5));
+    assert!(close(world.body(a).unwrap().velocity, Vf2d::new(0.0, 0.0), 1e-3));
+    assert!(close(world.body(b).unwrap().velocity, Vf2d::new(100.0, 0.0), 1e-3));
+
+    // Without restitution a small ball and one four times heavier move on together
+    let mut world = PhysicsWorld::new(Vf2d::new(0.0, 0.0));
+    let a = world.add(ball(Vf2d::new(0.0, 0.0), 5.0).with_velocity(Vf2d::new(100.0, 0.0)).with_restitution(0.0));
+    let b = world.add(ball(Vf2d::new(20.0, 0.0), 10.0).with_restitution(0.0));
+    for _ in 0..10 {
+        world.step();
+        if world.contacts().next().is_some() {
+            break;
+        }
+    }
+    assert!(close(world.body(a).unwrap().velocity, Vf2d::new(20.0, 0.0), 1e-3));
+    assert!(close(world.body(b).unwrap().velocity, Vf2d::new(20.0, 0.0), 1e-3));
+}
+
+#[test]
+fn friction_stops_sliding() {
+    let slide = |friction: f32| {
+        let mut world = PhysicsWorld::new(Vf2d::new(0.0, 200.0));
+        world.add(floor().with_friction(friction));
+        let body = block(BodyType::Dynamic, Vf2d::new(20.0, 95.02), 10.0, 10.0);
+        let id = world.add(body.with_velocity(Vf2d::new(50.0, 0.0)).with_friction(friction).with_restitution(0.0));
+        for _ in 0..60 {
+            world.update(DT);
+        }
+        world.remove(id).unwrap()
+    };
+    let slippery = slide(0.0);
+    assert!((slippery.velocity.x - 50.0).abs() < 1e-3);
+    assert!((slippery.position.x - 70.0).abs() < 0.1);
+
+    // Stops after sliding 50^2 / (2 * 200) pixels
+    let rough = slide(1.0);
+    assert!(rough.velocity.x.abs() < 1e-2, "{:?}", rough.velocity);
+    assert!((rough.position.x - 26.25).abs() < 1.0, "{:?}", rough.position);
+}
+
+#[test]
+fn kinematic_bodies_push_without_being_pushed() {
+    let mut world = PhysicsWorld::new(Vf2d::new(0.0, 0.0));
+    let wall = world.add(block(BodyType::Static, Vf2d::new(40.0, 50.0), 10.0, 10.0));
+    let pusher = block(BodyType::Kinematic, Vf2d::new(0.0, 0.0), 10.0, 10.0).with_velocity(Vf2d::new(60.0, 0.0));
+    let pusher = world.add(pusher.with_restitution(0.0));
+    let ghost = block(BodyType::Kinematic, Vf2d::new(0.0, 50.0), 10.0, 10.0).with_velocity(Vf2d::new(60.0, 0.0));
+    let ghost = world.add(ghost);
+    let pushed = world.add(block(BodyType::Dynamic, Vf2d::new(20.0, 0.0), 10.0, 10.0).with_restitution(0.0));
+    for _ in 0..60 {
+        world.update(DT);
+        // Kinematic and static bodies pass through each other
+        assert!(world.contacts().all(|(a, b, _)| a != ghost && b != ghost && a != wall && b != wall));
+    }
+    let (pusher, pushed) = (world.body(pusher).unwrap(), world.body(pushed).unwrap());
+    assert_eq!(pusher.velocity, Vf2d::new(60.0, 0.0));
+    assert!((pusher.position.x - 60.0).abs() < 1e-2);
+    assert!(pushed.position.x >= pusher.position.x + 9.0, "{:?}", pushed.position);
+    assert!((pushed.velocity.x - 60.0).abs() < 1e-2, "{:?}", pushed.velocity);
+    assert!((world.body(ghost).unwrap().position.x - 60.0).abs() < 1e-2);
+    assert_eq!(world.body(wall).unwrap().position, Vf2d::new(40.0, 50.0));
+}
+
+#[test]
+fn removing_keeps_other_ids() {
+    let mut world = PhysicsWorld::new(Vf2d::new(0.0, 0.0));
+    let ids: Vec<_> = (0..3).map(|i| world.add(ball(Vf2d::new(i as f32 * 100.0, 0.0), 1.0))).collect();
+    assert!(world.remove(ids[0]).is_some());
+    assert!(world.remove(ids[0]).is_none() && world.body(ids[0]).is_none());
+    assert_eq!(world.len(), 2);
+    assert_eq!(world.body(ids[2]).unwrap().position, Vf2d::new(200.0, 0.0));
+    assert_eq!(world.query_point(Vf2d::new(200.0, 0.0)), vec![ids[2]]);
+    assert!(world.query_point(Vf2d::new(0.0, 0.0)).is_empty());
+
+    let added = world.add(ball(Vf2d::new(0.0, 0.0), 1.0));
+    assert!(!ids.contains(&added));
+    let listed: Vec<_> = world.bodies().map(|(id, _)| id).collect();
+    assert_eq!(listed.len(), 3);
+    assert!(listed.contains(&ids[1]) && listed.contains(&ids[2]) && listed.contains(&added));
+}
+
+#[test]
+fn rays_and_points_find_bodies() {
+    let mut world = PhysicsWorld::new(Vf2d::new(0.0, 0.0)).with_cell_size(16.0);
+    let wall = world.add(block(BodyType::Static, Vf2d::new(55.0, 50.0), 10.0, 100.0));
+    let round = world.add(ball(Vf2d::new(20.0, 50.0), 5.0));
+
+    let (id, hit) = world.raycast(Vf2d::new(0.0, 50.0), Vf2d::new(100.0, 0.0)).unwrap();
+    assert_eq!(id, round);
+    assert!((hit.time - 0.15).abs() < 1e-5 && close(hit.normal, Vf2d::new(-1.0, 0.0), 1e-5));
+    let (id, hit) = world.raycast(Vf2d::new(100.0, 50.0), Vf2d::new(-100.0, 0.0)).unwrap();
+    assert_eq!(id, wall);
+    assert!((hit.time - 0.4).abs() < 1e-5 && close(hit.point, Vf2d::new(60.0, 50.0), 1e-4));
+    assert!(world.raycast(Vf2d::new(0.0, 50.0), Vf2d::new(10.0, 0.0)).is_none());
+
+    assert_eq!(world.query_point(Vf2d::new(22.0, 50.0)), vec![round]);
+    assert_eq!(world.query_point(Vf2d::new(55.0, 10.0)), vec![wall]);
+    assert!(world.query_point(Vf2d::new(40.0, 50.0)).is_empty());
+}
+
+#[test]
+fn debug_draw_colors_bodies_by_type() {
+    let mut world = PhysicsWorld::new(Vf2d::new(0.0, 0.0));
+    world.add(block(BodyType::Static, Vf2d::new(20.0, 20.0), 20.0, 20.0));
+    world.add(block(BodyType::Kinematic, Vf2d::new(20.0, 70.0), 20.0, 20.0));
+    world.add(ball(Vf2d::new(60.0, 50.0), 10.0));
+    let mut engine = YapeEngine::construct_headless(100, 100);
+    engine.clear(&yape::BLACK);
+    world.debug_draw(&mut engine, &Camera2D::default());
+    let target = engine.get_draw_target();
+    assert_eq!(*target.get_pixel(10, 20), yape::GREY);
+    assert_eq!(*target.get_pixel(20, 80), yape::YELLOW);
+    // The angle mark runs from the center to the edge
+    assert_eq!(*target.get_pixel(65, 50), yape::WHITE);
+    assert_eq!(*target.get_pixel(60, 55), yape::BLACK);
+}
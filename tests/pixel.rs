@@ -0,0 +1,89 @@
+use proptest::prelude::*;
+
+use yape::{Pixel, Sprite};
+
+fn any_pixel() -> impl Strategy<Value = Pixel> {
+    any::<(u8, u8, u8, u8)>().prop_map(|(r, g, b, a)| Pixel::rgba(r, g, b, a))
+}
+
+fn close(a: &Pixel, b: &Pixel) -> bool {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).abs() <= 1;
+    d(a.red, b.red) && d(a.green, b.green) && d(a.blue, b.blue) && a.alpha == b.alpha
+}
+
+#[test]
+fn from_rgb_unpacks_every_channel() {
+    assert_eq!(Pixel::from_rgb(0x00ff_8040), Pixel::rgb(0xff, 0x80, 0x40));
+    assert_eq!(Pixel::from_argb(0x80ff_8040), Pixel::rgba(0xff, 0x80, 0x40, 0x80));
+    assert_eq!(Pixel::from_rgba(0xff80_4020), Pixel::rgba(0xff, 0x80, 0x40, 0x20));
+}
+
+#[test]
+fn from_hex_rejects_malformed_input() {
+    assert_eq!(Pixel::from_hex("#ff8040").unwrap(), Pixel::rgb(0xff, 0x80, 0x40));
+    assert_eq!(Pixel::from_hex("ff804020").unwrap(), Pixel::rgba(0xff, 0x80, 0x40, 0x20));
+    assert!(Pixel::from_hex("#ff80").is_err());
+    assert!(Pixel::from_hex("#gg8040").is_err());
+    assert!(Pixel::from_hex("#+f8040").is_err());
+}
+
+#[test]
+fn mix_gamma_is_brighter_than_lerp_between_complementary_colors() {
+    let lerp = yape::RED.lerp(&yape::GREEN, 0.5);
+    let mixed = yape::RED.mix_gamma(&yape::GREEN, 0.5);
+    assert!(mixed.red > lerp.red && mixed.green > lerp.green);
+}
+
+proptest! {
+    #[test]
+    fn argb_round_trip(p in any_pixel()) {
+        prop_assert_eq!(Pixel::from_argb(p.to_argb()), p);
+    }
+
+    #[test]
+    fn rgba_round_trip(p in any_pixel()) {
+        prop_assert_eq!(Pixel::from_rgba(p.to_rgba()), p);
+    }
+
+    #[test]
+    fn rgb_round_trip(p in any_pixel()) {
+        prop_assert_eq!(Pixel::from_rgb(p.to_rgb()), p.with_alpha(255));
+    }
+
+    #[test]
+    fn hex_round_trip(p in any_pixel()) {
+        prop_assert_eq!(Pixel::from_hex(&p.to_hex()).unwrap(), p);
+    }
+
+    #[test]
+    fn hsv_round_trip(p in any_pixel()) {
+        let (h, s, v) = p.to_hsv();
+        let back = Pixel::from_hsv(h, s, v).with_alpha(p.alpha);
+        prop_assert!(close(&back, &p), "{:?} -> {:?}", p, back);
+    }
+
+    #[test]
+    fn hsl_round_trip(p in any_pixel()) {
+        let (h, s, l) = p.to_hsl();
+        let back = Pixel::from_hsl(h, s, l).with_alpha(p.alpha);
+        prop_assert!(close(&back, &p), "{:?} -> {:?}", p, back);
+    }
+
+    #[test]
+    fn lerp_and_mix_hit_their_endpoints(a in any_pixel(), b in any_pixel()) {
+        prop_assert_eq!(a.lerp(&b, 0.0), a);
+        prop_assert_eq!(a.lerp(&b, 1.0), b);
+        prop_assert!(close(&a.mix_gamma(&b, 0.0), &a));
+        prop_assert!(close(&a.mix_gamma(&b, 1.0), &b));
+    }
+
+    #[test]
+    fn write_rgb_matches_to_rgb(pixels in prop::collection::vec(any_pixel(), 1..64)) {
+        let mut sprite = Sprite::new(pixels.len() as u32, 1);
+        sprite.data.copy_from_slice(&pixels);
+        let mut rgb = vec![0; pixels.len()];
+        sprite.write_rgb(&mut rgb);
+        let expected: Vec<u32> = pixels.iter().map(|p| p.to_rgb()).collect();
+        prop_assert_eq!(rgb, expected);
+    }
+}
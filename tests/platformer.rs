@@ -0,0 +1,265 @@
+use yape::math::Vf2d;
+use yape::platformer::{ControllerConfig, ControllerEvents, ControllerInput, PlatformerController};
+use yape::tilemap::{TileFlags, TileMap, TileSet};
+use yape::Sprite;
+
+const DT: f32 = 1.0 / 60.0;
+
+const IDLE: ControllerInput = ControllerInput { left: false, right: false, jump: false, down: false };
+const LEFT: ControllerInput = ControllerInput { left: true, ..IDLE };
+const RIGHT: ControllerInput = ControllerInput { right: true, ..IDLE };
+const JUMP: ControllerInput = ControllerInput { jump: true, ..IDLE };
+const DOWN: ControllerInput = ControllerInput { down: true, ..IDLE };
+const RIGHT_JUMP: ControllerInput = ControllerInput { right: true, jump: true, ..IDLE };
+
+// 16 pixel tiles: # solid, = one way, / and \ slopes
+fn map(rows: &[&str]) -> TileMap {
+    tile_map(rows, 16)
+}
+
+fn tile_map(rows: &[&str], size: u32) -> TileMap {
+    let mut tileset = TileSet::new(Sprite::new(size * 4, size), size, size);
+    tileset.set_flags(1, TileFlags::SOLID);
+    tileset.set_flags(2, TileFlags::ONE_WAY);
+    tileset.set_flags(3, TileFlags::SOLID | TileFlags::SLOPE_UP_RIGHT);
+    tileset.set_flags(4, TileFlags::SOLID | TileFlags::SLOPE_UP_LEFT);
+    let mut map = TileMap::new(tileset, rows[0].len() as u32, rows.len() as u32);
+    map.add_layer("ground");
+    let tiles: Vec<u32> = rows
+        .iter()
+        .flat_map(|row| row.chars())
+        .map(|c| match c {
+            '#' => 1,
+            '=' => 2,
+            '/' => 3,
+            '\\' => 4,
+            _ => 0,
+        })
+        .collect();
+    map.layers[0].fill_from(&tiles);
+    map
+}
+
+// Player standing with its bottom at y
+fn player(x: f32, bottom: f32) -> PlatformerController {
+    let config = ControllerConfig::default();
+    PlatformerController::new(config, Vf2d::new(x, bottom - config.size.y))
+}
+
+fn play(controller: &mut PlatformerController, map: &TileMap, inputs: &[ControllerInput]) -> Vec<ControllerEvents> {
+    inputs.iter().map(|input| controller.update(map, *input, DT)).collect()
+}
+
+fn repeat(input: ControllerInput, frames: usize) -> Vec<ControllerInput> {
+    vec![input; frames]
+}
+
+#[test]
+fn falls_and_lands_on_the_floor() {
+    let map = map(&["......", "......", "......", "######"]);
+    let mut p = player(10.0, 14.0);
+    let events = play(&mut p, &map, &repeat(IDLE, 60));
+    assert!(p.is_on_ground());
+    assert_eq!(p.bounds().bottom(), 48.0);
+    assert_eq!(events.iter().filter(|e| e.landed).count(), 1);
+}
+
+#[test]
+fn walls_stop_horizontal_movement() {
+    let map = map(&["........", ".....#..", "########"]);
+    let mut p = player(8.0, 32.0);
+    let events = play(&mut p, &map, &repeat(RIGHT, 60));
+    assert_eq!(p.bounds().right(), 80.0);
+    assert_eq!(p.velocity.x, 0.0);
+    assert!(events.iter().any(|e| e.hit_wall));
+    assert!(p.is_on_ground());
+}
+
+#[test]
+fn walks_up_and_down_slopes_without_leaving_the_ground() {
+    let map = map(&["..........", "..........", "...../####", "##########"]);
+    let mut p = player(8.0, 48.0);
+    play(&mut p, &map, &repeat(IDLE, 2));
+
+    let mut on_slope = false;
+    for input in repeat(RIGHT, 80) {
+        p.update(&map, input, DT);
+        assert!(p.is_on_ground());
+        on_slope |= p.is_on_slope();
+    }
+    assert!(on_slope);
+    assert_eq!(p.bounds().bottom(), 32.0);
+    assert!(!p.is_on_slope());
+
+    for input in repeat(LEFT, 80) {
+        p.update(&map, input, DT);
+        assert!(p.is_on_ground());
+    }
+    assert_eq!(p.bounds().bottom(), 48.0);
+}
+
+#[test]
+fn one_way_platforms_are_jumped_through_and_dropped_through() {
+    let map = map(&["......", "......", "......", ".====.", "......", "######"]);
+    let mut p = player(24.0, 80.0);
+    play(&mut p, &map, &repeat(IDLE, 2));
+
+    let events = play(&mut p, &map, &[repeat(JUMP, 40), repeat(IDLE, 60)].concat());
+    assert!(events.iter().any(|e| e.jumped));
+    assert!(!events.iter().any(|e| e.hit_ceiling));
+    assert!(p.is_on_ground());
+    assert_eq!(p.bounds().bottom(), 48.0);
+
+    play(&mut p, &map, &repeat(DOWN, 60));
+    assert!(p.is_on_ground());
+    assert_eq!(p.bounds().bottom(), 80.0);
+}
+
+#[test]
+fn solid_ceilings_stop_jumps() {
+    let map = map(&["......", ".####.", "......", "......", "######"]);
+    let mut p = player(24.0, 64.0);
+    play(&mut p, &map, &repeat(IDLE, 2));
+    let events = play(&mut p, &map, &repeat(JUMP, 20));
+    assert!(events.iter().any(|e| e.hit_ceiling));
+    assert!(p.bounds().top() >= 32.0);
+}
+
+// Walks right until the player has left the ledge
+fn walk_off_ledge(map: &TileMap) -> PlatformerController {
+    let mut p = player(20.0, 32.0);
+    play(&mut p, map, &repeat(IDLE, 2));
+    while p.is_on_ground() {
+        p.update(map, RIGHT, DT);
+    }
+    p
+}
+
+#[test]
+fn coyote_time_allows_late_jumps() {
+    let map = map(&["........", "........", "###.....", "........", "........"]);
+
+    let mut late = walk_off_ledge(&map);
+    let events = play(&mut late, &map, &[repeat(RIGHT, 3), repeat(RIGHT_JUMP, 1)].concat());
+    assert!(events[3].jumped);
+
+    let mut too_late = walk_off_ledge(&map);
+    let events = play(&mut too_late, &map, &[repeat(RIGHT, 10), repeat(RIGHT_JUMP, 1)].concat());
+    assert!(!events[10].jumped);
+}
+
+#[test]
+fn jump_buffer_jumps_on_landing() {
+    let map = map(&["......", "......", "......", "......", "......", "######"]);
+    let landing = {
+        let mut p = player(24.0, 14.0);
+        let events = play(&mut p, &map, &repeat(IDLE, 60));
+        events.iter().position(|e| e.landed).unwrap()
+    };
+
+    let mut early = player(24.0, 14.0);
+    let inputs = [repeat(IDLE, landing - 3), repeat(JUMP, 10)].concat();
+    let events = play(&mut early, &map, &inputs);
+    assert!(events[landing].landed);
+    assert!(events[landing + 1].jumped);
+
+    let mut too_early = player(24.0, 14.0);
+    let inputs = [repeat(IDLE, landing - 10), repeat(JUMP, 20)].concat();
+    let events = play(&mut too_early, &map, &inputs);
+    assert!(!events.iter().any(|e| e.jumped));
+}
+
+#[test]
+fn speeds_up_and_slows_down_at_the_configured_rates() {
+    let map = map(&["......", "......", "######"]);
+    let mut p = player(8.0, 32.0);
+    play(&mut p, &map, &repeat(IDLE, 2));
+
+    p.update(&map, RIGHT, DT);
+    assert!((p.velocity.x - 15.0).abs() < 1e-3);
+    play(&mut p, &map, &repeat(RIGHT, 10));
+    assert_eq!(p.velocity.x, 90.0);
+    play(&mut p, &map, &repeat(IDLE, 4));
+    assert!((p.velocity.x - 10.0).abs() < 1e-3);
+    p.update(&map, IDLE, DT);
+    assert_eq!(p.velocity.x, 0.0);
+
+    // Steering in the air is slower
+    p.update(&map, RIGHT_JUMP, DT);
+    assert!((p.velocity.x - 15.0).abs() < 1e-3);
+    p.update(&map, RIGHT_JUMP, DT);
+    assert!((p.velocity.x - 25.0).abs() < 1e-3);
+}
+
+#[test]
+fn letting_go_of_jump_cuts_it_short() {
+    let map = map(&["......", "......", "......", "......", "......", "######"]);
+    let peak = |inputs: Vec<ControllerInput>| {
+        let mut p = player(24.0, 80.0);
+        play(&mut p, &map, &repeat(IDLE, 2));
+        let mut highest = 0.0f32;
+        for input in inputs {
+            p.update(&map, input, DT);
+            highest = highest.max(80.0 - p.bounds().bottom());
+        }
+        highest
+    };
+    // 300 - 15 * i pixels per second for 19 frames
+    let full = peak(repeat(JUMP, 60));
+    assert!((full - 47.5).abs() < 0.1, "{}", full);
+    let tap = peak([repeat(JUMP, 1), repeat(IDLE, 59)].concat());
+    assert!(tap < full / 2.0, "{} {}", tap, full);
+}
+
+#[test]
+fn falling_speed_is_capped() {
+    let map = map(&[vec!["......"; 19], vec!["######"]].concat());
+    let mut p = player(24.0, 14.0);
+    for _ in 0..40 {
+        p.update(&map, IDLE, DT);
+        assert!(p.velocity.y <= 400.0);
+    }
+    assert_eq!(p.velocity.y, 400.0);
+    assert!(!p.is_on_ground());
+}
+
+#[test]
+fn low_ledges_are_stepped_onto() {
+    // 4 pixel tiles, so a ledge one tile high is within the step height
+    let low = tile_map(
+        &[
+            "....................",
+            "....................",
+            "....................",
+            "....................",
+            "..........##########",
+            "####################",
+        ],
+        4,
+    );
+    let mut p = player(4.0, 20.0);
+    play(&mut p, &low, &repeat(IDLE, 2));
+    let events = play(&mut p, &low, &repeat(RIGHT, 30));
+    assert!(p.is_on_ground());
+    assert_eq!(p.bounds().bottom(), 16.0);
+    assert!(p.bounds().right() > 40.0);
+    assert!(!events.iter().any(|e| e.hit_wall || e.jumped));
+
+    let high = tile_map(
+        &[
+            "....................",
+            "....................",
+            "....................",
+            "..........##########",
+            "..........##########",
+            "####################",
+        ],
+        4,
+    );
+    let mut p = player(4.0, 20.0);
+    play(&mut p, &high, &repeat(IDLE, 2));
+    let events = play(&mut p, &high, &repeat(RIGHT, 30));
+    assert!(events.iter().any(|e| e.hit_wall));
+    assert_eq!(p.bounds().right(), 40.0);
+    assert_eq!(p.bounds().bottom(), 20.0);
+}
@@ -0,0 +1,123 @@
+use yape::polygon::FillRule;
+use yape::{Pixel, PixelMode, Vi2d, YapeEngine, YapeEngineApi};
+
+const DIM: Pixel = Pixel { red: 10, green: 10, blue: 10, alpha: 255 };
+
+// Fills additively so overdraw shows up as a brighter pixel
+fn fill(points: &[Vi2d], rule: FillRule) -> YapeEngine {
+    let mut engine = YapeEngine::construct_headless(32, 32);
+    engine.clear(&yape::BLACK);
+    engine.set_pixel_mode(PixelMode::Additive);
+    engine.fill_polygon(points, rule, &DIM);
+    assert!(engine.get_draw_target().data.iter().all(|p| p.red == 0 || p.red == 10), "overdraw");
+    engine
+}
+
+fn filled(engine: &YapeEngine, x: u32, y: u32) -> bool {
+    engine.get_draw_target().get_pixel(x, y).red != 0
+}
+
+fn count(engine: &YapeEngine) -> usize {
+    engine.get_draw_target().data.iter().filter(|p| p.red != 0).count()
+}
+
+// Five pointed star drawn in one stroke, its outline crosses itself around a pentagon
+fn star() -> Vec<Vi2d> {
+    (0..5)
+        .map(|i| {
+            let angle = -std::f32::consts::FRAC_PI_2 + i as f32 * 4.0 * std::f32::consts::PI / 5.0;
+            Vi2d::new((16.0 + 15.0 * angle.cos()).round() as i32, (16.0 + 15.0 * angle.sin()).round() as i32)
+        })
+        .collect()
+}
+
+#[test]
+fn star_center_is_a_hole_only_with_even_odd() {
+    let even_odd = fill(&star(), FillRule::EvenOdd);
+    let non_zero = fill(&star(), FillRule::NonZero);
+    assert!(!filled(&even_odd, 16, 16));
+    assert!(filled(&non_zero, 16, 16));
+
+    // The points are filled either way
+    for engine in [&even_odd, &non_zero].iter() {
+        assert!(filled(engine, 16, 4));
+        assert!(filled(engine, 5, 12));
+        assert!(filled(engine, 26, 12));
+    }
+    assert!(count(&non_zero) > count(&even_odd));
+    // Every pixel even-odd fills, non-zero fills too
+    let a = even_odd.get_draw_target();
+    let b = non_zero.get_draw_target();
+    assert!(a.data.iter().zip(b.data.iter()).all(|(a, b)| a.red == 0 || b.red != 0));
+}
+
+#[test]
+fn rectangle_polygon_matches_fill_rect() {
+    let points = [Vi2d::new(3, 4), Vi2d::new(10, 4), Vi2d::new(10, 9), Vi2d::new(3, 9)];
+    let polygon = fill(&points, FillRule::NonZero);
+    let mut rect = YapeEngine::construct_headless(32, 32);
+    rect.clear(&yape::BLACK);
+    rect.fill_rect(3, 4, 7, 5, &DIM);
+    assert_eq!(polygon.get_draw_target().data, rect.get_draw_target().data);
+    assert_eq!(count(&polygon), 35);
+}
+
+#[test]
+fn winding_direction_does_not_matter() {
+    let mut points = star();
+    let forward = fill(&points, FillRule::NonZero);
+    points.reverse();
+    let backward = fill(&points, FillRule::NonZero);
+    assert_eq!(forward.get_draw_target().data, backward.get_draw_target().data);
+}
+
+#[test]
+fn concave_notch_stays_empty() {
+    // A U shape opening upwards
+    let points = [
+        Vi2d::new(2, 2),
+        Vi2d::new(6, 2),
+        Vi2d::new(6, 10),
+        Vi2d::new(10, 10),
+        Vi2d::new(10, 2),
+        Vi2d::new(14, 2),
+        Vi2d::new(14, 14),
+        Vi2d::new(2, 14),
+    ];
+    for rule in [FillRule::EvenOdd, FillRule::NonZero].iter() {
+        let engine = fill(&points, *rule);
+        assert!(filled(&engine, 3, 5));
+        assert!(!filled(&engine, 8, 5));
+        assert!(filled(&engine, 8, 12));
+        assert_eq!(count(&engine), 12 * 12 - 4 * 8);
+    }
+}
+
+#[test]
+fn degenerate_polygons_fill_nothing() {
+    assert_eq!(count(&fill(&[], FillRule::NonZero)), 0);
+    assert_eq!(count(&fill(&[Vi2d::new(1, 1), Vi2d::new(8, 8)], FillRule::NonZero)), 0);
+    assert_eq!(count(&fill(&[Vi2d::new(1, 1), Vi2d::new(8, 1), Vi2d::new(20, 1)], FillRule::NonZero)), 0);
+}
+
+#[test]
+fn huge_polygons_only_scan_visible_rows() {
+    // Billions of rows tall, only the 32 on screen may be walked
+    let (top, bottom) = (-2_000_000_000, 2_000_000_000);
+    let tall = [Vi2d::new(0, top), Vi2d::new(32, top), Vi2d::new(32, bottom), Vi2d::new(0, bottom)];
+    assert_eq!(count(&fill(&tall, FillRule::NonZero)), 32 * 32);
+    let below = [Vi2d::new(0, 40), Vi2d::new(31, 40), Vi2d::new(31, 2_000_000_000)];
+    assert_eq!(count(&fill(&below, FillRule::EvenOdd)), 0);
+}
+
+#[test]
+fn outline_is_closed() {
+    let mut engine = YapeEngine::construct_headless(16, 16);
+    engine.draw_polygon(&[Vi2d::new(1, 1), Vi2d::new(8, 1), Vi2d::new(8, 6)], &yape::WHITE);
+    let target = engine.get_draw_target();
+    assert_eq!(*target.get_pixel(4, 1), yape::WHITE);
+    assert_eq!(*target.get_pixel(8, 3), yape::WHITE);
+    // Closing edge from the last point back to the first
+    assert_eq!(*target.get_pixel(1, 1), yape::WHITE);
+    assert_eq!(*target.get_pixel(5, 4), yape::WHITE);
+}
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use yape::camera::Camera2D;
+use yape::spatial::{QuadTree, SpatialHash};
+use yape::{Rectf, Vf2d, YapeEngine, YapeEngineApi};
+
+fn sorted(mut ids: Vec<u32>) -> Vec<u32> {
+    ids.sort_unstable();
+    ids
+}
+
+fn touches(a: &Rectf, b: &Rectf) -> bool {
+    a.x <= b.right() && b.x <= a.right() && a.y <= b.bottom() && b.y <= a.bottom()
+}
+
+fn distance2(rect: &Rectf, point: Vf2d) -> f32 {
+    (point - point.clamp(rect.pos(), Vf2d::new(rect.right(), rect.bottom()))).length2()
+}
+
+// Mixed sizes spread around the origin, some outside of the quadtree bounds
+fn entities() -> HashMap<u32, Rectf> {
+    (0..300u32)
+        .map(|i| {
+            let x = (i * 37 % 200) as f32 - 100.0 + (i % 3) as f32 * 0.25;
+            let y = (i * 91 % 180) as f32 - 90.0;
+            (i, Rectf::new(x, y, (1 + i * 7 % 13) as f32, (1 + i * 5 % 11) as f32))
+        })
+        .collect()
+}
+
+fn moved(i: u32, rect: &Rectf) -> Rectf {
+    Rectf::new(rect.x + (i % 17) as f32 - 8.0, rect.y + (i % 23) as f32 * 1.5 - 16.0, rect.w, rect.h)
+}
+
+fn points() -> Vec<Vf2d> {
+    let mut points: Vec<Vf2d> = (-4..4)
+        .flat_map(|y| (-4..4).map(move |x| Vf2d::new(x as f32 * 30.0 + 3.5, y as f32 * 30.0 - 1.25)))
+        .collect();
+    points.push(Vf2d::new(1000.0, 1000.0));
+    points.push(Vf2d::new(-500.0, 20.0));
+    points
+}
+
+#[test]
+fn hash_inserts_moves_and_removes() {
+    let mut hash = SpatialHash::new(10.0);
+    hash.insert(1, Rectf::new(2.0, 2.0, 4.0, 4.0));
+    hash.insert(2, Rectf::new(8.0, 8.0, 5.0, 5.0));
+    hash.insert(3, Rectf::new(30.0, 0.0, 2.0, 2.0));
+    assert_eq!(hash.len(), 3);
+    assert_eq!(sorted(hash.query_rect(&Rectf::new(0.0, 0.0, 10.0, 10.0))), vec![1, 2]);
+    // Entities in several cells are reported once
+    assert_eq!(sorted(hash.query_rect(&Rectf::new(5.0, 5.0, 10.0, 10.0))), vec![1, 2]);
+    // Touching edges and empty areas match
+    assert_eq!(hash.query_rect(&Rectf::new(13.0, 13.0, 1.0, 1.0)), vec![2]);
+    assert_eq!(hash.query_rect(&Rectf::new(3.0, 3.0, 0.0, 0.0)), vec![1]);
+    assert!(hash.query_rect(&Rectf::new(6.5, 0.0, 1.0, 1.0)).is_empty());
+
+    assert!(hash.update(2, Rectf::new(31.0, 1.0, 1.0, 1.0)));
+    assert_eq!(sorted(hash.query_rect(&Rectf::new(30.0, 0.0, 2.0, 2.0))), vec![2, 3]);
+    assert!(hash.query_rect(&Rectf::new(8.0, 8.0, 5.0, 5.0)).is_empty());
+    // Moving within the same cells still updates the bounds
+    assert!(hash.update(2, Rectf::new(31.5, 1.0, 1.0, 1.0)));
+    assert_eq!(hash.bounds(2), Some(Rectf::new(31.5, 1.0, 1.0, 1.0)));
+    assert!(!hash.update(9, Rectf::new(0.0, 0.0, 1.0, 1.0)));
+
+    hash.insert(4, Rectf::new(-15.0, -3.0, 2.0, 2.0));
+    assert_eq!(hash.query_rect(&Rectf::new(-14.0, -2.0, 0.0, 0.0)), vec![4]);
+    // Areas spanning billions of cells check the entities instead of walking the cells
+    let everything = Rectf::new(-1.0e12, -1.0e12, 2.0e12, 2.0e12);
+    assert_eq!(sorted(hash.query_rect(&everything)), vec![1, 2, 3, 4]);
+    assert_eq!(sorted(hash.query_radius(Vf2d::new(0.0, 0.0), 1.0e12)), vec![1, 2, 3, 4]);
+    // So far out every entity is at the same f32 distance, any of them will do
+    assert!(hash.nearest(Vf2d::new(-1.0e12, 0.0)).is_some());
+
+    assert!(hash.remove(3));
+    assert!(!hash.remove(3));
+    hash.insert(1, Rectf::new(50.0, 50.0, 1.0, 1.0));
+    assert_eq!(hash.len(), 3);
+    assert!(hash.query_rect(&Rectf::new(0.0, 0.0, 10.0, 10.0)).is_empty());
+
+    hash.clear();
+    assert!(hash.is_empty() && hash.bounds(1).is_none());
+    assert!(hash.query_rect(&Rectf::new(-100.0, -100.0, 200.0, 200.0)).is_empty());
+    assert_eq!(hash.nearest(Vf2d::new(0.0, 0.0)), None);
+}
+
+#[test]
+fn radius_queries_measure_to_the_closest_point() {
+    let rects = [
+        (1, Rectf::new(10.0, 0.0, 2.0, 2.0)),
+        (2, Rectf::new(0.0, 10.0, 2.0, 2.0)),
+        // Inside the query square but not the circle
+        (3, Rectf::new(8.0, 8.0, 2.0, 2.0)),
+        (4, Rectf::new(-1.0, -1.0, 2.0, 2.0)),
+    ];
+    let mut hash = SpatialHash::new(4.0);
+    let mut tree = QuadTree::new(Rectf::new(-16.0, -16.0, 32.0, 32.0), 1, 4);
+    for (id, rect) in rects.iter() {
+        hash.insert(*id, *rect);
+        tree.insert(*id, *rect);
+    }
+    assert_eq!(sorted(hash.query_radius(Vf2d::new(0.0, 0.0), 10.0)), vec![1, 2, 4]);
+    assert_eq!(sorted(tree.query_radius(Vf2d::new(0.0, 0.0), 10.0)), vec![1, 2, 4]);
+    assert_eq!(hash.nearest(Vf2d::new(9.0, 9.5)), Some(3));
+    assert_eq!(tree.nearest(Vf2d::new(9.0, 9.5)), Some(3));
+}
+
+#[test]
+fn queries_match_brute_force() {
+    let mut model = entities();
+    let mut hash = SpatialHash::new(8.0);
+    let mut tree = QuadTree::new(Rectf::new(-64.0, -64.0, 128.0, 128.0), 4, 6);
+    for (id, rect) in model.iter() {
+        hash.insert(*id, *rect);
+        tree.insert(*id, *rect);
+    }
+    for id in 0..300 {
+        if id % 5 == 0 {
+            model.remove(&id);
+            assert!(hash.remove(id) && tree.remove(id));
+        } else if id % 3 == 0 {
+            let rect = moved(id, &model[&id]);
+            model.insert(id, rect);
+            assert!(hash.update(id, rect) && tree.update(id, rect));
+        }
+    }
+    assert_eq!((hash.len(), tree.len()), (model.len(), model.len()));
+    for (id, rect) in model.iter() {
+        assert_eq!((hash.bounds(*id), tree.get(*id)), (Some(*rect), Some(*rect)));
+    }
+
+    for point in points() {
+        let area = Rectf::new(point.x, point.y, 25.0, 17.0);
+        let expected = sorted(model.iter().filter(|(_, r)| touches(r, &area)).map(|(id, _)| *id).collect());
+        assert_eq!(sorted(hash.query_rect(&area)), expected, "{:?}", area);
+        assert_eq!(sorted(tree.query_rect(&area)), expected, "{:?}", area);
+
+        let expected = sorted(model.iter().filter(|(_, r)| distance2(r, point) <= 144.0).map(|(id, _)| *id).collect());
+        assert_eq!(sorted(hash.query_radius(point, 12.0)), expected, "{:?}", point);
+        assert_eq!(sorted(tree.query_radius(point, 12.0)), expected, "{:?}", point);
+
+        // Compared by distance since several entities can be equally close
+        let best = model.values().map(|r| distance2(r, point)).fold(f32::INFINITY, f32::min);
+        let from_hash = hash.nearest(point).unwrap();
+        let from_tree = tree.nearest(point).unwrap();
+        assert_eq!(distance2(&model[&from_hash], point), best, "{:?}", point);
+        assert_eq!(distance2(&model[&from_tree], point), best, "{:?}", point);
+    }
+}
+
+#[test]
+fn quadtree_keeps_entities_outside_its_bounds() {
+    let mut tree = QuadTree::new(Rectf::new(0.0, 0.0, 16.0, 16.0), 1, 4);
+    tree.insert(1, Rectf::new(1.0, 1.0, 1.0, 1.0));
+    tree.insert(2, Rectf::new(10.0, 10.0, 1.0, 1.0));
+    tree.insert(3, Rectf::new(40.0, 40.0, 2.0, 2.0));
+    tree.insert(4, Rectf::new(-5.0, 2.0, 10.0, 1.0));
+    assert_eq!(tree.query_rect(&Rectf::new(39.0, 39.0, 1.0, 1.0)), vec![3]);
+    assert_eq!(tree.query_rect(&Rectf::new(-5.0, 0.0, 1.0, 4.0)), vec![4]);
+    assert_eq!(tree.nearest(Vf2d::new(50.0, 50.0)), Some(3));
+    assert!(tree.update(3, Rectf::new(12.0, 12.0, 1.0, 1.0)));
+    assert_eq!(sorted(tree.query_rect(&Rectf::new(10.0, 10.0, 6.0, 6.0))), vec![2, 3]);
+    assert!(!tree.update(9, Rectf::new(0.0, 0.0, 1.0, 1.0)));
+
+    tree.clear();
+    assert!(tree.is_empty() && tree.get(1).is_none());
+    assert_eq!(tree.bounds(), Rectf::new(0.0, 0.0, 16.0, 16.0));
+    assert_eq!(tree.nearest(Vf2d::new(0.0, 0.0)), None);
+}
+
+#[test]
+fn quadtree_splits_and_merges_back() {
+    let mut engine = YapeEngine::construct_headless(20, 20);
+    // The vertical split line crosses (8, 4)
+    let mut split = |tree: &QuadTree<u32>| {
+        engine.clear(&yape::BLACK);
+        tree.debug_draw(&mut engine, &Camera2D::default(), &yape::WHITE);
+        assert_eq!(*engine.get_draw_target().get_pixel(0, 4), yape::WHITE);
+        *engine.get_draw_target().get_pixel(8, 4) == yape::WHITE
+    };
+
+    let mut tree = QuadTree::new(Rectf::new(0.0, 0.0, 16.0, 16.0), 2, 4);
+    tree.insert(1, Rectf::new(1.0, 1.0, 1.0, 1.0));
+    tree.insert(2, Rectf::new(10.0, 1.0, 1.0, 1.0));
+    assert!(!split(&tree));
+    tree.insert(3, Rectf::new(1.0, 10.0, 1.0, 1.0));
+    assert!(split(&tree));
+    assert!(tree.remove(3));
+    assert!(!split(&tree));
+
+    // An entity straddling the children keeps the root split until it leaves
+    tree.insert(3, Rectf::new(1.0, 10.0, 1.0, 1.0));
+    tree.insert(4, Rectf::new(6.0, 6.0, 4.0, 4.0));
+    assert!(tree.remove(3));
+    assert!(split(&tree));
+    assert!(tree.remove(4));
+    assert!(!split(&tree));
+    assert_eq!(sorted(tree.query_rect(&tree.bounds())), vec![1, 2]);
+
+    // Depth is capped
+    let mut shallow = QuadTree::new(Rectf::new(0.0, 0.0, 16.0, 16.0), 1, 0);
+    for id in 0..4 {
+        shallow.insert(id, Rectf::new(1.0 + id as f32 * 4.0, 1.0, 1.0, 1.0));
+    }
+    assert!(!split(&shallow));
+}
+
+#[test]
+fn hash_draws_occupied_cells() {
+    let mut hash = SpatialHash::new(4.0);
+    hash.insert(1, Rectf::new(5.0, 1.0, 2.0, 2.0));
+    let mut engine = YapeEngine::construct_headless(16, 16);
+    engine.clear(&yape::BLACK);
+    hash.debug_draw(&mut engine, &Camera2D::default(), &yape::WHITE);
+    let target = engine.get_draw_target();
+    for &(x, y) in [(4, 2), (8, 2), (6, 0), (6, 4)].iter() {
+        assert_eq!(*target.get_pixel(x, y), yape::WHITE, "{} {}", x, y);
+    }
+    for &(x, y) in [(6, 2), (2, 2), (6, 6)].iter() {
+        assert_eq!(*target.get_pixel(x, y), yape::BLACK, "{} {}", x, y);
+    }
+}
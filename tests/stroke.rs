@@ -0,0 +1,97 @@
+use yape::stroke::LineStyle;
+use yape::{Vi2d, YapeEngine, YapeEngineApi};
+
+fn draw(f: impl Fn(&mut YapeEngine)) -> YapeEngine {
+    let mut engine = YapeEngine::construct_headless(48, 16);
+    f(&mut engine);
+    engine
+}
+
+// One character per pixel of row y from x = 0, '#' where something was drawn
+fn row(engine: &YapeEngine, y: u32, len: u32) -> String {
+    (0..len).map(|x| if engine.get_draw_target().get_pixel(x, y).alpha != 0 { '#' } else { '.' }).collect()
+}
+
+fn lit_pixels(engine: &YapeEngine) -> Vec<Vi2d> {
+    let target = engine.get_draw_target();
+    (0..target.height as i32)
+        .flat_map(|y| (0..target.width as i32).map(move |x| Vi2d::new(x, y)))
+        .filter(|p| target.get_pixel(p.x as u32, p.y as u32).alpha != 0)
+        .collect()
+}
+
+#[test]
+fn patterns_are_read_from_the_top_bit() {
+    let e = draw(|e| e.draw_line_pattern(Vi2d::new(0, 0), Vi2d::new(15, 0), 0xF0F0_F0F0, &yape::WHITE));
+    assert_eq!(row(&e, 0, 16), "####....####....");
+    let e = draw(|e| e.draw_line_pattern(Vi2d::new(0, 0), Vi2d::new(9, 0), 0xC000_0001, &yape::WHITE));
+    assert_eq!(row(&e, 0, 10), "##........");
+}
+
+#[test]
+fn patterns_repeat_every_32_pixels() {
+    let e = draw(|e| e.draw_line_pattern(Vi2d::new(0, 0), Vi2d::new(40, 0), 0x8000_0001, &yape::WHITE));
+    let expected: String = (0..41).map(|x| if x % 32 == 0 || x % 32 == 31 { '#' } else { '.' }).collect();
+    assert_eq!(row(&e, 0, 41), expected);
+}
+
+#[test]
+fn dashes_start_at_the_first_point() {
+    let style = LineStyle::Dash { dash: 3, gap: 2 };
+    let e = draw(|e| e.draw_line_styled(Vi2d::new(0, 0), Vi2d::new(11, 0), style, &yape::WHITE));
+    assert_eq!(row(&e, 0, 12), "###..###..##");
+    let e = draw(|e| e.draw_line_styled(Vi2d::new(11, 0), Vi2d::new(0, 0), style, &yape::WHITE));
+    assert_eq!(row(&e, 0, 12), "##..###..###");
+
+    // Steep lines step once per row
+    let e = draw(|e| e.draw_line_styled(Vi2d::new(2, 0), Vi2d::new(2, 9), style, &yape::WHITE));
+    let column: String = (0..10).map(|y| if e.get_draw_target().get_pixel(2, y).alpha != 0 { '#' } else { '.' }).collect();
+    assert_eq!(column, "###..###..");
+}
+
+#[test]
+fn degenerate_styles() {
+    let solid = draw(|e| e.draw_line(0, 0, 20, 7, &yape::WHITE));
+    let styled = draw(|e| e.draw_line_styled(Vi2d::new(0, 0), Vi2d::new(20, 7), LineStyle::Solid, &yape::WHITE));
+    assert_eq!(lit_pixels(&styled), lit_pixels(&solid));
+    let no_gap = draw(|e| e.draw_line_styled(Vi2d::new(0, 0), Vi2d::new(20, 7), LineStyle::Dash { dash: 0, gap: 0 }, &yape::WHITE));
+    assert_eq!(lit_pixels(&no_gap), lit_pixels(&solid));
+    let all_gap = draw(|e| e.draw_line_styled(Vi2d::new(0, 0), Vi2d::new(20, 7), LineStyle::Dash { dash: 0, gap: 4 }, &yape::WHITE));
+    assert!(lit_pixels(&all_gap).is_empty());
+    let empty = draw(|e| e.draw_line_pattern(Vi2d::new(0, 0), Vi2d::new(20, 7), 0, &yape::WHITE));
+    assert!(lit_pixels(&empty).is_empty());
+}
+
+#[test]
+fn polylines_carry_the_dash_around_corners() {
+    let points = [Vi2d::new(0, 0), Vi2d::new(3, 0), Vi2d::new(3, 3)];
+    let e = draw(|e| e.draw_polyline_styled(&points, LineStyle::Dash { dash: 2, gap: 1 }, &yape::WHITE));
+    // The shared corner is plotted once, so the dash keeps counting from the first segment
+    assert_eq!(lit_pixels(&e), vec![Vi2d::new(0, 0), Vi2d::new(1, 0), Vi2d::new(3, 0), Vi2d::new(3, 1), Vi2d::new(3, 3)]);
+}
+
+#[test]
+fn rects_are_walked_clockwise_from_the_top_left() {
+    let e = draw(|e| e.draw_rect_styled(Vi2d::new(0, 0), Vi2d::new(3, 2), LineStyle::Pattern(0xAAAA_AAAA), &yape::WHITE));
+    assert_eq!(row(&e, 0, 4), "#.#.");
+    assert_eq!(row(&e, 1, 4), "...#");
+    assert_eq!(row(&e, 2, 4), "#.#.");
+
+    let solid = draw(|e| e.draw_rect(0, 0, 3, 2, &yape::WHITE));
+    let styled = draw(|e| e.draw_rect_styled(Vi2d::new(0, 0), Vi2d::new(3, 2), LineStyle::Solid, &yape::WHITE));
+    assert_eq!(lit_pixels(&styled), lit_pixels(&solid));
+}
+
+#[test]
+fn circles_alternate_around_the_outline() {
+    let center = Vi2d::new(8, 8);
+    let solid = lit_pixels(&draw(|e| e.draw_circle_v(center, 6, &yape::WHITE)));
+    let styled = lit_pixels(&draw(|e| e.draw_circle_styled(center, 6, LineStyle::Solid, &yape::WHITE)));
+    assert_eq!(styled, solid);
+
+    let half = lit_pixels(&draw(|e| e.draw_circle_styled(center, 6, LineStyle::Pattern(0xAAAA_AAAA), &yape::WHITE)));
+    assert_eq!(half.len(), solid.len().div_ceil(2));
+    assert!(half.iter().all(|p| solid.contains(p)));
+    // The first step is on the positive x axis
+    assert!(half.contains(&Vi2d::new(14, 8)));
+}
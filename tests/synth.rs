@@ -0,0 +1,69 @@
+use yape::synth::{note_frequency, Envelope, Synth, Voice, Waveform};
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+#[test]
+fn envelope_goes_through_attack_decay_sustain_and_release() {
+    let envelope = Envelope::new(0.1, 0.2, 0.5, 0.4);
+    assert_eq!(envelope.amplitude(0.5, 1.0, None), 0.0);
+    assert!(close(envelope.amplitude(1.05, 1.0, None), 0.5));
+    assert!(close(envelope.amplitude(1.1, 1.0, None), 1.0));
+    assert!(close(envelope.amplitude(1.2, 1.0, None), 0.75));
+    assert!(close(envelope.amplitude(1.3, 1.0, None), 0.5));
+    assert!(close(envelope.amplitude(9.0, 1.0, None), 0.5));
+
+    // Released while sustaining, fades linearly to zero
+    assert!(close(envelope.amplitude(2.2, 1.0, Some(2.0)), 0.25));
+    assert_eq!(envelope.amplitude(2.4, 1.0, Some(2.0)), 0.0);
+    assert_eq!(envelope.amplitude(3.0, 1.0, Some(2.0)), 0.0);
+
+    // Released mid attack, fades from where the attack got to
+    assert!(close(envelope.amplitude(1.05, 1.0, Some(1.05)), 0.5));
+    assert!(close(envelope.amplitude(1.25, 1.0, Some(1.05)), 0.25));
+}
+
+#[test]
+fn released_voices_are_retired_after_their_release() {
+    let mut synth = Synth::new(100);
+    let envelope = Envelope::new(0.0, 0.0, 1.0, 0.05);
+    let held = synth.play(Voice::new(Waveform::Square, 10.0).with_envelope(envelope));
+    synth.play(Voice::new(Waveform::Square, 10.0).with_envelope(envelope).with_duration(0.1));
+    assert_eq!(synth.active_voices(), 2);
+
+    for _ in 0..10 {
+        synth.next_sample();
+    }
+    assert_eq!(synth.active_voices(), 2);
+    for _ in 0..10 {
+        synth.next_sample();
+    }
+    assert_eq!(synth.active_voices(), 1);
+
+    synth.release(held);
+    for _ in 0..10 {
+        synth.next_sample();
+    }
+    assert_eq!(synth.active_voices(), 0);
+    assert_eq!(synth.next_sample(), 0.0);
+}
+
+#[test]
+fn voices_are_mixed_and_clamped() {
+    let mut synth = Synth::new(100);
+    let envelope = Envelope::new(0.0, 0.0, 1.0, 0.0);
+    synth.play(Voice::new(Waveform::Square, 1.0).with_envelope(envelope).with_volume(0.25));
+    assert!(close(synth.next_sample(), 0.25));
+    synth.play(Voice::new(Waveform::Square, 1.0).with_envelope(envelope));
+    synth.play(Voice::new(Waveform::Square, 1.0).with_envelope(envelope));
+    assert_eq!(synth.next_sample(), 1.0);
+}
+
+#[test]
+fn note_frequency_follows_equal_temperament() {
+    assert_eq!(note_frequency(69), 440.0);
+    assert!(close(note_frequency(81), 880.0));
+    assert!(close(note_frequency(57), 220.0));
+    assert!((note_frequency(60) - 261.6256).abs() < 1e-3);
+}
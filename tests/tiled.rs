@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use yape::tiled::{self, ObjectShape, PropertyValue, TiledMap, TiledObject};
+use yape::tilemap::{AnimationFrame, TileFlags};
+use yape::{Pixel, Recti, Sprite, Vf2d, YapeErrorKind};
+
+// Gids with Tiled's flip bits set
+const FLIPPED_2: u32 = 0x8000_0002;
+const FLIPPED_1: u32 = 0x4000_0001;
+
+// Fresh scratch directory per test holding the tileset images, tests run in parallel
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("yape_tiled_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    // Two 2x2 tiles, red then blue
+    let mut tiles = Sprite::new(4, 2);
+    for y in 0..2 {
+        for x in 0..4 {
+            tiles.set_pixel(x, y, if x < 2 { &yape::RED } else { &yape::BLUE });
+        }
+    }
+    tiles.save_png(dir.join("tiles.png")).unwrap();
+    let mut extra = Sprite::new(2, 2);
+    for y in 0..2 {
+        for x in 0..2 {
+            extra.set_pixel(x, y, &yape::GREEN);
+        }
+    }
+    extra.save_png(dir.join("extra.png")).unwrap();
+
+    fs::write(
+        dir.join("extra.tsx"),
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" name="extra" tilewidth="2" tileheight="2" tilecount="1" columns="1">
+ <image source="extra.png" width="2" height="2"/>
+ <tile id="0"><properties><property name="one_way" type="bool" value="true"/></properties></tile>
+</tileset>"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.join("extra.tsj"),
+        r#"{"name": "extra", "tilewidth": 2, "tileheight": 2, "tilecount": 1, "columns": 1,
+            "image": "extra.png", "imagewidth": 2, "imageheight": 2,
+            "tiles": [{"id": 0, "properties": [{"name": "one_way", "type": "bool", "value": true}]}]}"#,
+    )
+    .unwrap();
+    dir
+}
+
+fn gid_bytes(gids: &[u32]) -> Vec<u8> {
+    gids.iter().flat_map(|gid| gid.to_le_bytes().to_vec()).collect()
+}
+
+fn zlib_base64(gids: &[u32]) -> String {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&gid_bytes(gids)).unwrap();
+    base64::engine::general_purpose::STANDARD.encode(encoder.finish().unwrap())
+}
+
+fn gzip_base64(gids: &[u32]) -> String {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&gid_bytes(gids)).unwrap();
+    base64::engine::general_purpose::STANDARD.encode(encoder.finish().unwrap())
+}
+
+const DECO: [u32; 6] = [3, FLIPPED_2, 0, FLIPPED_1, 0, 0];
+
+const TMX: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="3" height="2" tilewidth="2" tileheight="2" infinite="0">
+ <properties>
+  <property name="gravity" type="float" value="9.5"/>
+  <property name="title" value="Level 1"/>
+ </properties>
+ <tileset firstgid="1" name="tiles" tilewidth="2" tileheight="2" tilecount="2" columns="2">
+  <image source="tiles.png" width="4" height="2"/>
+  <tile id="0">
+   <animation>
+    <frame tileid="0" duration="100"/>
+    <frame tileid="1" duration="200"/>
+   </animation>
+  </tile>
+  <tile id="1"><properties><property name="solid" type="bool" value="true"/></properties></tile>
+ </tileset>
+ <tileset firstgid="3" source="extra.tsj"/>
+ <layer id="1" name="ground" width="3" height="2">
+  <properties><property name="parallax" type="float" value="0.5"/></properties>
+  <data encoding="csv">
+1,2,0,
+0,2,1
+</data>
+ </layer>
+ <layer id="2" name="deco" width="3" height="2">
+  <data encoding="base64" compression="zlib">DECO</data>
+ </layer>
+ <group id="3" name="hidden group" visible="0">
+  <layer id="4" name="hidden" width="3" height="2">
+   <data><tile/><tile/><tile/><tile/><tile/><tile gid="3"/></data>
+  </layer>
+ </group>
+ <objectgroup id="5" name="spawns">
+  <object id="1" name="player" type="spawn" x="4" y="6.5" width="2" height="2">
+   <properties>
+    <property name="hp" type="int" value="3"/>
+    <property name="boss" type="bool" value="true"/>
+    <property name="tint" type="color" value="#80ff0000"/>
+    <property name="script" type="file" value="ai/player.lua"/>
+    <property name="target" type="object" value="2"/>
+    <property name="note">line one
+line two</property>
+    <property name="stats" type="class" propertytype="Stats">
+     <properties><property name="speed" type="float" value="1.5"/></properties>
+    </property>
+   </properties>
+  </object>
+  <object id="2" name="exit" class="door" x="10" y="0" rotation="90" visible="0"><ellipse/></object>
+  <object id="3" name="path" x="1" y="1"><polyline points="0,0 4,2 -1,3"/></object>
+  <object id="4" name="coin" gid="2147483650" x="2" y="4" width="2" height="2"/>
+  <object id="5" name="marker" x="3" y="3"><point/></object>
+ </objectgroup>
+</map>"##;
+
+const TMJ: &str = r##"{
+ "width": 3, "height": 2, "tilewidth": 2, "tileheight": 2, "orientation": "orthogonal", "infinite": false,
+ "properties": [
+  {"name": "gravity", "type": "float", "value": 9.5},
+  {"name": "title", "type": "string", "value": "Level 1"}
+ ],
+ "tilesets": [
+  {"firstgid": 1, "name": "tiles", "image": "tiles.png", "imagewidth": 4, "imageheight": 2,
+   "tilewidth": 2, "tileheight": 2, "columns": 2, "tilecount": 2,
+   "tiles": [
+    {"id": 0, "animation": [{"tileid": 0, "duration": 100}, {"tileid": 1, "duration": 200}]},
+    {"id": 1, "properties": [{"name": "solid", "type": "bool", "value": true}]}
+   ]},
+  {"firstgid": 3, "source": "extra.tsx"}
+ ],
+ "layers": [
+  {"type": "tilelayer", "name": "ground", "width": 3, "height": 2, "data": [1, 2, 0, 0, 2, 1],
+   "properties": [{"name": "parallax", "type": "float", "value": 0.5}]},
+  {"type": "tilelayer", "name": "deco", "width": 3, "height": 2, "encoding": "base64", "compression": "gzip", "data": "DECO"},
+  {"type": "group", "name": "hidden group", "visible": false, "layers": [
+   {"type": "tilelayer", "name": "hidden", "width": 3, "height": 2, "data": [0, 0, 0, 0, 0, 3]}
+  ]},
+  {"type": "objectgroup", "name": "spawns", "objects": [
+   {"id": 1, "name": "player", "type": "spawn", "x": 4, "y": 6.5, "width": 2, "height": 2, "properties": [
+    {"name": "hp", "type": "int", "value": 3},
+    {"name": "boss", "type": "bool", "value": true},
+    {"name": "tint", "type": "color", "value": "#80ff0000"},
+    {"name": "script", "type": "file", "value": "ai/player.lua"},
+    {"name": "target", "type": "object", "value": 2},
+    {"name": "note", "type": "string", "value": "line one\nline two"},
+    {"name": "stats", "type": "class", "propertytype": "Stats", "value": {"speed": 1.5}}
+   ]},
+   {"id": 2, "name": "exit", "class": "door", "x": 10, "y": 0, "rotation": 90, "visible": false, "ellipse": true},
+   {"id": 3, "name": "path", "x": 1, "y": 1, "polyline": [{"x": 0, "y": 0}, {"x": 4, "y": 2}, {"x": -1, "y": 3}]},
+   {"id": 4, "name": "coin", "gid": 2147483650, "x": 2, "y": 4, "width": 2, "height": 2},
+   {"id": 5, "name": "marker", "x": 3, "y": 3, "point": true}
+  ]}
+ ]
+}"##;
+
+// Both formats describe the same map
+fn check(loaded: &TiledMap) {
+    let map = &loaded.map;
+    assert_eq!((map.width(), map.height()), (3, 2));
+    let names: Vec<&str> = map.layers.iter().map(|l| l.name.as_str()).collect();
+    assert_eq!(names, vec!["ground", "deco", "hidden"]);
+    let visible: Vec<bool> = map.layers.iter().map(|l| l.visible).collect();
+    assert_eq!(visible, vec![true, true, false]);
+
+    let tiles = |index: usize| -> Vec<u32> { (0..6).map(|i| map.layers[index].get(i % 3, i / 3)).collect() };
+    assert_eq!(tiles(0), vec![1, 2, 0, 0, 2, 1]);
+    // Flip bits are dropped
+    assert_eq!(tiles(1), vec![3, 2, 0, 1, 0, 0]);
+    assert_eq!(tiles(2), vec![0, 0, 0, 0, 0, 3]);
+
+    // Both tilesets land in one sheet, indexed by gid
+    let set = &map.tileset;
+    for (gid, color) in [(1, yape::RED), (2, yape::BLUE), (3, yape::GREEN)].iter() {
+        let source = set.source_rect(*gid).unwrap();
+        assert_eq!(source.w, 2);
+        assert_eq!(set.sheet.get_pixel(source.x as u32 + 1, source.y as u32 + 1), color);
+    }
+    assert_eq!(set.source_rect(3), Some(Recti::new(0, 2, 2, 2)));
+    assert_eq!(set.flags(1), TileFlags::NONE);
+    assert_eq!(set.flags(2), TileFlags::SOLID);
+    assert_eq!(set.flags(3), TileFlags::ONE_WAY);
+    assert!(map.is_solid(1, 0));
+    assert!(!map.is_solid(0, 0));
+    let frames = [AnimationFrame { tile: 1, duration: 0.1 }, AnimationFrame { tile: 2, duration: 0.2 }];
+    assert_eq!(set.animation(1), Some(&frames[..]));
+    assert_eq!(set.animation(2), None);
+
+    assert_eq!(loaded.properties.get("gravity"), Some(&PropertyValue::Float(9.5)));
+    assert_eq!(loaded.properties.get("title"), Some(&PropertyValue::String("Level 1".to_string())));
+    assert_eq!(loaded.layer_properties["ground"].get("parallax"), Some(&PropertyValue::Float(0.5)));
+    assert!(loaded.layer_properties["deco"].is_empty());
+
+    let spawns = loaded.object_layer("spawns").unwrap();
+    assert!(spawns.visible);
+    assert_eq!(spawns.objects.len(), 5);
+    assert!(loaded.object_layer("ground").is_none());
+
+    let mut stats = HashMap::new();
+    stats.insert("speed".to_string(), PropertyValue::Float(1.5));
+    let mut properties = HashMap::new();
+    properties.insert("hp".to_string(), PropertyValue::Int(3));
+    properties.insert("boss".to_string(), PropertyValue::Bool(true));
+    properties.insert("tint".to_string(), PropertyValue::Color(Pixel::rgba(255, 0, 0, 0x80)));
+    properties.insert("script".to_string(), PropertyValue::File("ai/player.lua".to_string()));
+    properties.insert("target".to_string(), PropertyValue::Object(2));
+    properties.insert("note".to_string(), PropertyValue::String("line one\nline two".to_string()));
+    properties.insert("stats".to_string(), PropertyValue::Class(stats));
+    let player = TiledObject {
+        id: 1,
+        name: "player".to_string(),
+        class: "spawn".to_string(),
+        position: Vf2d::new(4.0, 6.5),
+        size: Vf2d::new(2.0, 2.0),
+        rotation: 0.0,
+        visible: true,
+        gid: None,
+        shape: ObjectShape::Rect,
+        properties,
+    };
+    assert_eq!(loaded.find_object("player"), Some(&player));
+
+    let exit = loaded.find_object("exit").unwrap();
+    assert_eq!((exit.class.as_str(), exit.rotation, exit.visible), ("door", 90.0, false));
+    assert_eq!(exit.shape, ObjectShape::Ellipse);
+    let path = loaded.find_object("path").unwrap();
+    let points = vec![Vf2d::new(0.0, 0.0), Vf2d::new(4.0, 2.0), Vf2d::new(-1.0, 3.0)];
+    assert_eq!(path.shape, ObjectShape::Polyline(points));
+    assert_eq!(loaded.find_object("coin").unwrap().gid, Some(2));
+    assert_eq!(loaded.find_object("marker").unwrap().shape, ObjectShape::Point);
+    assert!(loaded.find_object("nobody").is_none());
+}
+
+#[test]
+fn tmx_maps_load_with_every_layer_encoding() {
+    let dir = scratch("tmx");
+    let path = dir.join("level.tmx");
+    fs::write(&path, TMX.replace("DECO", &zlib_base64(&DECO))).unwrap();
+    check(&tiled::load_tmx(&path).unwrap());
+    check(&tiled::load(path).unwrap());
+}
+
+#[test]
+fn tmj_maps_load_with_every_layer_encoding() {
+    let dir = scratch("tmj");
+    let text = TMJ.replace("DECO", &gzip_base64(&DECO));
+    check(&tiled::parse_tmj(&text, &dir).unwrap());
+
+    let path = dir.join("level.tmj");
+    fs::write(&path, text).unwrap();
+    check(&tiled::load(path).unwrap());
+}
+
+#[test]
+fn uncompressed_base64_layers_decode() {
+    let dir = scratch("base64");
+    let plain = base64::engine::general_purpose::STANDARD.encode(gid_bytes(&DECO));
+    let text = TMX.replace(r#"compression="zlib">DECO"#, &format!(">{}", plain));
+    check(&tiled::parse_tmx(&text, &dir).unwrap());
+}
+
+fn decode_error(result: Result<TiledMap, yape::YapeError>) -> yape::YapeError {
+    match result {
+        Ok(_) => panic!("expected an error"),
+        Err(error) => {
+            assert_eq!(error.kind, YapeErrorKind::Decode, "{}", error);
+            error
+        }
+    }
+}
+
+#[test]
+fn unsupported_and_broken_maps_are_decode_errors() {
+    let dir = scratch("errors");
+    let deco = zlib_base64(&DECO);
+    let tmx = TMX.replace("DECO", &deco);
+    let tmj = TMJ.replace("DECO", &gzip_base64(&DECO));
+    let parse_tmx = |text: String| tiled::parse_tmx(&text, &dir);
+    let parse_tmj = |text: String| tiled::parse_tmj(&text, &dir);
+
+    decode_error(parse_tmx(tmx.replace(r#"orientation="orthogonal""#, r#"orientation="isometric""#)));
+    decode_error(parse_tmx(tmx.replace(r#"infinite="0""#, r#"infinite="1""#)));
+    decode_error(parse_tmx(tmx.replace("0,2,1", "0,2")));
+    decode_error(parse_tmx(tmx.replace("0,2,1", "0,x,1")));
+    decode_error(parse_tmx(tmx.replace(r#"compression="zlib""#, r#"compression="zstd""#)));
+    decode_error(parse_tmx(tmx.replace(r#"width="3" height="2" tilewidth="2""#, r#"height="2" tilewidth="2""#)));
+    decode_error(parse_tmj(tmj.replace(r#""infinite": false"#, r#""infinite": true"#)));
+    decode_error(parse_tmj(tmj.replace("[1, 2, 0, 0, 2, 1]", "[1, 2, 0, 0, 2]")));
+    decode_error(parse_tmj(tmj.replace(r#""tileheight": 2, "orientation""#, r#""orientation""#)));
+
+    // Gids and sizes that would underflow or overflow
+    decode_error(parse_tmx(tmx.replace(r#"firstgid="1""#, r#"firstgid="0""#)));
+    decode_error(parse_tmx(tmx.replace(r#"firstgid="1""#, r#"firstgid="4294967295""#)));
+    decode_error(parse_tmj(tmj.replace(r#""firstgid": 1,"#, r#""firstgid": 0,"#)));
+    decode_error(parse_tmx(tmx.replace(r#"width="3" height="2" tilewidth="2""#, r#"width="65536" height="65536" tilewidth="2""#)));
+    decode_error(parse_tmj(tmj.replace(r#""tilecount": 2"#, r#""tilecount": 4294967295"#)));
+    decode_error(parse_tmj(tmj.replace(r#""firstgid": 1,"#, r#""firstgid": 4294967297,"#)));
+    decode_error(parse_tmj(tmj.replace("[1, 2, 0, 0, 2, 1]", "[1, 2, 0, 0, 2, 4294967297]")));
+    decode_error(parse_tmj(tmj.replace(r#""gid": 2147483650"#, r#""gid": 6442450946"#)));
+
+    // Zero tile sizes on the map or a tileset
+    decode_error(parse_tmx(tmx.replace(r#"width="3" height="2" tilewidth="2""#, r#"width="3" height="2" tilewidth="0""#)));
+    decode_error(parse_tmx(tmx.replace(r#"name="tiles" tilewidth="2" tileheight="2""#, r#"name="tiles" tilewidth="2" tileheight="0""#)));
+    decode_error(parse_tmj(tmj.replace(r#""tilewidth": 2, "tileheight": 2, "orientation""#, r#""tilewidth": 0, "tileheight": 2, "orientation""#)));
+    decode_error(parse_tmj(tmj.replace(r#""tilewidth": 2, "tileheight": 2, "columns""#, r#""tilewidth": 2, "tileheight": 0, "columns""#)));
+
+    // Parser errors are kept as the source
+    let error = decode_error(parse_tmx(tmx.replace("</map>", "")));
+    assert!(std::error::Error::source(&error).is_some());
+    let error = decode_error(parse_tmj(tmj.replace("\"width\": 3,", "\"width\": 3")));
+    assert!(std::error::Error::source(&error).is_some());
+    let error = decode_error(parse_tmx(tmx.replace(&deco, "not base64!")));
+    assert!(std::error::Error::source(&error).is_some());
+
+    decode_error(tiled::load(dir.join("level.txt")));
+    assert_eq!(tiled::load_tmx(dir.join("missing.tmx")).err().unwrap().kind, YapeErrorKind::Io);
+    assert!(tiled::parse_tmx(&tmx.replace("tiles.png", "missing.png"), &dir).is_err());
+    assert!(tiled::parse_tmx(&tmx, Path::new("/nonexistent")).is_err());
+}
@@ -0,0 +1,247 @@
+use yape::tilemap::{AnimationFrame, TileFlags, TileId, TileLayer, TileMap, TileSet, EMPTY_TILE};
+use yape::{Pixel, Recti, Sprite, Vf2d, Vi2d, YapeEngine, YapeEngineApi};
+
+// Texel (x, y) of tile n, different for every texel so cut tiles can be told apart
+fn texel(tile: TileId, x: i32, y: i32) -> Pixel {
+    Pixel::rgb(tile as u8 * 20, x as u8, y as u8)
+}
+
+// Sheet of 4x2 tiles of 2x2 pixels
+fn tileset() -> TileSet {
+    let mut sheet = Sprite::new(8, 4);
+    for tile in 1..=8u32 {
+        let (tx, ty) = (((tile - 1) % 4) * 2, ((tile - 1) / 4) * 2);
+        for y in 0..2 {
+            for x in 0..2 {
+                sheet.set_pixel(tx + x, ty + y, &texel(tile, x as i32, y as i32));
+            }
+        }
+    }
+    TileSet::new(sheet, 2, 2)
+}
+
+// Checks every screen pixel against the tile the map shows there
+fn assert_drawn(engine: &YapeEngine, layer: &TileLayer, scroll: Vi2d, viewport: &Recti) {
+    let target = engine.get_draw_target();
+    for y in 0..target.height as i32 {
+        for x in 0..target.width as i32 {
+            let map = Vi2d::new(x, y) - viewport.pos() + scroll;
+            let tile = layer.get(map.x.div_euclid(2), map.y.div_euclid(2));
+            let expected = if viewport.contains(Vi2d::new(x, y)) && tile != EMPTY_TILE {
+                texel(tile, map.x.rem_euclid(2), map.y.rem_euclid(2))
+            } else {
+                yape::BLACK
+            };
+            assert_eq!(*target.get_pixel(x as u32, y as u32), expected, "{} {}", x, y);
+        }
+    }
+}
+
+#[test]
+fn tileset_numbers_tiles_from_one() {
+    let set = tileset();
+    assert_eq!(set.tile_count(), 8);
+    assert_eq!(set.source_rect(EMPTY_TILE), None);
+    assert_eq!(set.source_rect(1), Some(Recti::new(0, 0, 2, 2)));
+    assert_eq!(set.source_rect(4), Some(Recti::new(6, 0, 2, 2)));
+    assert_eq!(set.source_rect(6), Some(Recti::new(2, 2, 2, 2)));
+    assert_eq!(set.source_rect(9), None);
+}
+
+#[test]
+fn flags_combine_across_layers() {
+    let mut set = tileset();
+    set.set_flags(3, TileFlags::SOLID);
+    set.set_flags(5, TileFlags::ONE_WAY);
+    // Empty and unknown tiles never carry flags
+    set.set_flags(EMPTY_TILE, TileFlags::SOLID);
+    set.set_flags(99, TileFlags::SOLID);
+    assert_eq!(set.flags(EMPTY_TILE), TileFlags::NONE);
+    assert_eq!(set.flags(99), TileFlags::NONE);
+    assert_eq!(set.flags(3), TileFlags::SOLID);
+
+    let mut map = TileMap::new(set, 4, 4);
+    let back = map.add_layer("back");
+    let front = map.add_layer("front");
+    assert_eq!((back, front), (0, 1));
+    map.layers[back].set(1, 1, 5);
+    map.layers[front].set(1, 1, 3);
+    map.layer_mut("front").unwrap().set(2, 1, 1);
+
+    let flags = map.flags_at(1, 1);
+    assert!(flags.contains(TileFlags::SOLID) && flags.contains(TileFlags::ONE_WAY));
+    assert!(map.is_solid(1, 1));
+    assert!(!map.is_solid(2, 1));
+    assert!(map.flags_at(2, 1).is_empty());
+    assert!(map.flags_at(-1, 1).is_empty());
+    assert_eq!(map.layer("front").unwrap().get(2, 1), 1);
+    assert!(map.layer("missing").is_none());
+}
+
+#[test]
+fn layers_read_across_chunks_and_outside_as_empty() {
+    let mut layer = TileLayer::new("ground", 40, 20);
+    layer.set(17, 18, 2);
+    layer.set(0, 0, 1);
+    layer.set(39, 19, 3);
+    // Out of range writes are dropped
+    layer.set(40, 0, 4);
+    layer.set(-1, 5, 4);
+    assert_eq!(layer.get(17, 18), 2);
+    assert_eq!(layer.get(0, 0), 1);
+    assert_eq!(layer.get(39, 19), 3);
+    assert_eq!(layer.get(16, 18), EMPTY_TILE);
+    assert_eq!(layer.get(40, 0), EMPTY_TILE);
+    assert_eq!(layer.get(-1, 5), EMPTY_TILE);
+
+    // Clearing the last tile of a chunk and writing it again
+    layer.set(17, 18, EMPTY_TILE);
+    assert_eq!(layer.get(17, 18), EMPTY_TILE);
+    layer.set(17, 18, 5);
+    assert_eq!(layer.get(17, 18), 5);
+
+    let mut small = TileLayer::new("small", 3, 2);
+    small.fill_from(&[1, 0, 2, 3, 4, 0, 7, 7]);
+    let read: Vec<TileId> = (0..6).map(|i| small.get(i % 3, i / 3)).collect();
+    assert_eq!(read, vec![1, 0, 2, 3, 4, 0]);
+}
+
+#[test]
+fn animations_loop_over_their_frames() {
+    let mut set = tileset();
+    let frames = vec![AnimationFrame { tile: 1, duration: 0.5 }, AnimationFrame { tile: 2, duration: 0.25 }];
+    set.set_animation(1, frames.clone());
+    assert_eq!(set.animation(1), Some(frames.as_slice()));
+    assert_eq!(set.resolve(1, 0.0), 1);
+    assert_eq!(set.resolve(1, 0.6), 2);
+    assert_eq!(set.resolve(1, 0.8), 1);
+    assert_eq!(set.resolve(1, -0.1), 2);
+    // Tiles without an animation show themselves
+    assert_eq!(set.resolve(3, 0.6), 3);
+
+    set.set_animation(1, Vec::new());
+    assert_eq!(set.animation(1), None);
+    assert_eq!(set.resolve(1, 0.6), 1);
+}
+
+#[test]
+fn cell_lookups_clamp_to_the_map() {
+    let map = TileMap::new(tileset(), 10, 10);
+    assert_eq!(map.pixel_size(), Vi2d::new(20, 20));
+    assert_eq!(map.cell_at(Vf2d::new(3.5, -0.5)), Vi2d::new(1, -1));
+    assert_eq!(map.cells_in(&Recti::new(3, 1, 4, 4)), Recti::new(1, 0, 3, 3));
+    assert_eq!(map.cells_in(&Recti::new(16, 16, 20, 20)), Recti::new(8, 8, 2, 2));
+    assert!(map.cells_in(&Recti::new(-5, -5, 4, 4)).is_empty());
+}
+
+#[test]
+fn zero_tile_sizes_are_taken_as_one() {
+    let mut sheet = Sprite::new(4, 4);
+    sheet.set_pixel(1, 0, &yape::RED);
+    let mut map = TileMap::new(TileSet::new(sheet, 0, 0), 10, 10);
+    assert_eq!(map.tileset.tile_size, Vi2d::new(1, 1));
+    assert_eq!(map.tileset.tile_count(), 16);
+    assert_eq!(map.cell_at(Vf2d::new(3.5, -0.5)), Vi2d::new(3, -1));
+    assert_eq!(map.cells_in(&Recti::new(3, 1, 4, 4)), Recti::new(3, 1, 4, 4));
+    let mut engine = YapeEngine::construct_headless(4, 4);
+    let layer = map.add_layer("ground");
+    map.layers[layer].set(2, 1, 2);
+    map.draw(&mut engine, Vf2d::new(0.0, 0.0), &Recti::new(0, 0, 4, 4));
+    assert_eq!(*engine.get_draw_target().get_pixel(2, 1), yape::RED);
+}
+
+#[test]
+fn raycast_stops_at_the_first_solid_cell() {
+    let mut set = tileset();
+    set.set_flags(2, TileFlags::SOLID);
+    let mut map = TileMap::new(set, 10, 4);
+    let layer = map.add_layer("walls");
+    map.layers[layer].set(5, 0, 2);
+    map.layers[layer].set(7, 0, 2);
+
+    let (cell, hit) = map.raycast(Vf2d::new(1.0, 1.0), Vf2d::new(20.0, 0.0)).unwrap();
+    assert_eq!(cell, Vi2d::new(5, 0));
+    assert!((hit.point.x - 10.0).abs() < 1e-4 && hit.point.y == 1.0);
+    assert_eq!(hit.normal, Vf2d::new(-1.0, 0.0));
+    assert!(map.raycast(Vf2d::new(1.0, 1.0), Vf2d::new(5.0, 0.0)).is_none());
+    assert!(map.raycast(Vf2d::new(1.0, 3.0), Vf2d::new(20.0, 0.0)).is_none());
+}
+
+#[test]
+fn draw_scrolls_and_cuts_tiles_to_the_viewport() {
+    let mut map = TileMap::new(tileset(), 10, 10);
+    let layer = map.add_layer("ground");
+    map.layers[layer].fill_from(&[1, 2, 0, 3, 4, 5, 6, 7, 8, 1, 0, 0, 6, 0, 2]);
+    map.layers[layer].set(3, 3, 5);
+    map.layers[layer].set(4, 4, 8);
+
+    for (scroll, drawn, viewport) in [
+        (Vf2d::new(0.0, 0.0), Vi2d::new(0, 0), Recti::new(0, 0, 8, 8)),
+        (Vf2d::new(1.0, 0.0), Vi2d::new(1, 0), Recti::new(0, 0, 8, 8)),
+        // Sub-pixel scroll is dropped
+        (Vf2d::new(3.7, 1.2), Vi2d::new(3, 1), Recti::new(0, 0, 8, 8)),
+        (Vf2d::new(1.0, 1.0), Vi2d::new(1, 1), Recti::new(2, 3, 5, 3)),
+        // Scrolled before the map and partly off screen
+        (Vf2d::new(-3.0, -1.0), Vi2d::new(-3, -1), Recti::new(-2, 1, 8, 20)),
+    ]
+    .iter()
+    {
+        let mut engine = YapeEngine::construct_headless(8, 8);
+        engine.clear(&yape::BLACK);
+        map.draw(&mut engine, *scroll, viewport);
+        // Viewports are clipped to the screen before the scroll is applied
+        let visible = viewport.clip(&Recti::new(0, 0, 8, 8)).unwrap();
+        assert_drawn(&engine, &map.layers[layer], *drawn, &visible);
+    }
+}
+
+#[test]
+fn draw_reaches_tiles_in_far_chunks() {
+    let mut map = TileMap::new(tileset(), 40, 40);
+    let layer = map.add_layer("ground");
+    map.layers[layer].set(33, 1, 3);
+    map.layers[layer].set(34, 35, 4);
+
+    for scroll in [Vi2d::new(64, 0), Vi2d::new(62, 66), Vi2d::new(73, 69)].iter() {
+        let mut engine = YapeEngine::construct_headless(8, 8);
+        engine.clear(&yape::BLACK);
+        let viewport = Recti::new(0, 0, 8, 8);
+        map.draw(&mut engine, scroll.to_f32(), &viewport);
+        assert_drawn(&engine, &map.layers[layer], *scroll, &viewport);
+    }
+}
+
+#[test]
+fn layers_draw_in_order_and_hidden_layers_are_skipped() {
+    let mut set = tileset();
+    set.set_animation(6, vec![AnimationFrame { tile: 6, duration: 0.5 }, AnimationFrame { tile: 7, duration: 0.5 }]);
+    let mut map = TileMap::new(set, 4, 4);
+    let back = map.add_layer("back");
+    let front = map.add_layer("front");
+    map.layers[back].fill_from(&[1; 16]);
+    map.layers[front].set(1, 1, 6);
+    let viewport = Recti::new(0, 0, 8, 8);
+
+    let mut engine = YapeEngine::construct_headless(8, 8);
+    map.draw(&mut engine, Vf2d::new(0.0, 0.0), &viewport);
+    let target = engine.get_draw_target();
+    assert_eq!(*target.get_pixel(0, 0), texel(1, 0, 0));
+    assert_eq!(*target.get_pixel(3, 2), texel(6, 1, 0));
+
+    // Animated tiles follow the map's clock
+    map.update(0.6);
+    map.draw(&mut engine, Vf2d::new(0.0, 0.0), &viewport);
+    assert_eq!(*engine.get_draw_target().get_pixel(3, 2), texel(7, 1, 0));
+
+    map.layers[front].visible = false;
+    map.draw(&mut engine, Vf2d::new(0.0, 0.0), &viewport);
+    assert_eq!(*engine.get_draw_target().get_pixel(3, 2), texel(1, 1, 0));
+
+    // Only the named layer is drawn
+    engine.clear(&yape::BLACK);
+    map.layers[front].visible = true;
+    map.draw_layer(&mut engine, front, Vf2d::new(0.0, 0.0), &viewport);
+    assert_eq!(*engine.get_draw_target().get_pixel(0, 0), yape::BLACK);
+    assert_eq!(*engine.get_draw_target().get_pixel(2, 3), texel(7, 0, 1));
+    map.draw_layer(&mut engine, 5, Vf2d::new(0.0, 0.0), &viewport);
+}
@@ -0,0 +1,138 @@
+use std::f32::consts::FRAC_PI_2;
+
+use yape::transform::{Sampling, Transform2D};
+use yape::{Pixel, Recti, Sprite, SpriteMode, Vf2d, Vi2d, YapeEngine, YapeEngineApi};
+
+fn close(a: Vf2d, b: Vf2d) -> bool {
+    (a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4
+}
+
+// 2x2 sprite with a distinct color per texel
+fn quad() -> Sprite {
+    let mut sprite = Sprite::new(2, 2);
+    sprite.set_pixel(0, 0, &yape::RED);
+    sprite.set_pixel(1, 0, &yape::GREEN);
+    sprite.set_pixel(0, 1, &yape::BLUE);
+    sprite.set_pixel(1, 1, &yape::WHITE);
+    sprite
+}
+
+fn pixel(engine: &YapeEngine, x: u32, y: u32) -> Pixel {
+    *engine.get_draw_target().get_pixel(x, y)
+}
+
+#[test]
+fn builder_calls_apply_in_order() {
+    let t = Transform2D::new().rotate(FRAC_PI_2).translate(10.0, 0.0);
+    assert!(close(t.forward(Vf2d::new(1.0, 0.0)), Vf2d::new(10.0, 1.0)));
+    let t = Transform2D::new().translate(10.0, 0.0).rotate(FRAC_PI_2);
+    assert!(close(t.forward(Vf2d::new(1.0, 0.0)), Vf2d::new(0.0, 11.0)));
+}
+
+#[test]
+fn then_composes_like_forward_twice() {
+    let a = Transform2D::new().scale(2.0, 3.0).shear(0.5, 0.0);
+    let b = Transform2D::new().rotate(0.3).translate(-4.0, 7.0);
+    let p = Vf2d::new(1.5, -2.0);
+    assert!(close(a.then(&b).forward(p), b.forward(a.forward(p))));
+}
+
+#[test]
+fn invert_round_trips() {
+    let t = Transform2D::new().scale(2.0, 0.5).shear(0.25, -0.5).rotate(1.2).translate(3.0, -8.0);
+    let inverse = t.invert().unwrap();
+    for p in [Vf2d::new(0.0, 0.0), Vf2d::new(5.0, -3.0), Vf2d::new(-1.5, 20.0)].iter() {
+        assert!(close(inverse.forward(t.forward(*p)), *p));
+        assert!(close(t.forward(inverse.forward(*p)), *p));
+    }
+    let identity = t.then(&inverse);
+    assert!(close(identity.forward(Vf2d::new(3.0, 4.0)), Vf2d::new(3.0, 4.0)));
+    assert!(Transform2D::new().scale(0.0, 1.0).invert().is_none());
+}
+
+#[test]
+fn translated_sprite_lands_on_whole_pixels() {
+    let mut engine = YapeEngine::construct_headless(8, 8);
+    engine.draw_sprite_transformed(&quad(), &Transform2D::new().translate(3.0, 2.0), Sampling::Nearest);
+    assert_eq!(pixel(&engine, 3, 2), yape::RED);
+    assert_eq!(pixel(&engine, 4, 2), yape::GREEN);
+    assert_eq!(pixel(&engine, 3, 3), yape::BLUE);
+    assert_eq!(pixel(&engine, 4, 3), yape::WHITE);
+    assert_eq!(pixel(&engine, 5, 2), Pixel::default());
+    assert_eq!(pixel(&engine, 2, 2), Pixel::default());
+}
+
+#[test]
+fn rotated_and_scaled_sprites_are_inverse_mapped() {
+    let mut engine = YapeEngine::construct_headless(8, 8);
+    // A quarter turn clockwise around the sprite's top left, moved back on screen
+    let t = Transform2D::new().rotate(FRAC_PI_2).translate(4.0, 0.0);
+    engine.draw_sprite_transformed(&quad(), &t, Sampling::Nearest);
+    assert_eq!(pixel(&engine, 3, 0), yape::RED);
+    assert_eq!(pixel(&engine, 3, 1), yape::GREEN);
+    assert_eq!(pixel(&engine, 2, 0), yape::BLUE);
+    assert_eq!(pixel(&engine, 2, 1), yape::WHITE);
+
+    let mut engine = YapeEngine::construct_headless(8, 8);
+    engine.draw_sprite_transformed(&quad(), &Transform2D::new().scale(2.0, 2.0), Sampling::Nearest);
+    for (x, y, expected) in [(0, 0, yape::RED), (1, 1, yape::RED), (2, 1, yape::GREEN), (3, 3, yape::WHITE)].iter() {
+        assert_eq!(pixel(&engine, *x, *y), *expected);
+    }
+    assert_eq!(pixel(&engine, 4, 0), Pixel::default());
+}
+
+#[test]
+fn bilinear_sampling_blends_between_texels() {
+    let mut sprite = Sprite::new(2, 1);
+    sprite.set_pixel(0, 0, &Pixel::rgb(0, 0, 0));
+    sprite.set_pixel(1, 0, &Pixel::rgb(200, 0, 0));
+    let mut engine = YapeEngine::construct_headless(8, 1);
+    engine.draw_sprite_transformed(&sprite, &Transform2D::new().scale(4.0, 1.0), Sampling::Bilinear);
+    let reds: Vec<u8> = (0..8).map(|x| pixel(&engine, x, 0).red).collect();
+    assert_eq!(reds[0], 0);
+    assert_eq!(reds[7], 200);
+    assert!(reds.windows(2).all(|w| w[0] <= w[1]), "{:?}", reds);
+    assert!(reds[3] > 0 && reds[4] < 200);
+}
+
+#[test]
+fn singular_transforms_draw_nothing() {
+    let mut engine = YapeEngine::construct_headless(4, 4);
+    engine.draw_sprite_transformed(&quad(), &Transform2D::new().scale(0.0, 2.0), Sampling::Nearest);
+    assert!(engine.get_draw_target().data.iter().all(|p| *p == Pixel::default()));
+}
+
+#[test]
+fn partial_sprites_are_clipped_and_scaled() {
+    let mut engine = YapeEngine::construct_headless(4, 4);
+    engine.draw_partial_sprite(Vi2d::new(-1, 0), &quad(), &Recti::new(0, 0, 2, 1), 1);
+    assert_eq!(pixel(&engine, 0, 0), yape::GREEN);
+    assert_eq!(pixel(&engine, 1, 0), Pixel::default());
+
+    engine.draw_partial_sprite(Vi2d::new(0, 2), &quad(), &Recti::new(1, 1, 5, 5), 2);
+    assert_eq!(pixel(&engine, 0, 2), yape::WHITE);
+    assert_eq!(pixel(&engine, 1, 3), yape::WHITE);
+    assert_eq!(pixel(&engine, 2, 2), Pixel::default());
+}
+
+#[test]
+fn sampling_helpers() {
+    let sprite = quad();
+    assert_eq!(*sprite.sample_nearest(0.0, 0.0), yape::RED);
+    assert_eq!(*sprite.sample_nearest(0.75, 0.25), yape::GREEN);
+    assert_eq!(*sprite.sample_nearest(1.0, 1.0), yape::WHITE);
+    assert_eq!(*sprite.sample(0, 0), yape::RED);
+    assert_eq!(*sprite.sample(1, 1), yape::WHITE);
+    assert_eq!(sprite.sample_bilinear(0.0, 0.0), yape::RED);
+
+    // Empty sprites sample as blank in any mode
+    let mut empty = Sprite::new(0, 3);
+    assert_eq!(*empty.sample_nearest(0.5, 0.5), yape::BLANK);
+    empty.mode = SpriteMode::Periodic;
+    assert_eq!(*empty.sample_nearest(1.0, 1.0), yape::BLANK);
+
+    let mut periodic = quad();
+    periodic.mode = SpriteMode::Periodic;
+    assert_eq!(*periodic.get_pixel(3, 2), yape::GREEN);
+    assert_eq!(*periodic.get_pixel(2, 5), yape::BLUE);
+}